@@ -0,0 +1,54 @@
+//! `require("@tachi/core")`: in-process generation for JS tooling.
+
+use napi::{Error, Result};
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tachi::GenerateOptions;
+
+#[napi(object)]
+pub struct GenerateOpts {
+    pub force: Option<bool>,
+    pub templates: Option<String>,
+}
+
+/// Render `yaml` in memory and return `{ [path]: contents }`.
+#[napi]
+pub fn render(yaml: String, templates: Option<String>) -> Result<HashMap<String, String>> {
+    let spec = tachi::parse_spec(&yaml).map_err(to_js)?;
+    let files = tachi::render(&spec, templates.as_deref().map(Path::new)).map_err(to_js)?;
+    Ok(files
+        .into_iter()
+        .map(|f| (f.path, String::from_utf8_lossy(&f.contents).into_owned()))
+        .collect())
+}
+
+/// Write the project for `yaml` to `outDir/<slug>` and return
+/// `{ [path]: "created" | "updated" | "unchanged" | "kept" | "merged" |
+/// "conflict" }`.
+#[napi]
+pub fn generate(
+    yaml: String,
+    out_dir: String,
+    opts: Option<GenerateOpts>,
+) -> Result<HashMap<String, String>> {
+    let opts = opts.unwrap_or(GenerateOpts {
+        force: None,
+        templates: None,
+    });
+    let options = GenerateOptions {
+        force: opts.force.unwrap_or(false),
+        templates: opts.templates.map(PathBuf::from),
+        ..GenerateOptions::default()
+    };
+    let spec = tachi::parse_spec(&yaml).map_err(to_js)?;
+    let written = tachi::generate(&spec, Path::new(&out_dir), &options).map_err(to_js)?;
+    Ok(written
+        .into_iter()
+        .map(|(path, status)| (path, status.label().to_string()))
+        .collect())
+}
+
+fn to_js(err: anyhow::Error) -> Error {
+    Error::from_reason(format!("{err:#}"))
+}
@@ -0,0 +1,60 @@
+//! `import tachi` from Python: the generator without a Rust toolchain.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+use std::path::PathBuf;
+use tachi::GenerateOptions;
+
+create_exception!(tachi, TachiError, PyException);
+
+/// generate(spec, out_dir, force=False, templates=None)
+///
+/// `spec` is either YAML text or a dict shaped like the YAML file. The project
+/// is written to `out_dir/<slug>`; returns {file name: "created" |
+/// "updated" | "unchanged" | "kept" | "merged" | "conflict"}.
+#[pyfunction]
+#[pyo3(signature = (spec, out_dir, force = false, templates = None))]
+fn generate<'py>(
+    py: Python<'py>,
+    spec: &Bound<'py, PyAny>,
+    out_dir: PathBuf,
+    force: bool,
+    templates: Option<PathBuf>,
+) -> PyResult<Bound<'py, PyDict>> {
+    // JSON is valid YAML, so a dict goes through the same parser as a file
+    let source: String = match spec.extract() {
+        Ok(yaml) => yaml,
+        Err(_) => PyModule::import_bound(py, "json")?
+            .call_method1("dumps", (spec,))?
+            .extract()?,
+    };
+
+    let options = GenerateOptions {
+        force,
+        templates,
+        ..GenerateOptions::default()
+    };
+    let written = py
+        .allow_threads(|| {
+            let spec = tachi::parse_spec(&source)?;
+            tachi::generate(&spec, &out_dir, &options)
+        })
+        .map_err(|err| TachiError::new_err(format!("{err:#}")))?;
+
+    let result = PyDict::new_bound(py);
+    for (name, status) in written {
+        result.set_item(name, status.label())?;
+    }
+    Ok(result)
+}
+
+#[pymodule]
+#[pyo3(name = "tachi")]
+fn tachi_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("TachiError", m.py().get_type_bound::<TachiError>())?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! In-browser rendering for the playground: YAML in, project files out.
+//!
+//! Build with `wasm-pack build --target web`.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize)]
+struct File {
+    path: String,
+    contents: String,
+}
+
+/// Render `yaml` and return `[{ path, contents }]`. Parse and validation
+/// errors are thrown with the full cause chain as the message.
+#[wasm_bindgen]
+pub fn render(yaml: &str) -> Result<JsValue, JsError> {
+    let files = tachi::parse_spec(yaml)
+        .and_then(|spec| tachi::render(&spec, None))
+        .map_err(|err| JsError::new(&format!("{err:#}")))?;
+    let files: Vec<File> = files
+        .into_iter()
+        .map(|f| File {
+            path: f.path,
+            contents: String::from_utf8_lossy(&f.contents).into_owned(),
+        })
+        .collect();
+    Ok(serde_wasm_bindgen::to_value(&files)?)
+}
@@ -0,0 +1,105 @@
+use crate::generate::WriteStatus;
+use crate::registry::SpecSource;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// SSH private key `gen --sign` uses without `--signing-key`
+pub const SIGNING_KEY_ENV: &str = "TACHI_SIGNING_KEY";
+/// in-toto statement `gen --sign` writes into the project, next to tachi.lock
+const ATTESTATION_FILE: &str = "tachi.intoto.json";
+/// Namespace of the SSH signature, which `ssh-keygen -Y verify -n` must match
+const SIGNATURE_NAMESPACE: &str = "tachi";
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str =
+    "https://github.com/Ghostlock-AI/agents/tree/main/instant-agents/tachi/provenance/v1";
+
+/// The spec a project was generated from, for its attestation.
+pub struct Provenance<'a> {
+    /// The spec file, or `registry:<ref>` for a pulled spec
+    pub spec_name: &'a str,
+    /// The spec file's contents
+    pub spec: &'a [u8],
+    pub source: Option<&'a SpecSource>,
+}
+
+/// Write an in-toto statement into `project_dir` naming the SHA-256 of every
+/// file `generate` wrote, with the spec's digest and the tachi version as its
+/// predicate, and sign it with the SSH private key `key` into a detached
+/// `.sig` beside it. Files the user kept or that hold conflict markers are not
+/// tachi's output, so they are left out. Returns the statement's path.
+///
+/// Downstream, `ssh-keygen -Y verify -n tachi -f <allowed_signers> -I <signer>
+/// -s tachi.intoto.json.sig < tachi.intoto.json` checks the signature.
+pub fn sign(
+    project_dir: &Path,
+    written: &[(String, WriteStatus)],
+    provenance: &Provenance,
+    key: &Path,
+) -> Result<PathBuf> {
+    let mut subjects = Vec::new();
+    for (name, status) in written {
+        if matches!(status, WriteStatus::Kept | WriteStatus::Conflicted) {
+            continue;
+        }
+        let path = project_dir.join(name);
+        let data = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        subjects.push(json!({ "name": name, "digest": { "sha256": sha256_hex(&data) } }));
+    }
+    let mut spec = json!({
+        "name": provenance.spec_name,
+        "digest": { "sha256": sha256_hex(provenance.spec) },
+    });
+    if let Some(source) = provenance.source {
+        spec["registry"] = json!({
+            "url": source.registry,
+            "name": source.name,
+            "tag": source.tag,
+            "digest": source.digest,
+        });
+    }
+    let statement = json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subjects,
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "generator": { "name": "tachi", "version": env!("CARGO_PKG_VERSION") },
+            "spec": spec,
+        },
+    });
+    let path = project_dir.join(ATTESTATION_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&statement)? + "\n")
+        .with_context(|| format!("writing {}", path.display()))?;
+
+    // ssh-keygen refuses to overwrite an old signature
+    let signature = project_dir.join(format!("{ATTESTATION_FILE}.sig"));
+    match fs::remove_file(&signature) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Err(err).with_context(|| format!("removing {}", signature.display()));
+        }
+        _ => {}
+    }
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(key)
+        .arg(&path)
+        .output()
+        .context("running ssh-keygen, which --sign signs with")?;
+    if !output.status.success() {
+        bail!(
+            "signing {} with {}: {}",
+            path.display(),
+            key.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(path)
+}
+
+/// SHA-256 of `data` as lowercase hex, as `sha256sum` prints it.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
@@ -0,0 +1,144 @@
+use crate::spec::{BundleFormat, CliFramework, Interface, Spec};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files the generated Python reads from its own directory at run time.
+const RUNTIME_DATA: &[&str] = &["planning_prompt.md", "agent_card.json"];
+
+/// Build the spec's bundle from the project generated in `project_dir`:
+/// create `.venv` with `python` unless it is there, install the
+/// requirements and the builder into it, and build into `dist/`. The
+/// commands print to the terminal as they run. Returns the artifact.
+pub fn bundle(spec: &Spec, project_dir: &Path, python: &str) -> Result<PathBuf> {
+    // The commands run in the project, so paths into it must not be relative
+    let project_dir = &fs::canonicalize(project_dir)
+        .with_context(|| format!("reading {}", project_dir.display()))?;
+    let slug = spec.names().slug;
+    let venv = project_dir.join(".venv");
+    if !venv.exists() {
+        run(Command::new(python)
+            .args(["-m", "venv", ".venv"])
+            .current_dir(project_dir))?;
+    }
+    let pip = || {
+        let mut cmd = Command::new(venv_bin(&venv, "python"));
+        cmd.args(["-m", "pip", "install", "--disable-pip-version-check"])
+            .current_dir(project_dir);
+        cmd
+    };
+    let (module, function) = entry_point(spec);
+    let build = project_dir.join("build");
+    let dist = project_dir.join("dist");
+    match spec.bundle.format {
+        BundleFormat::Shiv => {
+            run(pip().arg("shiv"))?;
+            // shiv packs a ready site-packages: the requirements and the project's modules
+            let staging = build.join(&slug);
+            if staging.exists() {
+                fs::remove_dir_all(&staging)
+                    .with_context(|| format!("removing {}", staging.display()))?;
+            }
+            run(pip()
+                .args(["-r", "requirements.txt", "--target"])
+                .arg(&staging))?;
+            copy_app(project_dir, &staging)?;
+            let artifact = dist.join(format!("{slug}.pyz"));
+            run(Command::new(venv_bin(&venv, "shiv"))
+                .arg("--site-packages")
+                .arg(&staging)
+                .arg("--entry-point")
+                .arg(format!("{module}:{function}"))
+                .args(["--python", "/usr/bin/env python3", "--output-file"])
+                .arg(&artifact)
+                .current_dir(project_dir))?;
+            Ok(artifact)
+        }
+        BundleFormat::Pyinstaller => {
+            run(pip().args(["-r", "requirements.txt", "pyinstaller"]))?;
+            let mut cmd = Command::new(venv_bin(&venv, "pyinstaller"));
+            cmd.args(["--onefile", "--noconfirm", "--name", &slug])
+                .arg("--distpath")
+                .arg(&dist)
+                .arg("--workpath")
+                .arg(&build)
+                .arg("--specpath")
+                .arg(&build)
+                // CodeAgent reads its prompts from package data
+                .args(["--collect-data", "smolagents"]);
+            if spec.bundle_entry() == Interface::Gradio {
+                cmd.args([
+                    "--collect-data",
+                    "gradio",
+                    "--collect-data",
+                    "gradio_client",
+                ]);
+            }
+            let separator = if cfg!(windows) { ';' } else { ':' };
+            for data in RUNTIME_DATA {
+                let path = project_dir.join(data);
+                if path.exists() {
+                    cmd.arg("--add-data")
+                        .arg(format!("{}{separator}.", path.display()));
+                }
+            }
+            run(cmd.arg(format!("{module}.py")).current_dir(project_dir))?;
+            let binary = if cfg!(windows) {
+                format!("{slug}.exe")
+            } else {
+                slug
+            };
+            Ok(dist.join(binary))
+        }
+    }
+}
+
+/// Module and callable the bundle starts.
+fn entry_point(spec: &Spec) -> (&'static str, &'static str) {
+    match spec.bundle_entry() {
+        Interface::Cli if spec.cli.framework == CliFramework::Typer => ("cli", "app"),
+        Interface::Cli => ("cli", "main"),
+        Interface::Server => ("server", "main"),
+        Interface::Gradio => ("gradio_app", "main"),
+    }
+}
+
+/// Copy the project's modules and the data they read into `dest`.
+fn copy_app(project_dir: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| format!("creating {}", dest.display()))?;
+    let entries =
+        fs::read_dir(project_dir).with_context(|| format!("reading {}", project_dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if path.is_file() && (name.ends_with(".py") || RUNTIME_DATA.contains(&name)) {
+            fs::copy(&path, dest.join(name))
+                .with_context(|| format!("copying {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn venv_bin(venv: &Path, name: &str) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join(format!("{name}.exe"))
+    } else {
+        venv.join("bin").join(name)
+    }
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    let mut shown = cmd.get_program().to_string_lossy().into_owned();
+    for arg in cmd.get_args() {
+        shown.push(' ');
+        shown.push_str(&arg.to_string_lossy());
+    }
+    let status = cmd.status().with_context(|| format!("running `{shown}`"))?;
+    if !status.success() {
+        bail!("`{shown}` failed ({status})");
+    }
+    Ok(())
+}
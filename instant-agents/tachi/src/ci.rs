@@ -0,0 +1,117 @@
+use crate::generate::PYTHON_VERSION;
+use crate::spec::{CiDocker, Packaging, Spec};
+
+/// GitHub Actions workflow: install, compile and import checks, pytest over
+/// tests/ with its mock model, then the optional image build. `agent_dir`
+/// is the directory agent.py is in, when a template pack moved it out of the
+/// project root. Built from plain strings, since `${{ }}` would clash with
+/// Tera.
+pub(crate) fn render_github(spec: &Spec, agent_dir: Option<&str>) -> String {
+    let names = spec.names();
+    let mut out = String::from(
+        "name: CI\n\
+         \n\
+         on:\n  \
+         push:\n  \
+         pull_request:\n\
+         \n\
+         jobs:\n  \
+         test:\n    \
+         runs-on: ubuntu-latest\n",
+    );
+    match spec.packaging {
+        Packaging::Pip => out.push_str(&format!(
+            "    steps:\n      \
+             - uses: actions/checkout@v4\n      \
+             - uses: actions/setup-python@v5\n        \
+             with:\n          \
+             python-version: \"{PYTHON_VERSION}\"\n          \
+             cache: pip\n      \
+             - name: Install dependencies\n        \
+             run: pip install -r requirements.txt\n"
+        )),
+        // setup-miniconda activates the environment in login shells only
+        Packaging::Conda => out.push_str(&format!(
+            "    defaults:\n      \
+             run:\n        \
+             shell: bash -el {{0}}\n    \
+             steps:\n      \
+             - uses: actions/checkout@v4\n      \
+             - uses: conda-incubator/setup-miniconda@v3\n        \
+             with:\n          \
+             environment-file: environment.yml\n          \
+             activate-environment: {}\n",
+            names.slug
+        )),
+    }
+    out.push_str(
+        "      - name: Check that every module compiles\n        \
+         run: python -m compileall -q .\n      \
+         - name: Import the agent\n",
+    );
+    if let Some(dir) = agent_dir {
+        out.push_str(&format!("        working-directory: {dir}\n"));
+    }
+    out.push_str(
+        "        run: python -c \"import agent\"\n      \
+         - name: Run tests\n        \
+         run: |\n          \
+         pip install pytest\n          \
+         python -m pytest\n",
+    );
+    let Some(docker) = spec.ci.as_ref().and_then(|ci| ci.docker) else {
+        return out;
+    };
+    let push = docker == CiDocker::Push;
+    out.push_str(
+        "\n  \
+         docker:\n    \
+         needs: test\n    \
+         runs-on: ubuntu-latest\n",
+    );
+    if push {
+        out.push_str(
+            "    permissions:\n      \
+             contents: read\n      \
+             packages: write\n",
+        );
+    }
+    out.push_str(
+        "    steps:\n      \
+         - uses: actions/checkout@v4\n      \
+         - uses: docker/setup-buildx-action@v3\n",
+    );
+    if push {
+        // ghcr.io wants a lowercase owner
+        out.push_str(&format!(
+            "      - name: Pick the image name\n        \
+             run: echo \"IMAGE=ghcr.io/${{GITHUB_REPOSITORY_OWNER,,}}/{}\" >> \"$GITHUB_ENV\"\n      \
+             - uses: docker/login-action@v3\n        \
+             if: github.ref == format('refs/heads/{{0}}', github.event.repository.default_branch)\n        \
+             with:\n          \
+             registry: ghcr.io\n          \
+             username: ${{{{ github.actor }}}}\n          \
+             password: ${{{{ secrets.GITHUB_TOKEN }}}}\n",
+            names.image
+        ));
+    }
+    out.push_str(
+        "      - uses: docker/build-push-action@v6\n        \
+         with:\n          \
+         context: .\n          \
+         file: .devcontainer/Dockerfile\n",
+    );
+    if push {
+        out.push_str(
+            "          push: ${{ github.ref == format('refs/heads/{0}', github.event.repository.default_branch) }}\n          \
+             tags: ${{ env.IMAGE }}:latest\n",
+        );
+    } else {
+        out.push_str(&format!(
+            "          push: false\n          \
+             tags: {}:ci\n",
+            names.image
+        ));
+    }
+    out
+}
@@ -0,0 +1,134 @@
+//! `lang: csharp`: a .NET console project running the agent on Semantic
+//! Kernel, each tool a plugin class under `Plugins/`.
+
+use crate::generate::{env_vars, GeneratedFile, Renderer};
+use crate::graph;
+use crate::spec::Spec;
+use anyhow::{Context, Result};
+
+/// Type names the project declares, which its namespace may not repeat.
+const TYPE_NAMES: &[&str] = &["Agent", "Env", "Program", "Plugins"];
+
+/// The project's namespace: `agent.slug` in PascalCase.
+fn namespace(spec: &Spec) -> String {
+    let mut name: String = spec
+        .names()
+        .slug
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().expect("parts are not empty");
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if TYPE_NAMES.contains(&name.as_str()) {
+        name.push_str("App");
+    }
+    name
+}
+
+pub(crate) fn render_files(tera: &Renderer, spec: &Spec) -> Result<Vec<GeneratedFile>> {
+    let mut files = Vec::new();
+    let namespace = namespace(spec);
+    let plugins: Vec<_> = spec
+        .agent
+        .tools
+        .iter()
+        .map(|t| {
+            t.tool
+                .cs_plugin()
+                .expect("validated: every tool has a plugin")
+        })
+        .collect();
+
+    // Generate <Namespace>.csproj
+    let mut ctx = tera::Context::new();
+    ctx.insert("namespace", &namespace);
+    let csproj = tera
+        .render("agent.csproj", &ctx)
+        .context("rendering agent.csproj template")?;
+    files.push(GeneratedFile::new(&format!("{namespace}.csproj"), csproj));
+
+    // Generate Program.cs
+    let banner = format!("{} ({})", spec.names().display, spec.semantic_kernel.model);
+    ctx.insert(
+        "banner",
+        &serde_json::to_string(&banner).expect("strings serialize"),
+    );
+    let program = tera
+        .render("Program.cs", &ctx)
+        .context("rendering Program.cs template")?;
+    files.push(GeneratedFile::new("Program.cs", program));
+
+    // Generate Agent.cs
+    ctx.insert("model_id", &spec.semantic_kernel.model);
+    let instructions = "Answer the request, calling the tools when they help. \
+                        Once you have the answer, reply with it and call no tools.";
+    let system_message = match &spec.agent.description {
+        Some(description) => format!("{description}\n\n{instructions}"),
+        None => instructions.to_string(),
+    };
+    // A JSON string is a valid C# string literal
+    ctx.insert(
+        "system_message",
+        &serde_json::to_string(&system_message).expect("strings serialize"),
+    );
+    ctx.insert("max_steps", &spec.semantic_kernel.max_steps);
+    ctx.insert("plugins", &plugins);
+    let agent = tera
+        .render("Agent.cs", &ctx)
+        .context("rendering Agent.cs template")?;
+    files.push(GeneratedFile::new("Agent.cs", agent));
+
+    // Generate Plugins/
+    let writable = plugins.iter().any(|p| p.class == "WritableFilesPlugin");
+    ctx.insert("writable", &writable);
+    let mut templates: Vec<&str> = plugins.iter().map(|p| p.template).collect();
+    if templates
+        .iter()
+        .any(|t| matches!(*t, "HttpPlugin.cs" | "WebpagePlugin.cs"))
+    {
+        templates.push("Web.cs");
+    }
+    for template in templates {
+        let rendered = tera
+            .render(template, &ctx)
+            .with_context(|| format!("rendering {template} template"))?;
+        files.push(GeneratedFile::new(&format!("Plugins/{template}"), rendered));
+    }
+
+    // Generate README.md
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", &spec.agent.name);
+    ctx.insert("description", &spec.agent.description);
+    ctx.insert("plugins", &plugins);
+    ctx.insert("files", &plugins.iter().any(|p| p.name == "files"));
+    let readme = tera
+        .render("README_csharp.md", &ctx)
+        .context("rendering README_csharp.md template")?;
+    files.push(GeneratedFile::new("README.md", readme));
+
+    // Generate architecture.md
+    if spec.docs.architecture {
+        files.push(GeneratedFile::new(
+            "architecture.md",
+            graph::architecture_md(spec),
+        ));
+    }
+
+    // Generate .env.example
+    let env: Vec<String> = env_vars(spec)
+        .iter()
+        .map(|var| format!("# {}\n{}={}\n", var.comment, var.name, var.example))
+        .collect();
+    files.push(GeneratedFile::new(".env.example", env.join("\n")));
+
+    // Keep build output out of the repository
+    files.push(GeneratedFile::new(".gitignore", "bin/\nobj/\n.env\n"));
+
+    Ok(files)
+}
@@ -0,0 +1,329 @@
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+/// Above this many cells the LCS table is skipped and the changed middle
+/// is shown as one replacement.
+const MAX_TABLE: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// `diff -u` style diff of `old` against `new`, with ANSI colors when
+/// `color` is set. Empty when the texts are equal.
+pub fn unified_diff(path: &str, old: &str, new: &str, color: bool) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = edit_script(&a, &b);
+    if ops.iter().all(|&op| op == Op::Equal) {
+        return String::new();
+    }
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("\x1b[{code}m{text}\x1b[0m\n")
+        } else {
+            format!("{text}\n")
+        }
+    };
+
+    let mut out = paint("1", format!("--- a/{path}")) + &paint("1", format!("+++ b/{path}"));
+    for (start, end) in hunks(&ops) {
+        // Line numbers where the hunk starts in each file
+        let old_start = ops[..start].iter().filter(|&&op| op != Op::Insert).count();
+        let new_start = ops[..start].iter().filter(|&&op| op != Op::Delete).count();
+        let old_len = ops[start..end]
+            .iter()
+            .filter(|&&op| op != Op::Insert)
+            .count();
+        let new_len = ops[start..end]
+            .iter()
+            .filter(|&&op| op != Op::Delete)
+            .count();
+        out += &paint(
+            "36",
+            format!(
+                "@@ -{},{old_len} +{},{new_len} @@",
+                old_start + usize::from(old_len > 0),
+                new_start + usize::from(new_len > 0)
+            ),
+        );
+        let (mut i, mut j) = (old_start, new_start);
+        for &op in &ops[start..end] {
+            match op {
+                Op::Equal => {
+                    out += &format!(" {}\n", a[i]);
+                    i += 1;
+                    j += 1;
+                }
+                Op::Delete => {
+                    out += &paint("31", format!("-{}", a[i]));
+                    i += 1;
+                }
+                Op::Insert => {
+                    out += &paint("32", format!("+{}", b[j]));
+                    j += 1;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `new` with every change from `old` left as a git-style conflict block,
+/// `yours` (old) above `tachi` (new), for the user to resolve by hand.
+pub fn conflict_markers(old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    let mut ops = edit_script(&a, &b).into_iter().peekable();
+    while let Some(op) = ops.next() {
+        if op == Op::Equal {
+            out += a[i];
+            out.push('\n');
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // One block for the whole run of changed lines
+        let (mut yours, mut theirs) = (String::new(), String::new());
+        let mut op = op;
+        loop {
+            match op {
+                Op::Delete => {
+                    yours += &format!("{}\n", a[i]);
+                    i += 1;
+                }
+                Op::Insert => {
+                    theirs += &format!("{}\n", b[j]);
+                    j += 1;
+                }
+                Op::Equal => unreachable!(),
+            }
+            match ops.next_if(|&next| next != Op::Equal) {
+                Some(next) => op = next,
+                None => break,
+            }
+        }
+        out += &format!("<<<<<<< yours\n{yours}=======\n{theirs}>>>>>>> tachi\n");
+    }
+    out
+}
+
+/// Lines `base[start..end]` replaced by `lines` on one side of a merge.
+struct Change<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Each run of changed lines between `base` and `other`.
+fn base_changes<'a>(base: &[&str], other: &[&'a str]) -> Vec<Change<'a>> {
+    let mut changes: Vec<Change> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut in_change = false;
+    for op in edit_script(base, other) {
+        if op == Op::Equal {
+            in_change = false;
+            i += 1;
+            j += 1;
+            continue;
+        }
+        if !in_change {
+            changes.push(Change {
+                start: i,
+                end: i,
+                lines: Vec::new(),
+            });
+            in_change = true;
+        }
+        let change = changes.last_mut().expect("pushed above");
+        if op == Op::Delete {
+            i += 1;
+            change.end = i;
+        } else {
+            change.lines.push(other[j]);
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// `base[start..end]` with `changes` (all inside that range) applied.
+fn apply(base: &[&str], start: usize, end: usize, changes: &[Change]) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for change in changes {
+        for line in &base[pos..change.start] {
+            out += &format!("{line}\n");
+        }
+        for line in &change.lines {
+            out += &format!("{line}\n");
+        }
+        pos = change.end;
+    }
+    for line in &base[pos..end] {
+        out += &format!("{line}\n");
+    }
+    out
+}
+
+/// Three-way merge of `yours` and `theirs`, both edited from `base`.
+/// Changes on one side are taken as they are; overlapping or touching
+/// changes that differ become conflict blocks like [`conflict_markers`].
+/// Returns the merged text and whether it has any conflicts.
+pub fn merge3(base: &str, yours: &str, theirs: &str) -> (String, bool) {
+    let base: Vec<&str> = base.lines().collect();
+    let yours: Vec<&str> = yours.lines().collect();
+    let theirs: Vec<&str> = theirs.lines().collect();
+    let ours = base_changes(&base, &yours);
+    let others = base_changes(&base, &theirs);
+
+    let mut out = String::new();
+    let mut conflicted = false;
+    let (mut pos, mut x, mut y) = (0, 0, 0);
+    while x < ours.len() || y < others.len() {
+        // Start a group at the earliest change, then pull in every change
+        // from either side that reaches it
+        let start = match (ours.get(x), others.get(y)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => unreachable!(),
+        };
+        let (x0, y0) = (x, y);
+        let mut end = start;
+        loop {
+            if let Some(a) = ours.get(x).filter(|a| a.start <= end) {
+                end = end.max(a.end);
+                x += 1;
+            } else if let Some(b) = others.get(y).filter(|b| b.start <= end) {
+                end = end.max(b.end);
+                y += 1;
+            } else {
+                break;
+            }
+        }
+
+        for line in &base[pos..start] {
+            out += &format!("{line}\n");
+        }
+        let mine = apply(&base, start, end, &ours[x0..x]);
+        let tachi = apply(&base, start, end, &others[y0..y]);
+        if y == y0 || mine == tachi {
+            out += &mine;
+        } else if x == x0 {
+            out += &tachi;
+        } else {
+            out += &format!("<<<<<<< yours\n{mine}=======\n{tachi}>>>>>>> tachi\n");
+            conflicted = true;
+        }
+        pos = end;
+    }
+    for line in &base[pos..] {
+        out += &format!("{line}\n");
+    }
+    (out, conflicted)
+}
+
+/// Shortest edit script by longest common subsequence, after trimming the
+/// common prefix and suffix.
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let (n, m) = (a_mid.len(), b_mid.len());
+
+    let mut ops = vec![Op::Equal; prefix];
+    if (n + 1) * (m + 1) > MAX_TABLE {
+        ops.extend(std::iter::repeat_n(Op::Delete, n));
+        ops.extend(std::iter::repeat_n(Op::Insert, m));
+    } else {
+        // lcs[i][j]: LCS length of a_mid[i..] and b_mid[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[at(i, j)] = if a_mid[i] == b_mid[j] {
+                    lcs[at(i + 1, j + 1)] + 1
+                } else {
+                    lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && a_mid[i] == b_mid[j] {
+                ops.push(Op::Equal);
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+                ops.push(Op::Delete);
+                i += 1;
+            } else {
+                ops.push(Op::Insert);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+/// Ranges of `ops` to print: each change with its context, merging
+/// changes whose context overlaps.
+fn hunks(ops: &[Op]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (k, _) in ops.iter().enumerate().filter(|(_, &op)| op != Op::Equal) {
+        let start = k.saturating_sub(CONTEXT);
+        let end = (k + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge3;
+
+    const BASE: &str = "a\nb\nc\nd\ne\n";
+
+    #[test]
+    fn merge3_takes_separate_edits_from_both_sides() {
+        let yours = "a\nB\nc\nd\ne\n";
+        let theirs = "a\nb\nc\nd\nE\n";
+        assert_eq!(
+            merge3(BASE, yours, theirs),
+            ("a\nB\nc\nd\nE\n".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn merge3_marks_adjacent_edits_as_a_conflict() {
+        let yours = "a\nB\nc\nd\ne\n";
+        let theirs = "a\nb\nC\nd\ne\n";
+        assert_eq!(
+            merge3(BASE, yours, theirs),
+            (
+                "a\n<<<<<<< yours\nB\nc\n=======\nb\nC\n>>>>>>> tachi\nd\ne\n".to_string(),
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn merge3_takes_identical_edits_once() {
+        let edited = "a\nB\nc\nd\nE\n";
+        assert_eq!(merge3(BASE, edited, edited), (edited.to_string(), false));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::generate;
+use crate::importer;
+use crate::spec::Spec;
+use crate::templates;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// One way a generated project no longer matches its spec.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Drift {
+    /// agent.py uses a tool the spec doesn't list
+    ToolAdded {
+        tool: String,
+    },
+    /// The spec lists a tool agent.py doesn't use
+    ToolRemoved {
+        tool: String,
+    },
+    ModelChanged {
+        spec: String,
+        code: Option<String>,
+    },
+    InjectionFilter {
+        spec: bool,
+        code: bool,
+    },
+    FileMissing {
+        path: String,
+    },
+    /// The file on disk differs from what the spec renders now
+    FileModified {
+        path: String,
+    },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::ToolAdded { tool } => {
+                write!(f, "tool {tool} is in agent.py but not in the spec")
+            }
+            Drift::ToolRemoved { tool } => {
+                write!(f, "tool {tool} is in the spec but not in agent.py")
+            }
+            Drift::ModelChanged { spec, code } => match code {
+                Some(code) => write!(f, "model is {code} in agent.py, {spec} in the spec"),
+                None => write!(f, "agent.py sets no model_id; the spec uses {spec}"),
+            },
+            Drift::InjectionFilter { spec, code } => write!(
+                f,
+                "injection filter is {} in agent.py, {} in the spec",
+                on_off(*code),
+                on_off(*spec)
+            ),
+            Drift::FileMissing { path } => write!(f, "{path} is missing"),
+            Drift::FileModified { path } => {
+                write!(f, "{path} differs from what the spec generates")
+            }
+        }
+    }
+}
+
+fn on_off(b: bool) -> &'static str {
+    if b {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Compare the project in `project_dir` with `spec`: semantic differences read
+/// back from agent.py first, then every file the spec would generate.
+pub fn diff_spec(spec: &Spec, project_dir: &Path, templates: Option<&Path>) -> Result<Vec<Drift>> {
+    let mut drift = Vec::new();
+
+    let agent_py = project_dir.join("agent.py");
+    if agent_py.exists() {
+        let source = fs::read_to_string(&agent_py)
+            .with_context(|| format!("reading {}", agent_py.display()))?;
+        let scan = importer::scan_agent_py(&source);
+        let expected = spec.tool_classes();
+        for tool in &scan.tools {
+            if !expected.contains(tool) {
+                drift.push(Drift::ToolAdded {
+                    tool: tool.to_string(),
+                });
+            }
+        }
+        for tool in &expected {
+            if !scan.tools.contains(tool) {
+                drift.push(Drift::ToolRemoved {
+                    tool: tool.to_string(),
+                });
+            }
+        }
+        let model_id = spec.model_id();
+        if scan.model_id != Some(model_id) {
+            drift.push(Drift::ModelChanged {
+                spec: model_id.to_string(),
+                code: scan.model_id.map(str::to_string),
+            });
+        }
+        if scan.guarded != spec.wants_injection_filter() {
+            drift.push(Drift::InjectionFilter {
+                spec: spec.wants_injection_filter(),
+                code: scan.guarded,
+            });
+        }
+    }
+
+    let templates = templates::pinned(project_dir, templates)?;
+    for file in generate::render_formatted(spec, templates.as_deref())? {
+        let path = project_dir.join(&file.path);
+        match fs::read(&path) {
+            Ok(existing) if existing == file.contents => {}
+            // Scaffolds are meant to be edited
+            Ok(_) if file.scaffold => {}
+            Ok(_) => drift.push(Drift::FileModified { path: file.path }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                drift.push(Drift::FileMissing { path: file.path })
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading {}", path.display()));
+            }
+        }
+    }
+    Ok(drift)
+}
@@ -0,0 +1,277 @@
+use crate::secrets::Secret;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    Internal,
+    SpecParse,
+    Validation,
+    Io,
+    Conflict,
+    Template,
+    Lint,
+    Drift,
+    Unformatted,
+    Secrets,
+    Verify,
+    TemplateTest,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Internal => 1,
+            ErrorKind::SpecParse => 3,
+            ErrorKind::Validation => 4,
+            ErrorKind::Io => 5,
+            ErrorKind::Conflict => 6,
+            ErrorKind::Template => 7,
+            ErrorKind::Lint => 8,
+            ErrorKind::Drift => 9,
+            ErrorKind::Unformatted => 10,
+            ErrorKind::Secrets => 11,
+            ErrorKind::Verify => 12,
+            ErrorKind::TemplateTest => 13,
+        }
+    }
+
+    /// Classify by the first typed error found walking the chain outward-in.
+    pub fn of(err: &anyhow::Error) -> ErrorKind {
+        for cause in err.chain() {
+            if cause.is::<OverwriteConflict>() {
+                return ErrorKind::Conflict;
+            }
+            if cause.is::<Unformatted>() {
+                return ErrorKind::Unformatted;
+            }
+            if cause.is::<SecretsFound>() {
+                return ErrorKind::Secrets;
+            }
+            if cause.is::<InvalidPython>() {
+                return ErrorKind::Verify;
+            }
+            if cause.is::<TemplateTestFailed>() {
+                return ErrorKind::TemplateTest;
+            }
+            if cause.is::<DriftFound>() {
+                return ErrorKind::Drift;
+            }
+            if cause.is::<LintFailed>() {
+                return ErrorKind::Lint;
+            }
+            if cause.is::<ValidationError>() || cause.is::<InvalidSnippets>() {
+                return ErrorKind::Validation;
+            }
+            if cause.is::<serde_yaml_ng::Error>() || cause.is::<MergeKeyError>() {
+                return ErrorKind::SpecParse;
+            }
+            if cause.is::<tera::Error>() {
+                return ErrorKind::Template;
+            }
+            if cause.is::<std::io::Error>() {
+                return ErrorKind::Io;
+            }
+        }
+        ErrorKind::Internal
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationError {
+    /// Dotted path of the offending spec key, e.g. `agent.name`
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub(crate) fn new(key: &'static str, message: impl Into<String>) -> Self {
+        ValidationError {
+            key,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Python embedded in the spec that does not compile: one message per
+/// snippet, naming the spec line and key.
+#[derive(Debug)]
+pub struct InvalidSnippets(pub(crate) Vec<String>);
+
+impl fmt::Display for InvalidSnippets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} Python snippet(s) in the spec do not compile",
+            self.0.len()
+        )?;
+        for message in &self.0 {
+            write!(f, "\n  {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidSnippets {}
+
+#[derive(Debug)]
+pub struct OverwriteConflict(pub(crate) PathBuf);
+
+impl fmt::Display for OverwriteConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "refusing to overwrite existing file: {} (use --force)",
+            self.0.display()
+        )
+    }
+}
+
+impl std::error::Error for OverwriteConflict {}
+
+/// A spec document that does not deserialize once its `<<` merge keys are
+/// applied. serde only saw the merged text, so `line` is looked up in the
+/// file from the key the message names.
+#[derive(Debug)]
+pub struct MergeKeyError {
+    pub message: String,
+    /// 1-based
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for MergeKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(line) = self.line {
+            write!(f, " at line {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeKeyError {}
+
+/// `tachi lint` found this many findings at or above the failure threshold.
+#[derive(Debug)]
+pub struct LintFailed(pub usize);
+
+impl fmt::Display for LintFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lint finding(s) at or above the --fail-on level",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for LintFailed {}
+
+/// Machine-readable form of an error, shared by `--error-format json` and
+/// `tachi serve`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub kind: ErrorKind,
+    pub code: u8,
+    pub message: String,
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        let kind = ErrorKind::of(err);
+        ErrorReport {
+            kind,
+            code: kind.exit_code(),
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+/// `tachi diff-spec` found this many differences between spec and project.
+#[derive(Debug)]
+pub struct DriftFound(pub usize);
+
+impl fmt::Display for DriftFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "project has drifted from the spec ({} difference(s))",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for DriftFound {}
+
+/// `tachi fmt --check` found this many files that are not canonical.
+#[derive(Debug)]
+pub struct Unformatted(pub usize);
+
+impl fmt::Display for Unformatted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} spec file(s) need formatting (run tachi fmt)", self.0)
+    }
+}
+
+impl std::error::Error for Unformatted {}
+
+/// Rendered output holds values that look like real credentials.
+#[derive(Debug)]
+pub struct SecretsFound(pub(crate) Vec<Secret>);
+
+impl fmt::Display for SecretsFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "refusing to write {} value(s) that look like credentials; move them to .env, or pass --allow-secrets if they belong in the output",
+            self.0.len()
+        )?;
+        for secret in &self.0 {
+            write!(
+                f,
+                "\n  {}:{}: {} {}...",
+                secret.path, secret.line, secret.kind, secret.preview
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SecretsFound {}
+
+/// `gen --verify` found generated Python that does not compile; holds what
+/// the compiler printed.
+#[derive(Debug)]
+pub struct InvalidPython(pub(crate) String);
+
+impl fmt::Display for InvalidPython {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "generated Python does not compile\n{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidPython {}
+
+/// `tachi template test` found this many failures in a template pack.
+#[derive(Debug)]
+pub struct TemplateTestFailed(pub usize);
+
+impl fmt::Display for TemplateTestFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} template pack check(s) failed", self.0)
+    }
+}
+
+impl std::error::Error for TemplateTestFailed {}
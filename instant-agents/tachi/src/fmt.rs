@@ -0,0 +1,421 @@
+use crate::spec::Spec;
+use anyhow::{bail, ensure, Context, Result};
+use serde_yaml_ng::{Mapping, Value};
+
+const INDENT: &str = "  ";
+
+/// Canonical form of a spec file: keys in declaration order, enum values and
+/// scalars in the spelling serde writes, two-space indentation. Comments and
+/// blank lines stay attached to the entry below them, except those above the
+/// first key, which stay at the top. Flow mappings are expanded to block style;
+/// list items that are mappings (tools with options) are kept as written.
+/// Each `---`-separated spec in a file is formatted on its own.
+pub fn format_spec(source: &str) -> Result<String> {
+    let documents = crate::yaml::documents(source);
+    if documents.len() == 1 {
+        return format_document(source);
+    }
+    // Checks the documents against each other as well
+    crate::parse_specs(source)?;
+    let formatted = documents
+        .iter()
+        .enumerate()
+        .map(|(index, document)| {
+            if document.text.lines().all(is_trivia) {
+                return Ok(document.text.to_string());
+            }
+            format_document(document.text).with_context(|| format!("document {}", index + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(formatted.concat())
+}
+
+fn format_document(source: &str) -> Result<String> {
+    let spec = crate::parse_spec(source)?;
+    let canonical = serde_yaml_ng::to_value(&spec).context("serializing spec")?;
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut at = 0;
+    let (mut entries, trailing) = parse_mapping(&lines, &mut at, 0)?;
+    ensure!(at == lines.len(), "line {}: unexpected indentation", at + 1);
+    // Comments above the first key head the file and don't move with it
+    let header = entries
+        .first_mut()
+        .map(|e| std::mem::take(&mut e.trivia))
+        .unwrap_or_default();
+
+    let mut body = String::new();
+    emit_mapping(&mut body, entries, canonical.as_mapping(), 0)?;
+    let mut out = String::new();
+    push_trivia(&mut out, 0, &header);
+    out.push_str(if header.is_empty() {
+        body.trim_start_matches('\n')
+    } else {
+        &body
+    });
+    push_trivia(&mut out, 0, &trailing);
+
+    let reparsed: Spec = serde_yaml_ng::from_str(&out).context("re-reading formatted spec")?;
+    ensure!(
+        serde_yaml_ng::to_value(&reparsed)? == canonical,
+        "formatting changed the meaning of the spec; please report this"
+    );
+    Ok(out)
+}
+
+// ----------------------
+// Parsing
+// ----------------------
+
+/// A `key: ...` line with its leading comments and nested content.
+struct Entry {
+    trivia: Vec<String>,
+    key: String,
+    comment: Option<String>,
+    body: Body,
+}
+
+enum Body {
+    /// Text after the colon: a scalar or a flow collection
+    Inline(String),
+    Mapping(Vec<Entry>),
+    Sequence(Vec<Item>),
+}
+
+/// A `- value` line of a block sequence. Items that are mappings keep their
+/// deeper lines as written, relative to the `-`.
+struct Item {
+    trivia: Vec<String>,
+    value: String,
+    comment: Option<String>,
+    nested: Vec<String>,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_trivia(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---"
+}
+
+/// Comment and blank lines before the next content line, which is left at `at`.
+fn take_trivia(lines: &[&str], at: &mut usize) -> Vec<String> {
+    let mut trivia = Vec::new();
+    while *at < lines.len() && is_trivia(lines[*at]) {
+        let trimmed = lines[*at].trim();
+        trivia.push(trimmed.to_string());
+        *at += 1;
+    }
+    trivia
+}
+
+/// Split `value  # comment` outside of quotes.
+fn split_comment(text: &str) -> (&str, Option<String>) {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => {
+                return (text[..i].trim_end(), Some(text[i..].to_string()));
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    (text.trim_end(), None)
+}
+
+/// Parse mapping entries at `indent` or deeper; stops at the first line that
+/// is less indented. Returns trivia that belongs to whatever comes next.
+fn parse_mapping(
+    lines: &[&str],
+    at: &mut usize,
+    indent: usize,
+) -> Result<(Vec<Entry>, Vec<String>)> {
+    let mut entries = Vec::new();
+    let mut level = None;
+    loop {
+        let start = *at;
+        let trivia = take_trivia(lines, at);
+        let Some(line) = lines.get(*at) else {
+            // Trailing comments belong to the top level
+            if indent > 0 {
+                *at = start;
+                return Ok((entries, Vec::new()));
+            }
+            return Ok((entries, trivia));
+        };
+        let this_indent = indent_of(line);
+        if this_indent < indent || level.is_some_and(|l| this_indent < l) {
+            *at = start;
+            return Ok((entries, Vec::new()));
+        }
+        let level = *level.get_or_insert(this_indent);
+        ensure!(
+            this_indent == level,
+            "line {}: unexpected indentation",
+            *at + 1
+        );
+
+        let content = line.trim_start();
+        ensure!(
+            !content.starts_with('-'),
+            "line {}: expected a key, found a list item",
+            *at + 1
+        );
+        let Some((key, rest)) = content.split_once(':') else {
+            bail!("line {}: expected `key: value`", *at + 1);
+        };
+        let (value, comment) = split_comment(rest.trim_start());
+        ensure!(
+            !value.starts_with(['|', '>', '&', '*', '!']),
+            "line {}: block scalars, anchors and tags are not supported by tachi fmt",
+            *at + 1
+        );
+        *at += 1;
+
+        let body = if !value.is_empty() {
+            Body::Inline(value.to_string())
+        } else {
+            let after = *at;
+            take_trivia(lines, at);
+            let next = lines.get(*at).copied();
+            *at = after;
+            match next {
+                Some(next) if next.trim_start().starts_with('-') && indent_of(next) >= level => {
+                    Body::Sequence(parse_items(lines, at, indent_of(next))?)
+                }
+                Some(next) if indent_of(next) > level => {
+                    // Nested levels never return trivia; it stays for the next entry
+                    let (children, _) = parse_mapping(lines, at, level + 1)?;
+                    Body::Mapping(children)
+                }
+                _ => Body::Inline(String::new()),
+            }
+        };
+        entries.push(Entry {
+            trivia,
+            key: key.trim().to_string(),
+            comment,
+            body,
+        });
+    }
+}
+
+fn parse_items(lines: &[&str], at: &mut usize, indent: usize) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    loop {
+        let start = *at;
+        let trivia = take_trivia(lines, at);
+        let item = lines
+            .get(*at)
+            .filter(|l| indent_of(l) == indent)
+            .and_then(|l| l.trim_start().strip_prefix('-'));
+        let Some(item) = item else {
+            *at = start;
+            return Ok(items);
+        };
+        let (value, comment) = split_comment(item.trim_start());
+        *at += 1;
+        let mut nested = Vec::new();
+        loop {
+            let after = *at;
+            take_trivia(lines, at);
+            match lines.get(*at) {
+                Some(line) if indent_of(line) > indent => {
+                    *at += 1;
+                    nested.extend(lines[after..*at].iter().map(|l| {
+                        if indent_of(l) >= indent {
+                            l[indent..].trim_end().to_string()
+                        } else {
+                            l.trim().to_string()
+                        }
+                    }));
+                }
+                _ => {
+                    *at = after;
+                    break;
+                }
+            }
+        }
+        items.push(Item {
+            trivia,
+            value: value.to_string(),
+            comment,
+            nested,
+        });
+    }
+}
+
+// ----------------------
+// Emitting
+// ----------------------
+
+fn push_line(out: &mut String, depth: usize, text: &str, comment: Option<&str>) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(text);
+    if let Some(comment) = comment {
+        out.push(' ');
+        out.push_str(comment);
+    }
+    out.push('\n');
+}
+
+fn push_trivia(out: &mut String, depth: usize, trivia: &[String]) {
+    for line in trivia {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            push_line(out, depth, line, None);
+        }
+    }
+}
+
+fn scalar(value: &Value) -> Result<String> {
+    Ok(serde_yaml_ng::to_string(value)?.trim_end().to_string())
+}
+
+fn flow_sequence(items: &[Value]) -> Result<String> {
+    let items: Vec<String> = items.iter().map(scalar).collect::<Result<_>>()?;
+    Ok(format!("[{}]", items.join(", ")))
+}
+
+/// Write `entries` in the key order of `canonical`. Keys serde doesn't know
+/// go last, untouched.
+fn emit_mapping(
+    out: &mut String,
+    mut entries: Vec<Entry>,
+    canonical: Option<&Mapping>,
+    depth: usize,
+) -> Result<()> {
+    let rank = |key: &str| {
+        canonical
+            .and_then(|m| m.keys().position(|k| k.as_str() == Some(key)))
+            .unwrap_or(usize::MAX)
+    };
+    entries.sort_by_key(|e| rank(&e.key));
+
+    for entry in entries {
+        push_trivia(out, depth, &entry.trivia);
+        let comment = entry.comment.as_deref();
+        let value = canonical.and_then(|m| m.get(entry.key.as_str()));
+        match (entry.body, value) {
+            (Body::Mapping(children), Some(Value::Mapping(m))) => {
+                push_line(out, depth, &format!("{}:", entry.key), comment);
+                emit_mapping(out, children, Some(m), depth + 1)?;
+            }
+            (Body::Inline(flow), Some(Value::Mapping(m))) if flow.starts_with('{') => {
+                push_line(out, depth, &format!("{}:", entry.key), comment);
+                let present: Mapping =
+                    serde_yaml_ng::from_str(&flow).context("parsing flow mapping")?;
+                emit_mapping(out, synthesize(&present), Some(m), depth + 1)?;
+            }
+            (Body::Sequence(items), Some(Value::Sequence(values))) => {
+                push_line(out, depth, &format!("{}:", entry.key), comment);
+                ensure!(
+                    items.len() == values.len(),
+                    "list {} changed length",
+                    entry.key
+                );
+                for (item, value) in items.into_iter().zip(values) {
+                    if value.is_mapping() {
+                        emit_item(out, item, depth + 1);
+                        continue;
+                    }
+                    push_trivia(out, depth + 1, &item.trivia);
+                    push_line(
+                        out,
+                        depth + 1,
+                        &format!("- {}", scalar(value)?),
+                        item.comment.as_deref(),
+                    );
+                }
+            }
+            (Body::Inline(_), Some(Value::Sequence(values)))
+                if !values.iter().any(Value::is_mapping) =>
+            {
+                push_line(
+                    out,
+                    depth,
+                    &format!("{}: {}", entry.key, flow_sequence(values)?),
+                    comment,
+                );
+            }
+            (Body::Inline(_), Some(value)) if !value.is_mapping() && !value.is_sequence() => {
+                push_line(
+                    out,
+                    depth,
+                    &format!("{}: {}", entry.key, scalar(value)?),
+                    comment,
+                );
+            }
+            (body, _) => emit_verbatim(out, entry.key, body, comment, depth),
+        }
+    }
+    Ok(())
+}
+
+/// Entries for the keys present in a flow mapping, so it can be expanded.
+fn synthesize(present: &Mapping) -> Vec<Entry> {
+    present
+        .iter()
+        .map(|(key, value)| Entry {
+            trivia: Vec::new(),
+            key: key.as_str().unwrap_or_default().to_string(),
+            comment: None,
+            body: match value {
+                Value::Mapping(m) => Body::Mapping(synthesize(m)),
+                _ => Body::Inline(String::new()),
+            },
+        })
+        .collect()
+}
+
+fn emit_verbatim(out: &mut String, key: String, body: Body, comment: Option<&str>, depth: usize) {
+    match body {
+        Body::Inline(value) if value.is_empty() => {
+            push_line(out, depth, &format!("{key}:"), comment)
+        }
+        Body::Inline(value) => push_line(out, depth, &format!("{key}: {value}"), comment),
+        Body::Mapping(children) => {
+            push_line(out, depth, &format!("{key}:"), comment);
+            for child in children {
+                push_trivia(out, depth + 1, &child.trivia);
+                emit_verbatim(
+                    out,
+                    child.key,
+                    child.body,
+                    child.comment.as_deref(),
+                    depth + 1,
+                );
+            }
+        }
+        Body::Sequence(items) => {
+            push_line(out, depth, &format!("{key}:"), comment);
+            for item in items {
+                emit_item(out, item, depth + 1);
+            }
+        }
+    }
+}
+
+fn emit_item(out: &mut String, item: Item, depth: usize) {
+    push_trivia(out, depth, &item.trivia);
+    push_line(
+        out,
+        depth,
+        &format!("- {}", item.value),
+        item.comment.as_deref(),
+    );
+    for line in &item.nested {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            push_line(out, depth, line, None);
+        }
+    }
+}
@@ -0,0 +1,1817 @@
+use crate::ci;
+use crate::csharp;
+use crate::diff;
+use crate::error::{OverwriteConflict, SecretsFound};
+use crate::golang;
+use crate::graph;
+use crate::java;
+use crate::lock;
+use crate::mount::{self, Mount};
+use crate::pack;
+use crate::project;
+use crate::quality;
+use crate::registry::SpecSource;
+use crate::secrets;
+use crate::spec::{
+    CacheBackend, Capability, CiProvider, CliFramework, CliInput, CliUi, Framework, Interface,
+    Lang, Packaging, ResponseFormat, Router, ServerAuth, Spec, Tool, ToolKind, WorkerBackend,
+};
+use crate::tasks;
+use crate::templates;
+use crate::tools::EnvVar;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tera::Tera;
+
+/// Python the generated containers and environments run on.
+pub(crate) const PYTHON_VERSION: &str = "3.12";
+/// Default port of server.py
+const SERVER_PORT: u16 = 8080;
+/// Default port of gradio_app.py, Gradio's own default
+const GRADIO_PORT: u16 = 7860;
+
+#[derive(Debug, Default, Clone)]
+pub struct GenerateOptions {
+    /// Overwrite files whose content differs from the rendered output
+    pub force: bool,
+    /// Directory of template overrides
+    pub templates: Option<PathBuf>,
+    /// Fail before writing anything if tachi.lock would change
+    pub locked: bool,
+    /// What to do with a modified file, by path, instead of failing
+    pub resolve: HashMap<String, Resolution>,
+    /// Write values that look like real credentials instead of failing
+    pub allow_secrets: bool,
+    /// Registry the spec was pulled from, recorded in tachi.lock
+    pub source: Option<SpecSource>,
+    /// Write into the output directory itself, an existing project, rather
+    /// than a new `<slug>` directory in it
+    pub in_place: bool,
+    /// Serve the agent from this existing web app instead of server.py
+    pub mount: Option<Mount>,
+}
+
+/// How to handle one existing file that differs from the rendered output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Overwrite,
+    /// Leave the file as it is
+    Keep,
+    /// Merge with the user's edits, with conflict markers where they clash
+    /// (around every difference when there is no base in `.tachi/base`)
+    Merge,
+}
+
+/// One rendered project file; `path` is relative to the project directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GeneratedFile {
+    pub path: String,
+    pub contents: Vec<u8>,
+    /// Written once for the user to edit; an existing copy is never replaced
+    pub scaffold: bool,
+    /// The project's own file with tachi's additions, written over it
+    /// without asking
+    pub existing: bool,
+    /// Copied from the template pack as it is, so never formatted or merged
+    pub asset: bool,
+}
+
+impl GeneratedFile {
+    pub(crate) fn new(path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        GeneratedFile {
+            path: path.to_string(),
+            contents: contents.into(),
+            scaffold: false,
+            existing: false,
+            asset: false,
+        }
+    }
+
+    pub(crate) fn scaffold(path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        GeneratedFile {
+            scaffold: true,
+            ..GeneratedFile::new(path, contents)
+        }
+    }
+
+    pub(crate) fn existing(path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        GeneratedFile {
+            existing: true,
+            ..GeneratedFile::new(path, contents)
+        }
+    }
+
+    pub(crate) fn asset(path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        GeneratedFile {
+            asset: true,
+            ..GeneratedFile::new(path, contents)
+        }
+    }
+}
+
+/// The template set, remembering the context each template was rendered
+/// with so `tachi explain` can show it.
+pub(crate) struct Renderer {
+    tera: Tera,
+    /// The template pack, if any
+    templates: Option<PathBuf>,
+    contexts: RefCell<Vec<(String, serde_json::Value)>>,
+}
+
+impl Renderer {
+    fn new(templates: Option<&Path>) -> Result<Self> {
+        Ok(Renderer {
+            tera: templates::environment(templates)?,
+            templates: templates.map(Path::to_path_buf),
+            contexts: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// The tool classes the template pack hands to the agent.
+    pub(crate) fn pack_tools(&self) -> Result<Vec<pack::PackTool>> {
+        match &self.templates {
+            Some(dir) => pack::tools(dir),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Where the template pack puts built-in file `name` for `spec`.
+    pub(crate) fn placed(&self, spec: &Spec, name: &str) -> Result<String> {
+        pack::placed(spec, self.templates.as_deref(), name)
+    }
+
+    pub(crate) fn render(&self, name: &str, ctx: &tera::Context) -> tera::Result<String> {
+        self.contexts
+            .borrow_mut()
+            .push((name.to_string(), ctx.clone().into_json()));
+        self.tera.render(name, ctx)
+    }
+}
+
+/// Render every file for `spec` in memory. Without template overrides this
+/// touches no file system, so it also runs on wasm32.
+pub fn render(spec: &Spec, templates: Option<&Path>) -> Result<Vec<GeneratedFile>> {
+    render_pack(&Renderer::new(templates)?, spec, templates)
+}
+
+/// The context each template is rendered with for `spec`, in render order.
+pub fn template_contexts(
+    spec: &Spec,
+    templates: Option<&Path>,
+) -> Result<Vec<(String, serde_json::Value)>> {
+    let renderer = Renderer::new(templates)?;
+    render_pack(&renderer, spec, templates)?;
+    Ok(renderer.contexts.into_inner())
+}
+
+/// Every file for `spec`, placed where the template pack's manifest says.
+fn render_pack(
+    tera: &Renderer,
+    spec: &Spec,
+    templates: Option<&Path>,
+) -> Result<Vec<GeneratedFile>> {
+    let mut files = render_files(tera, spec)?;
+    if let Some(dir) = templates {
+        pack::apply(tera, spec, dir, &mut files)?;
+    }
+    Ok(files)
+}
+
+fn render_files(tera: &Renderer, spec: &Spec) -> Result<Vec<GeneratedFile>> {
+    let mut files = match spec.lang {
+        Lang::Python => render_python_files(tera, spec)?,
+        Lang::Csharp => csharp::render_files(tera, spec)?,
+        Lang::Go => golang::render_files(tera, spec)?,
+        Lang::Java => java::render_files(tera, spec)?,
+    };
+
+    // Generate LICENSE
+    if let (Some(license), Some(copyright)) = (spec.license, &spec.copyright) {
+        let mut ctx = tera::Context::new();
+        ctx.insert("license", license.name());
+        ctx.insert("copyright", copyright);
+        let text = tera
+            .render("LICENSE", &ctx)
+            .context("rendering LICENSE template")?;
+        files.push(GeneratedFile::new("LICENSE", text));
+    }
+
+    // Copyright header on every source file
+    if let Some(copyright) = &spec.copyright {
+        for (extension, comment) in [(".py", "#"), (".cs", "//"), (".go", "//"), (".java", "//")] {
+            let mut header = format!("{comment} Copyright (c) {copyright}\n");
+            if let Some(license) = spec.license {
+                header.push_str(&format!(
+                    "{comment} SPDX-License-Identifier: {}\n",
+                    license.spdx()
+                ));
+            }
+            for file in files.iter_mut().filter(|f| f.path.ends_with(extension)) {
+                prepend_header(&mut file.contents, &header);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn render_python_files(tera: &Renderer, spec: &Spec) -> Result<Vec<GeneratedFile>> {
+    let mut files = Vec::new();
+    let mut when = pack::Conditions::builtin(spec)?;
+
+    // Generate agent.py
+    let agent_py = match spec.framework {
+        Framework::Smolagents => render_agent_py(tera, spec)?,
+        Framework::OpenaiAssistants => render_openai_agent_py(tera, spec)?,
+        Framework::Autogen => render_autogen_agent_py(tera, spec)?,
+        Framework::Llamaindex => render_llamaindex_agent_py(tera, spec)?,
+        Framework::SemanticKernel => unreachable!("validated: semantic-kernel generates C#"),
+        Framework::SpringAi => unreachable!("validated: spring-ai generates Java"),
+    };
+    files.push(GeneratedFile::new("agent.py", agent_py));
+
+    // Generate cli.py
+    if when.holds("cli.py")? {
+        let cli_py = render_cli_py(tera, spec)?;
+        files.push(GeneratedFile::new("cli.py", cli_py));
+    }
+
+    // Generate server.py
+    if when.holds("server.py")? {
+        let mut ctx = tera::Context::new();
+        ctx.insert("agent_name", &spec.agent.name);
+        ctx.insert("port", &SERVER_PORT);
+        ctx.insert("approval", &spec.approval.is_some());
+        let server = &spec.server;
+        ctx.insert("api_key", &(server.auth == ServerAuth::ApiKey));
+        ctx.insert("rate_limit", &server.rate_limit);
+        ctx.insert("max_concurrent_runs", &server.max_concurrent_runs);
+        ctx.insert("timeout", &server.timeout);
+        ctx.insert("streaming", &server.streaming);
+        let server_py = tera
+            .render("server.py", &ctx)
+            .context("rendering server.py template")?;
+        files.push(GeneratedFile::new("server.py", server_py));
+        if when.holds("chat.html")? {
+            let chat_html = tera
+                .render("chat.html", &ctx)
+                .context("rendering chat.html template")?;
+            files.push(GeneratedFile::new("chat.html", chat_html));
+        }
+        ctx.insert("name", &spec.agent.name);
+        ctx.insert(
+            "description",
+            &spec.agent.description.clone().unwrap_or_else(|| {
+                format!("smolagents CodeAgent on {}", spec.agent.model.model_id())
+            }),
+        );
+        let (response_json, response_schema) = match &spec.agent.response_format {
+            None => (false, String::new()),
+            Some(ResponseFormat::Json(_)) => (true, String::new()),
+            // One line of JSON is also one line of YAML flow style
+            Some(ResponseFormat::JsonSchema { json_schema }) => (
+                true,
+                serde_json::to_string(json_schema)
+                    .context("converting agent.response-format json-schema to JSON")?,
+            ),
+        };
+        ctx.insert("response_json", &response_json);
+        ctx.insert("response_schema", &response_schema);
+        if when.holds("openapi.yaml")? {
+            let openapi = tera
+                .render("openapi.yaml", &ctx)
+                .context("rendering openapi.yaml template")?;
+            files.push(GeneratedFile::new("openapi.yaml", openapi));
+        }
+    }
+
+    // Generate gradio_app.py
+    if when.holds("gradio_app.py")? {
+        let mut ctx = tera::Context::new();
+        ctx.insert("port", &GRADIO_PORT);
+        let gradio_py = tera
+            .render("gradio_app.py", &ctx)
+            .context("rendering gradio_app.py template")?;
+        files.push(GeneratedFile::new("gradio_app.py", gradio_py));
+    }
+
+    // Generate README.md
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", &spec.agent.name);
+    ctx.insert("description", &spec.agent.description);
+    ctx.insert("slug", &spec.names().slug);
+    ctx.insert("conda", &(spec.packaging == Packaging::Conda));
+    ctx.insert("typer", &(spec.cli.framework == CliFramework::Typer));
+    let interfaces: Vec<&str> = spec.interfaces.iter().map(|i| i.name()).collect();
+    ctx.insert("interfaces", &interfaces);
+    ctx.insert("server_port", &SERVER_PORT);
+    ctx.insert("api_key", &(spec.server.auth == ServerAuth::ApiKey));
+    ctx.insert("streaming", &spec.server.streaming);
+    ctx.insert("gradio_port", &GRADIO_PORT);
+    let environments: Vec<&String> = spec.environments.keys().collect();
+    ctx.insert("environments", &environments);
+    ctx.insert("default_environment", spec.default_environment());
+    let readme = tera
+        .render("README.md", &ctx)
+        .context("rendering README.md template")?;
+    files.push(GeneratedFile::new("README.md", readme));
+
+    // Generate tools.py
+    if when.holds("tools.py")? {
+        let mut ctx = tera::Context::new();
+        for kind in ToolKind::ALL {
+            ctx.insert(kind.name().replace('-', "_"), &spec.has_tool(*kind));
+        }
+        ctx.insert("classes", &spec.tool_classes());
+        let search = spec.agent.tools.iter().find_map(|t| match &t.tool {
+            Tool::Search(search) if !t.ships_with_smolagents() => Some(search.provider.name()),
+            _ => None,
+        });
+        ctx.insert("search_provider", &search.unwrap_or_default());
+        let render_js = spec
+            .agent
+            .tools
+            .iter()
+            .any(|t| matches!(&t.tool, Tool::Webpage(webpage) if webpage.render_js));
+        ctx.insert("render_js", &render_js);
+        let tools_py = tera
+            .render("tools.py", &ctx)
+            .context("rendering tools.py template")?;
+        files.push(GeneratedFile::new("tools.py", tools_py));
+    }
+
+    // Keep the directories tools save into
+    for dir in spec.agent.tools.iter().filter_map(|t| t.output_dir()) {
+        files.push(GeneratedFile::new(&format!("{dir}/.gitkeep"), ""));
+    }
+
+    // Generate replay.py
+    if when.holds("replay.py")? {
+        let replay_py = tera
+            .render("replay.py", &tera::Context::new())
+            .context("rendering replay.py template")?;
+        files.push(GeneratedFile::new("replay.py", replay_py));
+    }
+
+    // Generate guardrails.py
+    if when.holds("guardrails.py")? {
+        let guardrails_py = tera
+            .render("guardrails.py", &tera::Context::new())
+            .context("rendering guardrails.py template")?;
+        files.push(GeneratedFile::new("guardrails.py", guardrails_py));
+    }
+
+    // Generate voice.py
+    if when.holds("voice.py")? {
+        let voice = &spec.voice;
+        // Python literals: a quoted string, or None for unset
+        let literal = |value: &Option<String>| match value {
+            Some(s) => serde_json::to_string(s).expect("strings serialize"),
+            None => "None".to_string(),
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("whisper_model", voice.whisper_model.name());
+        ctx.insert("language", &literal(&voice.language));
+        ctx.insert("input_device", &literal(&voice.input_device));
+        ctx.insert("output_device", &literal(&voice.output_device));
+        ctx.insert("tts", &voice.tts);
+        let voice_py = tera
+            .render("voice.py", &ctx)
+            .context("rendering voice.py template")?;
+        files.push(GeneratedFile::new("voice.py", voice_py));
+    }
+
+    // Generate ingest.py
+    if when.holds("ingest.py")? {
+        let knowledge = spec.knowledge.as_ref().expect("ingest.py needs knowledge");
+        let literal = |s: &str| serde_json::to_string(s).expect("strings serialize");
+        let mut ctx = tera::Context::new();
+        ctx.insert("path", &literal(knowledge.path.trim_end_matches('/')));
+        ctx.insert("index", &literal(knowledge.index.trim_end_matches('/')));
+        ctx.insert("embedding_model", &knowledge.embedding_model);
+        let ingest_py = tera
+            .render("ingest.py", &ctx)
+            .context("rendering ingest.py template")?;
+        files.push(GeneratedFile::new("ingest.py", ingest_py));
+    }
+
+    // Generate memory.py
+    if when.holds("memory.py")? {
+        let memory = spec.memory.as_ref().expect("memory.py needs memory");
+        let mut ctx = tera::Context::new();
+        ctx.insert("embedding_model", &memory.embedding_model);
+        let path = serde_json::to_string(&memory.path).expect("strings serialize");
+        ctx.insert("path", &path);
+        ctx.insert("top_k", &memory.top_k);
+        // Python literals: a number, or None for unset
+        let literal = |value: Option<u32>| value.map_or("None".to_string(), |n| n.to_string());
+        ctx.insert("retention_days", &literal(memory.retention_days));
+        ctx.insert("max_entries", &literal(memory.max_entries));
+        let memory_py = tera
+            .render("memory.py", &ctx)
+            .context("rendering memory.py template")?;
+        files.push(GeneratedFile::new("memory.py", memory_py));
+    }
+
+    // Generate approval.py
+    if when.holds("approval.py")? {
+        let approval = spec.approval.as_ref().expect("approval.py needs approval");
+        let mut ctx = tera::Context::new();
+        ctx.insert("timeout", &approval.timeout);
+        let approval_py = tera
+            .render("approval.py", &ctx)
+            .context("rendering approval.py template")?;
+        files.push(GeneratedFile::new("approval.py", approval_py));
+    }
+
+    // Generate response_format.py
+    if when.holds("response_format.py")? {
+        let format = spec
+            .agent
+            .response_format
+            .as_ref()
+            .expect("response_format.py needs agent.response-format");
+        let schema = match format {
+            ResponseFormat::Json(_) => String::new(),
+            ResponseFormat::JsonSchema { json_schema } => serde_json::to_string_pretty(json_schema)
+                .context("converting agent.response-format json-schema to JSON")?,
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("schema", &schema);
+        let response_format_py = tera
+            .render("response_format.py", &ctx)
+            .context("rendering response_format.py template")?;
+        files.push(GeneratedFile::new("response_format.py", response_format_py));
+    }
+
+    // Generate cache.py
+    if when.holds("cache.py")? {
+        let cache = spec.cache.as_ref().expect("cache.py needs cache");
+        let mut ctx = tera::Context::new();
+        ctx.insert("redis", &(cache.backend == CacheBackend::Redis));
+        ctx.insert("ttl", &cache.ttl);
+        let path =
+            serde_json::to_string(cache.path.trim_end_matches('/')).expect("strings serialize");
+        ctx.insert("path", &path);
+        let cache_py = tera
+            .render("cache.py", &ctx)
+            .context("rendering cache.py template")?;
+        files.push(GeneratedFile::new("cache.py", cache_py));
+    }
+
+    // Generate limits.py
+    if when.holds("limits.py")? {
+        let limits = spec.limits.as_ref().expect("limits.py needs limits");
+        // Python literals: a number, or None for unset
+        let literal = |value: Option<f64>| value.map_or("None".to_string(), |n| n.to_string());
+        let mut ctx = tera::Context::new();
+        ctx.insert(
+            "max_tokens_per_run",
+            &literal(limits.max_tokens_per_run.map(|n| n as f64)),
+        );
+        ctx.insert(
+            "max_seconds_per_run",
+            &literal(limits.max_seconds_per_run.map(f64::from)),
+        );
+        ctx.insert("max_dollars_per_day", &literal(limits.max_dollars_per_day));
+        ctx.insert(
+            "dollars_per_million_tokens",
+            &literal(limits.dollars_per_million_tokens),
+        );
+        let limits_py = tera
+            .render("limits.py", &ctx)
+            .context("rendering limits.py template")?;
+        files.push(GeneratedFile::new("limits.py", limits_py));
+    }
+
+    // Generate hooks.py
+    if when.holds("hooks.py")? {
+        let bodies = spec.hooks.bodies();
+        let mut ctx = tera::Context::new();
+        ctx.insert("from_spec", &!bodies.is_empty());
+        for (_, name, body) in &bodies {
+            ctx.insert(*name, &indent_body(body));
+        }
+        let hooks_py = tera
+            .render("hooks.py", &ctx)
+            .context("rendering hooks.py template")?;
+        // Hooks written in the spec make the spec the file's owner
+        files.push(if bodies.is_empty() {
+            GeneratedFile::scaffold("hooks.py", hooks_py)
+        } else {
+            GeneratedFile::new("hooks.py", hooks_py)
+        });
+    }
+
+    // Generate planning_prompt.md
+    if when.holds("planning_prompt.md")? {
+        let prompt = tera
+            .render("planning_prompt.md", &tera::Context::new())
+            .context("rendering planning_prompt.md template")?;
+        files.push(GeneratedFile::scaffold("planning_prompt.md", prompt));
+    }
+
+    // Generate a2a_server.py and agent_card.json
+    if when.holds("a2a_server.py")? {
+        let mut ctx = tera::Context::new();
+        ctx.insert("port", &spec.a2a.port);
+        ctx.insert("approval", &spec.approval.is_some());
+        let server_py = tera
+            .render("a2a_server.py", &ctx)
+            .context("rendering a2a_server.py template")?;
+        files.push(GeneratedFile::new("a2a_server.py", server_py));
+        files.push(GeneratedFile::new(
+            "agent_card.json",
+            render_agent_card(spec)?,
+        ));
+    }
+
+    // Generate health.py for the servers' /readyz
+    if when.holds("health.py")? {
+        let mut ctx = tera::Context::new();
+        ctx.insert("server", &spec.has_interface(Interface::Server));
+        ctx.insert("a2a", &spec.a2a.enabled);
+        let (check_url, token_variable) = model_check(spec);
+        ctx.insert("check_url", check_url);
+        ctx.insert("token_variable", token_variable);
+        ctx.insert(
+            "model_endpoint",
+            &spec
+                .environments
+                .values()
+                .any(|e| e.model_endpoint.is_some()),
+        );
+        let health_py = tera
+            .render("health.py", &ctx)
+            .context("rendering health.py template")?;
+        files.push(GeneratedFile::new("health.py", health_py));
+    }
+
+    // Generate batch.py and its timer
+    if when.holds("batch.py")? {
+        let batch = &spec.batch;
+        let literal = |s: &str| serde_json::to_string(s).expect("strings serialize");
+        let mut ctx = tera::Context::new();
+        ctx.insert("input", &literal(&batch.input));
+        ctx.insert("output", &literal(&batch.output));
+        ctx.insert("retries", &batch.retries);
+        let batch_py = tera
+            .render("batch.py", &ctx)
+            .context("rendering batch.py template")?;
+        files.push(GeneratedFile::new("batch.py", batch_py));
+    }
+    if when.holds("batch.cron")? {
+        files.push(GeneratedFile::new("batch.cron", render_batch_cron(spec)));
+    }
+    if when.holds("batch.service")? {
+        let slug = spec.names().slug;
+        let (service, timer) = render_batch_units(spec);
+        files.push(GeneratedFile::new(
+            &format!("{slug}-batch.service"),
+            service,
+        ));
+        files.push(GeneratedFile::new(&format!("{slug}-batch.timer"), timer));
+    }
+
+    // Generate worker.py and docker-compose.yml
+    if when.holds("worker.py")? {
+        let mut ctx = tera::Context::new();
+        ctx.insert("celery", &(spec.worker.backend == WorkerBackend::Celery));
+        ctx.insert("queue", &spec.worker.queue);
+        ctx.insert("timeout", &spec.worker.timeout);
+        ctx.insert("app_name", &spec.names().package);
+        ctx.insert("python_version", PYTHON_VERSION);
+        for name in ["worker.py", "docker-compose.yml"] {
+            let rendered = tera
+                .render(name, &ctx)
+                .with_context(|| format!("rendering {name} template"))?;
+            files.push(GeneratedFile::new(name, rendered));
+        }
+    }
+
+    // Generate pipeline.py
+    if when.holds("pipeline.py")? {
+        let pipeline = spec.pipeline.as_ref().expect("pipeline.py needs pipeline");
+        let literal = |s: &str| serde_json::to_string(s).expect("strings serialize");
+        let steps: Vec<serde_json::Value> = pipeline
+            .steps
+            .iter()
+            .map(|step| {
+                let args: Vec<(String, String)> = step
+                    .args
+                    .iter()
+                    .map(|(key, value)| (literal(key), literal(value)))
+                    .collect();
+                serde_json::json!({
+                    "name": step.name,
+                    "agent": step.agent.as_deref().map(literal),
+                    "tool": step.tool,
+                    "args": args,
+                })
+            })
+            .collect();
+        let mut ctx = tera::Context::new();
+        ctx.insert("steps", &steps);
+        let pipeline_py = tera
+            .render("pipeline.py", &ctx)
+            .context("rendering pipeline.py template")?;
+        files.push(GeneratedFile::new("pipeline.py", pipeline_py));
+    }
+
+    // Generate router.py; autogen routes are a group chat in agent.py instead
+    if when.holds("router.py")? {
+        let router = spec.router.as_ref().expect("router.py needs router");
+        let routes = route_context(spec, router);
+        let fallback = router.fallback.as_ref().unwrap_or(&router.routes[0].name);
+        let mut ctx = tera::Context::new();
+        ctx.insert(
+            "classifier_model",
+            router.model.unwrap_or(spec.agent.model).model_id(),
+        );
+        ctx.insert("routes", &routes);
+        ctx.insert("fallback", fallback);
+        let router_py = tera
+            .render("router.py", &ctx)
+            .context("rendering router.py template")?;
+        files.push(GeneratedFile::new("router.py", router_py));
+    }
+
+    // Generate architecture.md
+    if when.holds("architecture.md")? {
+        files.push(GeneratedFile::new(
+            "architecture.md",
+            graph::architecture_md(spec),
+        ));
+    }
+
+    // Generate justfile or Makefile
+    if when.holds("justfile")? {
+        let runner = spec.tasks.expect("justfile needs tasks");
+        let (path, contents) = tasks::render(spec, runner);
+        files.push(GeneratedFile::new(path, contents));
+    }
+
+    // Generate pyproject.toml and .pre-commit-config.yaml
+    if when.holds("pyproject.toml")? {
+        let quality = spec.quality.as_ref().expect("pyproject.toml needs quality");
+        files.push(GeneratedFile::new(
+            "pyproject.toml",
+            quality::render_pyproject(quality),
+        ));
+    }
+    if when.holds(".pre-commit-config.yaml")? {
+        let quality = spec
+            .quality
+            .as_ref()
+            .expect(".pre-commit-config.yaml needs quality");
+        files.push(GeneratedFile::new(
+            ".pre-commit-config.yaml",
+            quality::render_pre_commit(quality),
+        ));
+    }
+
+    // Generate .github/workflows/ci.yml
+    if when.holds(".github/workflows/ci.yml")? {
+        let ci = spec.ci.as_ref().expect("ci.yml needs ci");
+        let agent_py = tera.placed(spec, "agent.py")?;
+        let agent_dir = agent_py.rsplit_once('/').map(|(dir, _)| dir);
+        match ci.provider {
+            CiProvider::Github => files.push(GeneratedFile::new(
+                ".github/workflows/ci.yml",
+                ci::render_github(spec, agent_dir),
+            )),
+        }
+    }
+
+    // Generate tests/test_agent.py, which ci.yml runs
+    if when.holds("tests/test_agent.py")? {
+        let test_agent_py = render_test_agent_py(tera, spec)?;
+        files.push(GeneratedFile::new("tests/test_agent.py", test_agent_py));
+    }
+
+    // Generate .devcontainer/
+    if when.holds(".devcontainer/devcontainer.json")? {
+        files.push(GeneratedFile::new(
+            ".devcontainer/devcontainer.json",
+            render_devcontainer(spec)?,
+        ));
+        files.push(GeneratedFile::new(
+            ".devcontainer/Dockerfile",
+            format!(
+                "FROM mcr.microsoft.com/devcontainers/python:1-{PYTHON_VERSION}-bookworm\n\
+                 \n\
+                 # Rebuild the container after changing requirements.txt\n\
+                 COPY requirements.txt /tmp/requirements.txt\n\
+                 RUN pip install --no-cache-dir -r /tmp/requirements.txt && rm /tmp/requirements.txt\n"
+            ),
+        ));
+    }
+
+    // Generate requirements.txt
+    let reqs = render_requirements(spec);
+    if when.holds("environment.yml")? {
+        files.push(GeneratedFile::new(
+            "environment.yml",
+            render_conda_env(spec, &reqs),
+        ));
+    }
+    files.push(GeneratedFile::new("requirements.txt", reqs));
+
+    // Generate config.py and .env.example from the same variables
+    let vars = env_vars(spec);
+    let py_str = |s: &str| serde_json::to_string(s).expect("strings serialize");
+    let variables: Vec<serde_json::Value> = vars
+        .iter()
+        .map(|var| {
+            serde_json::json!({
+                "name": py_str(&var.name),
+                "required": if var.required { "True" } else { "False" },
+                "default": if var.required { "None".to_string() } else { py_str(&var.example) },
+                "comment": py_str(&var.comment),
+            })
+        })
+        .collect();
+    let environments: Vec<&String> = spec.environments.keys().collect();
+    let mut ctx = tera::Context::new();
+    ctx.insert("variables", &variables);
+    ctx.insert("environments", &environments);
+    ctx.insert("default_environment", &py_str(spec.default_environment()));
+    let config_py = tera
+        .render("config.py", &ctx)
+        .context("rendering config.py template")?;
+    files.push(GeneratedFile::new("config.py", config_py));
+    let env: Vec<String> = vars
+        .iter()
+        .map(|var| format!("# {}\n{}={}\n", var.comment, var.name, var.example))
+        .collect();
+    files.push(GeneratedFile::new(".env.example", env.join("\n")));
+    for (name, environment) in &spec.environments {
+        let values = environment.values();
+        if values.is_empty() {
+            continue;
+        }
+        let mut env = format!("# Loaded over .env when APP_ENV is {name}\n");
+        for (key, value) in values {
+            let comment = vars
+                .iter()
+                .find(|var| var.name == key)
+                .map(|var| &var.comment);
+            env.push_str(&format!(
+                "\n# {}\n{key}={value}\n",
+                comment.expect("listed in env_vars")
+            ));
+        }
+        files.push(GeneratedFile::new(&format!(".env.{name}.example"), env));
+    }
+
+    Ok(files)
+}
+
+/// Insert `header` at the top of `contents`, below a shebang line.
+fn prepend_header(contents: &mut Vec<u8>, header: &str) {
+    let at = if contents.starts_with(b"#!") {
+        contents
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(contents.len(), |i| i + 1)
+    } else {
+        0
+    };
+    contents.splice(at..at, header.bytes());
+}
+
+/// [`render`], then format the Python through ruff when the spec has a
+/// `quality` section and ruff is installed.
+pub(crate) fn render_formatted(
+    spec: &Spec,
+    templates: Option<&Path>,
+) -> Result<Vec<GeneratedFile>> {
+    let mut files = render(spec, templates)?;
+    if let Some(quality) = &spec.quality {
+        quality::format_python(&mut files, quality)?;
+    }
+    Ok(files)
+}
+
+/// The directory `generate` writes to: `out_dir/<slug>`, or `out_dir`
+/// itself with `in_place`.
+pub fn project_dir(spec: &Spec, out_dir: &Path, options: &GenerateOptions) -> PathBuf {
+    if options.in_place {
+        out_dir.to_path_buf()
+    } else {
+        out_dir.join(spec.names().slug)
+    }
+}
+
+/// The project directory and the files `generate` would write there,
+/// tachi.lock last.
+fn prepare(
+    spec: &Spec,
+    out_dir: &Path,
+    options: &GenerateOptions,
+) -> Result<(PathBuf, Vec<GeneratedFile>, String)> {
+    let project_dir = project_dir(spec, out_dir, options);
+
+    // Vendored templates pin the output across tachi upgrades
+    let templates = templates::pinned(&project_dir, options.templates.as_deref())?;
+    let mut files = render_formatted(spec, templates.as_deref())?;
+    if let Some(mount) = &options.mount {
+        let tera = Renderer::new(templates.as_deref())?;
+        let router = mount::mount(&tera, spec, mount, &project_dir, &mut files)?;
+        if let Some(quality) = &spec.quality {
+            let router = files
+                .iter_mut()
+                .find(|f| f.path == router)
+                .expect("mount adds the router");
+            quality::format_python(std::slice::from_mut(router), quality)?;
+        }
+    }
+    if options.in_place {
+        project::adopt(spec, &project_dir, &mut files)?;
+    }
+    if !options.allow_secrets {
+        let found = secrets::scan(&files);
+        if !found.is_empty() {
+            return Err(SecretsFound(found).into());
+        }
+    }
+    let lock = lock::render_lock(spec, templates.as_deref(), options.source.as_ref())?;
+    if options.locked {
+        lock::check_locked(&project_dir, &lock)?;
+    }
+    Ok((project_dir, files, lock))
+}
+
+/// Where `generate` keeps the last render of each file, as the base for
+/// merging the user's edits into the next one.
+const BASE_DIR: &str = ".tachi/base";
+
+/// The user's copy of `file` merged with the new render against the last
+/// one, with whether that needed conflict markers. `None` when either the
+/// file or its base is missing, or the file is an asset.
+fn merge_with_base(project_dir: &Path, file: &GeneratedFile) -> Result<Option<(Vec<u8>, bool)>> {
+    if file.asset {
+        return Ok(None);
+    }
+    let read = |path: PathBuf| match fs::read(&path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+    };
+    let Some(old) = read(project_dir.join(&file.path))? else {
+        return Ok(None);
+    };
+    let Some(base) = read(project_dir.join(BASE_DIR).join(&file.path))? else {
+        return Ok(None);
+    };
+    let (merged, conflicted) = diff::merge3(
+        &String::from_utf8_lossy(&base),
+        &String::from_utf8_lossy(&old),
+        &String::from_utf8_lossy(&file.contents),
+    );
+    Ok(Some((merged.into_bytes(), conflicted)))
+}
+
+/// Write a merge result for `file`, calling it merged only when it kept
+/// some of the user's edits.
+fn write_merged(
+    project_dir: &Path,
+    file: &GeneratedFile,
+    contents: &[u8],
+    conflicted: bool,
+) -> Result<WriteStatus> {
+    let status = write_file(project_dir, &file.path, contents, true)?;
+    Ok(if conflicted {
+        WriteStatus::Conflicted
+    } else if status == WriteStatus::Updated && contents != file.contents {
+        WriteStatus::Merged
+    } else {
+        status
+    })
+}
+
+/// An existing file that regenerating would overwrite with new contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+    /// `new` is a clean merge of the user's edits into the render, which
+    /// `generate` writes without needing `--force`
+    pub merges: bool,
+}
+
+/// The existing files `generate` would change, without writing anything.
+/// Scaffolds are left out, since they are never replaced, and so are the
+/// project's own manifests, which only gain tachi's entries.
+pub fn changes(spec: &Spec, out_dir: &Path, options: &GenerateOptions) -> Result<Vec<FileChange>> {
+    let (project_dir, files, _) = prepare(spec, out_dir, options)?;
+    let mut changes = Vec::new();
+    for file in files.into_iter().filter(|f| !f.scaffold && !f.existing) {
+        let path = project_dir.join(&file.path);
+        let old = match fs::read(&path) {
+            Ok(old) => old,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        let (new, merges) = match merge_with_base(&project_dir, &file)? {
+            Some((merged, false)) => (merged, true),
+            _ => (file.contents, false),
+        };
+        if old != new {
+            changes.push(FileChange {
+                path: file.path,
+                old,
+                new,
+                merges,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Render every file for `spec` into its [`project_dir`] and report what
+/// happened to each one.
+pub fn generate(
+    spec: &Spec,
+    out_dir: &Path,
+    options: &GenerateOptions,
+) -> Result<Vec<(String, WriteStatus)>> {
+    let (project_dir, files, lock) = prepare(spec, out_dir, options)?;
+
+    let mut written = Vec::new();
+    for file in files {
+        if file.scaffold && project_dir.join(&file.path).exists() {
+            written.push((file.path, WriteStatus::Kept));
+            continue;
+        }
+        if file.existing {
+            let status = match write_file(&project_dir, &file.path, &file.contents, true)? {
+                WriteStatus::Updated => WriteStatus::Merged,
+                status => status,
+            };
+            written.push((file.path, status));
+            continue;
+        }
+        let status = match options.resolve.get(&file.path) {
+            Some(Resolution::Overwrite) => {
+                write_file(&project_dir, &file.path, &file.contents, true)?
+            }
+            Some(Resolution::Keep) => WriteStatus::Kept,
+            // An asset has no lines to mark, so merging keeps the user's copy
+            Some(Resolution::Merge) if file.asset => WriteStatus::Kept,
+            Some(Resolution::Merge) => match merge_with_base(&project_dir, &file)? {
+                Some((merged, conflicted)) => {
+                    write_merged(&project_dir, &file, &merged, conflicted)?
+                }
+                // Without a base every difference is a conflict
+                None => {
+                    let path = project_dir.join(&file.path);
+                    let old =
+                        fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+                    let merged = diff::conflict_markers(
+                        &String::from_utf8_lossy(&old),
+                        &String::from_utf8_lossy(&file.contents),
+                    );
+                    write_merged(&project_dir, &file, merged.as_bytes(), true)?
+                }
+            },
+            None if options.force => write_file(&project_dir, &file.path, &file.contents, true)?,
+            None => match merge_with_base(&project_dir, &file)? {
+                Some((merged, conflicted)) => {
+                    write_merged(&project_dir, &file, &merged, conflicted)?
+                }
+                None => write_file(&project_dir, &file.path, &file.contents, false)?,
+            },
+        };
+        // A kept file still differs from the render, so its base stays put
+        // and the next run offers the same change again
+        if status != WriteStatus::Kept && !file.scaffold {
+            write_file(
+                &project_dir.join(BASE_DIR),
+                &file.path,
+                &file.contents,
+                true,
+            )?;
+        }
+        written.push((file.path, status));
+    }
+    // The lock tracks tachi rather than the user, so it is always rewritten
+    let status = write_file(&project_dir, lock::LOCK_FILE, lock.as_bytes(), true)?;
+    written.push((lock::LOCK_FILE.to_string(), status));
+    Ok(written)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStatus {
+    Created,
+    Updated,
+    Unchanged,
+    /// A scaffold the user owns was already there, or the user kept theirs
+    Kept,
+    /// The render merged with the user's edits
+    Merged,
+    /// Written with conflict markers to resolve by hand
+    Conflicted,
+}
+
+impl WriteStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            WriteStatus::Created => "created",
+            WriteStatus::Updated => "updated",
+            WriteStatus::Unchanged => "unchanged",
+            WriteStatus::Kept => "kept",
+            WriteStatus::Merged => "merged",
+            WriteStatus::Conflicted => "conflict",
+        }
+    }
+}
+
+/// Write `content` unless the file on disk already hashes the same, so
+/// repeated runs leave mtimes alone. Only differing files need `--force`.
+fn write_file(out_dir: &Path, name: &str, content: &[u8], force: bool) -> Result<WriteStatus> {
+    let path = out_dir.join(name);
+    let dir = path.parent().unwrap_or(out_dir);
+    fs::create_dir_all(dir)
+        .with_context(|| format!("creating output directory {}", dir.display()))?;
+    let status = if path.exists() {
+        let existing = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        if Sha256::digest(&existing) == Sha256::digest(content) {
+            return Ok(WriteStatus::Unchanged);
+        }
+        if !force {
+            return Err(OverwriteConflict(path).into());
+        }
+        WriteStatus::Updated
+    } else {
+        WriteStatus::Created
+    };
+    fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
+    Ok(status)
+}
+
+/// `body` as the body of a function, indented under its `def`.
+pub(crate) fn indent_body(body: &str) -> String {
+    body.trim_end()
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("    {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The tools agent.py imports and the expressions that build them, the
+/// template pack's own after the spec's.
+fn insert_tools(ctx: &mut tera::Context, tera: &Renderer, spec: &Spec) -> Result<()> {
+    // Built-in tools import from smolagents, the rest from the generated tools.py
+    let tool_imports = |smolagents: bool| -> Vec<&str> {
+        spec.agent
+            .tools
+            .iter()
+            .filter(|t| t.ships_with_smolagents() == smolagents)
+            .flat_map(|t| t.py_classes())
+            .collect()
+    };
+    let injection_filter = spec.wants_injection_filter();
+    let approval_classes = spec.approval_classes();
+    let mut tool_instances: Vec<String> = spec
+        .agent
+        .tools
+        .iter()
+        .flat_map(|t| {
+            let guarded = injection_filter && t.kind().fetches_web_content();
+            let approval_classes = &approval_classes;
+            let literal = |s: &str| serde_json::to_string(s).expect("strings serialize");
+            let mut overrides = Vec::new();
+            if let Some(name) = &t.name {
+                overrides.push(format!("name={}", literal(name)));
+            }
+            if let Some(description) = &t.description {
+                overrides.push(format!("description={}", literal(description)));
+            }
+            t.py_classes()
+                .into_iter()
+                .zip(t.py_instances())
+                .map(move |(class, instance)| {
+                    let instance = if overrides.is_empty() {
+                        instance
+                    } else {
+                        format!("override_tool({instance}, {})", overrides.join(", "))
+                    };
+                    let instance = if approval_classes.contains(&class) {
+                        format!("require_approval({instance})")
+                    } else {
+                        instance
+                    };
+                    if guarded {
+                        format!("guard({instance})")
+                    } else {
+                        instance
+                    }
+                })
+        })
+        .collect();
+    ctx.insert(
+        "tool_overrides",
+        &spec
+            .agent
+            .tools
+            .iter()
+            .any(|t| t.name.is_some() || t.description.is_some()),
+    );
+    // One import line per module, in the manifest's order
+    let mut pack_tool_imports: Vec<(String, Vec<String>)> = Vec::new();
+    for tool in tera.pack_tools()? {
+        tool_instances.push(format!("{}()", tool.class));
+        match pack_tool_imports
+            .iter_mut()
+            .find(|(m, _)| *m == tool.module)
+        {
+            Some((_, classes)) => classes.push(tool.class),
+            None => pack_tool_imports.push((tool.module, vec![tool.class])),
+        }
+    }
+    ctx.insert("tool_imports", &tool_imports(true));
+    ctx.insert("local_tool_imports", &tool_imports(false));
+    ctx.insert(
+        "pack_tool_imports",
+        &pack_tool_imports
+            .iter()
+            .map(|(module, classes)| format!("from {module} import {}", classes.join(", ")))
+            .collect::<Vec<_>>(),
+    );
+    ctx.insert("tool_instances", &tool_instances);
+    Ok(())
+}
+
+/// Offline tests of agent.py. smolagents agents also run once, with a mock
+/// model, unless the answer has to match a JSON schema or runs read memory.
+fn render_test_agent_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let smolagents = spec.framework == Framework::Smolagents;
+    let json_answer = matches!(spec.agent.response_format, Some(ResponseFormat::Json(_)));
+    let mut ctx = tera::Context::new();
+    ctx.insert(
+        "agent_dir",
+        &relative_dir(
+            &tera.placed(spec, "tests/test_agent.py")?,
+            &tera.placed(spec, "agent.py")?,
+        ),
+    );
+    ctx.insert("smolagents", &smolagents);
+    ctx.insert("model_class", spec.model_class());
+    ctx.insert(
+        "structured_outputs",
+        &(smolagents && spec.structured_outputs()),
+    );
+    ctx.insert("json_answer", &(smolagents && json_answer));
+    ctx.insert("cache", &spec.cache.is_some());
+    ctx.insert(
+        "knowledge",
+        &(spec.framework == Framework::Llamaindex && spec.knowledge.is_some()),
+    );
+    ctx.insert(
+        "run",
+        &(smolagents
+            && spec.memory.is_none()
+            && !matches!(
+                spec.agent.response_format,
+                Some(ResponseFormat::JsonSchema { .. })
+            )),
+    );
+    ctx.insert("final_answer_hook", &spec.hooks.agent);
+    tera.render("test_agent.py", &ctx)
+        .context("rendering test_agent.py template")
+}
+
+/// The directory project file `to` is in, relative to the one `from` is in.
+fn relative_dir(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').collect();
+    let to: Vec<&str> = to.split('/').collect();
+    let (from, to) = (&from[..from.len() - 1], &to[..to.len() - 1]);
+    let common = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+fn render_agent_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let mut ctx = tera::Context::new();
+    insert_tools(&mut ctx, tera, spec)?;
+    ctx.insert("add_base_tools", &spec.agent.add_base_tools);
+    let excluded: Vec<String> = spec
+        .agent
+        .exclude_base_tools
+        .iter()
+        .map(|b| serde_json::to_string(b.py_name()).expect("strings serialize"))
+        .collect();
+    ctx.insert(
+        "excluded_base_tools",
+        &(!excluded.is_empty()).then(|| format!("[{}]", excluded.join(", "))),
+    );
+    ctx.insert("injection_filter", &spec.wants_injection_filter());
+    ctx.insert("approval", &spec.approval.is_some());
+    ctx.insert("long_term_memory", &spec.memory.is_some());
+    ctx.insert("hooks", &spec.hooks.agent);
+    ctx.insert("cache", &spec.cache.is_some());
+    ctx.insert("limits", &spec.limits.is_some());
+    ctx.insert("response_format", &spec.agent.response_format.is_some());
+    ctx.insert("structured_outputs", &spec.structured_outputs());
+    ctx.insert(
+        "planning_interval",
+        &spec.planning.enabled.then_some(spec.planning.interval),
+    );
+    ctx.insert("router", &spec.router.is_some());
+    ctx.insert("max_steps", &spec.agent.max_steps);
+    // A Python literal, so 0 still renders
+    ctx.insert(
+        "temperature",
+        &spec.agent.temperature.map(|t| t.to_string()),
+    );
+    ctx.insert("model_id", &spec.agent.model.model_id());
+    ctx.insert("model_class", spec.model_class());
+    let environments = || spec.environments.values();
+    ctx.insert(
+        "model_provider",
+        &environments().any(|e| e.model_provider.is_some()),
+    );
+    ctx.insert(
+        "model_endpoint",
+        &environments().any(|e| e.model_endpoint.is_some()),
+    );
+    let security = spec.security.as_ref();
+    let authorized_imports: Vec<String> = security
+        .iter()
+        .flat_map(|s| &s.authorized_imports)
+        .map(|m| serde_json::to_string(m).expect("strings serialize"))
+        .collect();
+    ctx.insert(
+        "authorized_imports",
+        &(!authorized_imports.is_empty()).then(|| format!("[{}]", authorized_imports.join(", "))),
+    );
+    ctx.insert(
+        "max_execution_seconds",
+        &security.and_then(|s| s.max_execution_seconds),
+    );
+
+    tera.render("agent.py", &ctx)
+        .context("rendering agent.py template")
+}
+
+/// Each router route as the Python literals router.py and the autogen
+/// agent.py list it with.
+fn route_context(spec: &Spec, router: &Router) -> Vec<serde_json::Value> {
+    let literal = |s: &str| serde_json::to_string(s).expect("strings serialize");
+    router
+        .routes
+        .iter()
+        .map(|route| {
+            // The route's tools, as the classes create_agent() keeps
+            let classes = (!route.tools.is_empty()).then(|| {
+                let classes: Vec<String> = spec
+                    .agent
+                    .tools
+                    .iter()
+                    .filter(|t| route.tools.contains(&t.kind()))
+                    .flat_map(|t| t.py_classes())
+                    .map(literal)
+                    .collect();
+                format!("[{}]", classes.join(", "))
+            });
+            serde_json::json!({
+                "name": route.name,
+                "description": literal(&route.description),
+                "instructions": route.instructions.as_deref().map_or("None".to_string(), literal),
+                "classes": classes.unwrap_or_else(|| "None".to_string()),
+            })
+        })
+        .collect()
+}
+
+/// agent.py for `framework: openai-assistants`.
+fn render_openai_agent_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let mut ctx = tera::Context::new();
+    insert_tools(&mut ctx, tera, spec)?;
+    ctx.insert("model_id", &spec.openai.model);
+    ctx.insert("max_steps", &spec.openai.max_steps);
+    tera.render("agent_openai.py", &ctx)
+        .context("rendering agent_openai.py template")
+}
+
+/// agent.py for `framework: autogen`: one assistant, or with a router a
+/// group chat of one agent per route.
+fn render_autogen_agent_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let mut ctx = tera::Context::new();
+    insert_tools(&mut ctx, tera, spec)?;
+    let instructions = "Answer the request, calling the tools when they help. \
+                        Once you have the answer, reply with it and call no tools.";
+    let system_message = match &spec.agent.description {
+        Some(description) => format!("{description}\n\n{instructions}"),
+        None => instructions.to_string(),
+    };
+    ctx.insert(
+        "system_message",
+        &serde_json::to_string(&system_message).expect("strings serialize"),
+    );
+    ctx.insert("agent_name", &spec.names().package);
+    ctx.insert("model_id", &spec.autogen.model);
+    ctx.insert("max_rounds", &spec.autogen.max_rounds);
+    let routes = spec
+        .router
+        .as_ref()
+        .map(|router| route_context(spec, router))
+        .unwrap_or_default();
+    ctx.insert("routes", &routes);
+    tera.render("agent_autogen.py", &ctx)
+        .context("rendering agent_autogen.py template")
+}
+
+/// agent.py for `framework: llamaindex`, querying the knowledge index when
+/// the spec has one.
+fn render_llamaindex_agent_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let mut ctx = tera::Context::new();
+    insert_tools(&mut ctx, tera, spec)?;
+    ctx.insert("model_id", spec.agent.model.model_id());
+    ctx.insert("max_iterations", &spec.llamaindex.max_iterations);
+    ctx.insert("knowledge", &spec.knowledge.is_some());
+    if let Some(knowledge) = &spec.knowledge {
+        ctx.insert("top_k", &knowledge.top_k);
+        let description = format!(
+            "Answers questions from the documents in {}/. Ask it a full question.",
+            knowledge.path.trim_end_matches('/')
+        );
+        ctx.insert(
+            "knowledge_description",
+            &serde_json::to_string(&description).expect("strings serialize"),
+        );
+    }
+    tera.render("agent_llamaindex.py", &ctx)
+        .context("rendering agent_llamaindex.py template")
+}
+
+fn render_cli_py(tera: &Renderer, spec: &Spec) -> Result<String> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("agent_name", &spec.agent.name);
+    ctx.insert("model_id", spec.model_id());
+    ctx.insert("openai", &(spec.framework == Framework::OpenaiAssistants));
+    ctx.insert("autogen", &(spec.framework == Framework::Autogen));
+    ctx.insert("llamaindex", &(spec.framework == Framework::Llamaindex));
+    ctx.insert("tachi_version", env!("CARGO_PKG_VERSION"));
+    ctx.insert("rich", &(spec.cli.ui == CliUi::Rich));
+    ctx.insert("transcripts", &spec.cli.transcripts);
+    ctx.insert(
+        "prompt_toolkit",
+        &(spec.cli.input == CliInput::PromptToolkit),
+    );
+    ctx.insert("vision", &spec.has_capability(Capability::Vision));
+    ctx.insert("approval", &spec.approval.is_some());
+    let voice = spec.has_capability(Capability::Voice);
+    ctx.insert("voice", &voice);
+    ctx.insert("tts", &(voice && spec.voice.tts));
+
+    let template = match spec.cli.framework {
+        CliFramework::Plain => "cli.py",
+        CliFramework::Typer => "cli_typer.py",
+    };
+    tera.render(template, &ctx)
+        .with_context(|| format!("rendering {template} template"))
+}
+
+/// The URL health.py calls to check the model API takes the key, and the
+/// variable holding the key.
+fn model_check(spec: &Spec) -> (&'static str, &'static str) {
+    match spec.framework {
+        Framework::Smolagents | Framework::Llamaindex => (
+            "https://huggingface.co/api/whoami-v2",
+            "HUGGINGFACEHUB_API_TOKEN",
+        ),
+        _ => ("https://api.openai.com/v1/models", "OPENAI_API_KEY"),
+    }
+}
+
+/// A2A agent card served at `/.well-known/agent.json`.
+fn render_agent_card(spec: &Spec) -> Result<String> {
+    let a2a = &spec.a2a;
+    let url = a2a
+        .url
+        .clone()
+        .unwrap_or_else(|| format!("http://localhost:{}/", a2a.port));
+    let tool_tags = spec.tool_classes();
+    let card = serde_json::json!({
+        "name": spec.agent.name,
+        "description": spec.agent.description.clone().unwrap_or_else(|| {
+            format!("smolagents CodeAgent on {}", spec.agent.model.model_id())
+        }),
+        "url": url,
+        "version": "0.1.0",
+        "protocolVersion": "0.2.6",
+        "capabilities": { "streaming": false, "pushNotifications": false },
+        "defaultInputModes": ["text/plain"],
+        "defaultOutputModes": ["text/plain"],
+        "skills": [{
+            "id": "ask",
+            "name": "Ask",
+            "description": "Answer a question, using tools and Python as needed",
+            "tags": tool_tags,
+        }],
+    });
+    let mut json = serde_json::to_string_pretty(&card).context("serializing agent card")?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Every environment variable the project reads, in `.env.example` order:
+/// the model token, the tools' keys, then settings with defaults. A name two
+/// tools share is listed once, with the first tool's comment.
+pub(crate) fn env_vars(spec: &Spec) -> Vec<EnvVar> {
+    let setting = |name: &str, comment: &str, default: &str| EnvVar {
+        name: name.to_string(),
+        comment: comment.to_string(),
+        example: default.to_string(),
+        required: false,
+    };
+    let mut vars = match spec.framework {
+        Framework::Smolagents | Framework::Llamaindex => vec![EnvVar {
+            name: "HUGGINGFACEHUB_API_TOKEN".to_string(),
+            comment: "Hugging Face token for the model".to_string(),
+            example: String::new(),
+            required: true,
+        }],
+        Framework::OpenaiAssistants if spec.lang == Lang::Go => vec![
+            EnvVar {
+                name: "OPENAI_API_KEY".to_string(),
+                comment: "API key for the model; another server may not need one".to_string(),
+                example: String::new(),
+                required: true,
+            },
+            setting(
+                "OPENAI_MODEL",
+                &format!("Model the chat runs; empty uses {}", spec.openai.model),
+                "",
+            ),
+            setting(
+                "OPENAI_BASE_URL",
+                "OpenAI-compatible API to chat with; empty uses OpenAI",
+                "",
+            ),
+        ],
+        Framework::OpenaiAssistants => vec![
+            EnvVar {
+                name: "OPENAI_API_KEY".to_string(),
+                comment: "OpenAI API key for the model".to_string(),
+                example: String::new(),
+                required: true,
+            },
+            setting(
+                "OPENAI_MODEL",
+                &format!(
+                    "Model the Responses API runs; empty uses {}",
+                    spec.openai.model
+                ),
+                "",
+            ),
+        ],
+        Framework::SemanticKernel => vec![
+            EnvVar {
+                name: "OPENAI_API_KEY".to_string(),
+                comment: "OpenAI API key for the Semantic Kernel chat model".to_string(),
+                example: String::new(),
+                required: true,
+            },
+            setting(
+                "SEMANTIC_KERNEL_MODEL",
+                &format!(
+                    "Chat model the kernel runs; empty uses {}",
+                    spec.semantic_kernel.model
+                ),
+                "",
+            ),
+        ],
+        Framework::SpringAi => vec![
+            EnvVar {
+                name: "OPENAI_API_KEY".to_string(),
+                comment: "OpenAI API key for the Spring AI chat model".to_string(),
+                example: String::new(),
+                required: true,
+            },
+            setting(
+                "OPENAI_MODEL",
+                &format!(
+                    "Chat model the agent runs; empty uses {}",
+                    spec.spring_ai.model
+                ),
+                "",
+            ),
+        ],
+        Framework::Autogen => vec![
+            EnvVar {
+                name: "OPENAI_API_KEY".to_string(),
+                comment: "OpenAI API key the AG2 agents call the model with".to_string(),
+                example: String::new(),
+                required: true,
+            },
+            setting(
+                "AUTOGEN_MODEL",
+                &format!(
+                    "Model every AG2 agent runs; empty uses {}",
+                    spec.autogen.model
+                ),
+                "",
+            ),
+        ],
+    };
+    if !spec.environments.is_empty() {
+        let names: Vec<&str> = spec.environments.keys().map(String::as_str).collect();
+        vars.push(setting(
+            "APP_ENV",
+            &format!(
+                "Environment whose .env.<name> is loaded over this file: {}",
+                names.join(", ")
+            ),
+            spec.default_environment(),
+        ));
+    }
+    let environments = || spec.environments.values();
+    if environments().any(|e| e.model_provider.is_some()) {
+        vars.push(setting(
+            "MODEL_PROVIDER",
+            "Hugging Face inference provider serving the model; empty picks one",
+            "",
+        ));
+    }
+    if environments().any(|e| e.model_endpoint.is_some()) {
+        vars.push(setting(
+            "MODEL_ENDPOINT",
+            &format!(
+                "Dedicated endpoint URL serving the model instead of {}",
+                spec.agent.model.model_id()
+            ),
+            "",
+        ));
+    }
+    vars.extend(spec.agent.tools.iter().flat_map(|t| t.env_vars()));
+    if spec
+        .cache
+        .as_ref()
+        .is_some_and(|c| c.backend == CacheBackend::Redis)
+    {
+        vars.push(setting(
+            "CACHE_REDIS_URL",
+            "Redis for the model response cache",
+            "redis://localhost:6379/0",
+        ));
+    }
+    if spec.cache.is_some() {
+        vars.push(setting(
+            "NO_CACHE",
+            "Set to 1 to skip the model response cache",
+            "",
+        ));
+    }
+    if spec.worker.enabled {
+        match spec.worker.backend {
+            WorkerBackend::Rq => vars.push(setting(
+                "REDIS_URL",
+                "Redis the RQ worker takes tasks from",
+                "redis://localhost:6379/0",
+            )),
+            WorkerBackend::Celery => vars.extend([
+                setting(
+                    "CELERY_BROKER_URL",
+                    "Celery broker the worker takes tasks from",
+                    "redis://localhost:6379/0",
+                ),
+                setting(
+                    "CELERY_RESULT_BACKEND",
+                    "Celery result store",
+                    "redis://localhost:6379/1",
+                ),
+            ]),
+        }
+    }
+    if spec.has_interface(Interface::Server) {
+        vars.push(setting(
+            "SERVER_PORT",
+            "Port server.py listens on without --port",
+            &SERVER_PORT.to_string(),
+        ));
+        if spec.server.auth == ServerAuth::ApiKey {
+            vars.push(EnvVar {
+                name: "SERVER_API_KEY".to_string(),
+                comment: "Key clients send as a Bearer token or X-API-Key".to_string(),
+                example: String::new(),
+                required: true,
+            });
+        }
+    }
+    if spec.has_interface(Interface::Gradio) {
+        vars.push(setting(
+            "GRADIO_SERVER_PORT",
+            "Port gradio_app.py listens on without --port",
+            &GRADIO_PORT.to_string(),
+        ));
+    }
+    for key in environments().flat_map(|e| e.env.keys()) {
+        vars.push(setting(key, "Set per environment", ""));
+    }
+    let mut seen = std::collections::HashSet::new();
+    vars.retain(|var| seen.insert(var.name.clone()));
+    vars
+}
+
+/// Dev container built from .devcontainer/Dockerfile. Codespaces secrets
+/// arrive as environment variables; elsewhere the copied .env holds them.
+/// Host variables are not forwarded: an unset one would arrive empty and
+/// shadow the value in .env.
+fn render_devcontainer(spec: &Spec) -> Result<String> {
+    let codespaces_secrets: serde_json::Map<String, serde_json::Value> = env_vars(spec)
+        .into_iter()
+        .filter(|var| var.required)
+        .map(|var| (var.name, serde_json::json!({ "description": var.comment })))
+        .collect();
+    let mut container = serde_json::json!({
+        "name": spec.agent.name,
+        "build": { "dockerfile": "Dockerfile", "context": ".." },
+        "secrets": codespaces_secrets,
+        "postCreateCommand": "test -f .env || cp .env.example .env",
+        "customizations": {
+            "vscode": { "extensions": ["ms-python.python"] },
+        },
+    });
+    let mut ports = Vec::new();
+    if spec.a2a.enabled {
+        ports.push(spec.a2a.port);
+    }
+    if spec.has_interface(Interface::Server) {
+        ports.push(SERVER_PORT);
+    }
+    if spec.has_interface(Interface::Gradio) {
+        ports.push(GRADIO_PORT);
+    }
+    if !ports.is_empty() {
+        container["forwardPorts"] = serde_json::json!(ports);
+    }
+    let mut json =
+        serde_json::to_string_pretty(&container).context("serializing devcontainer.json")?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Where the timer expects the project to be installed.
+fn install_dir(spec: &Spec) -> String {
+    format!("/opt/{}", spec.names().slug)
+}
+
+/// Conda environment with the project's Python and its pip requirements.
+fn render_conda_env(spec: &Spec, requirements: &str) -> String {
+    let mut env = format!(
+        "# Create with `conda env create -f environment.yml`, then\n\
+         # `conda activate {slug}`. Packages come from pip, as in requirements.txt.\n\
+         name: {slug}\n\
+         channels:\n  - conda-forge\n\
+         dependencies:\n  - python={PYTHON_VERSION}\n  - pip\n  - pip:\n",
+        slug = spec.names().slug,
+    );
+    for req in requirements.lines() {
+        env.push_str(&format!("      - {req}\n"));
+    }
+    env
+}
+
+fn render_batch_cron(spec: &Spec) -> String {
+    let schedule = spec.batch.timer.as_ref().map_or("", |t| t.schedule.trim());
+    format!(
+        "# Runs batch.py for {name}. Install with `crontab -e` and paste the line\n\
+         # below, after pointing `cd` at where the project lives.\n\
+         {schedule} cd {dir} && .venv/bin/python batch.py >> batch.log 2>&1\n",
+        name = spec.agent.name,
+        dir = install_dir(spec),
+    )
+}
+
+/// A oneshot service running batch.py and the timer that starts it.
+fn render_batch_units(spec: &Spec) -> (String, String) {
+    let slug = spec.names().slug;
+    let schedule = spec.batch.timer.as_ref().map_or("", |t| t.schedule.trim());
+    let dir = install_dir(spec);
+    let service = format!(
+        "# Runs batch.py once; {slug}-batch.timer starts it on schedule. Copy both\n\
+         # units to ~/.config/systemd/user/, point WorkingDirectory at where the\n\
+         # project lives, then: systemctl --user enable --now {slug}-batch.timer\n\
+         [Unit]\n\
+         Description={name} batch run\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         WorkingDirectory={dir}\n\
+         ExecStart={dir}/.venv/bin/python batch.py\n",
+        name = spec.agent.name,
+    );
+    let timer = format!(
+        "[Unit]\n\
+         Description=Run the {name} batch on schedule\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={schedule}\n\
+         # Catch up on a run missed while the machine was off\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        name = spec.agent.name,
+    );
+    (service, timer)
+}
+
+fn render_requirements(spec: &Spec) -> String {
+    // Extras go on the one smolagents line, so pip sees no conflicting pins
+    let extras = if spec.has_interface(Interface::Gradio) {
+        "[gradio]"
+    } else {
+        ""
+    };
+    let smolagents = match spec.runtime.smolagents_version {
+        Some(version) => format!("smolagents{extras}=={version}"),
+        None => format!("smolagents{extras}"),
+    };
+    let mut reqs = vec![smolagents.as_str(), "python-dotenv"];
+    // smolagents still provides the tool classes
+    match spec.framework {
+        Framework::Smolagents => {}
+        Framework::OpenaiAssistants => reqs.push("openai"),
+        Framework::Autogen => reqs.push("ag2[openai]"),
+        Framework::SemanticKernel => unreachable!("validated: semantic-kernel generates C#"),
+        Framework::SpringAi => unreachable!("validated: spring-ai generates Java"),
+        Framework::Llamaindex => {
+            reqs.extend(["llama-index-core", "llama-index-llms-huggingface-api"]);
+            if spec.knowledge.is_some() {
+                reqs.extend([
+                    "llama-index-embeddings-huggingface",
+                    "llama-index-readers-file",
+                ]);
+            }
+        }
+    }
+    reqs.extend(spec.search_package());
+    if spec.cli.framework == CliFramework::Typer {
+        reqs.push("typer");
+    }
+    if spec.cli.ui == CliUi::Rich {
+        reqs.push("rich");
+    }
+    if spec.cli.input == CliInput::PromptToolkit {
+        reqs.push("prompt_toolkit");
+    }
+    if spec.has_capability(Capability::Vision) {
+        reqs.push("pillow");
+    }
+    if spec.has_capability(Capability::Voice) {
+        reqs.extend(["sounddevice", "numpy", "faster-whisper"]);
+        if spec.voice.tts {
+            reqs.extend(["pyttsx3", "soundfile"]);
+        }
+    }
+    if spec.memory.is_some() {
+        reqs.extend(["sentence-transformers", "numpy"]);
+    }
+    if matches!(
+        spec.agent.response_format,
+        Some(ResponseFormat::JsonSchema { .. })
+    ) {
+        reqs.push("jsonschema");
+    }
+    if spec.worker.enabled {
+        reqs.push(match spec.worker.backend {
+            WorkerBackend::Rq => "rq>=1.12",
+            WorkerBackend::Celery => "celery[redis]",
+        });
+    }
+    if spec
+        .cache
+        .as_ref()
+        .is_some_and(|c| c.backend == CacheBackend::Redis)
+    {
+        reqs.push("redis");
+    }
+    reqs.extend(spec.agent.tools.iter().flat_map(|t| t.requirements()));
+    // Features and tools share packages, such as numpy for voice and memory
+    let mut seen = HashSet::new();
+    reqs.retain(|req| seen.insert(*req));
+    let mut out = reqs.join("\n");
+    out.push('\n');
+    out
+}
@@ -0,0 +1,190 @@
+//! `lang: go`: a Go module running the agent loop against any
+//! OpenAI-compatible Chat Completions server, with a typed stub per tool
+//! function for the user to fill in.
+
+use crate::generate::{env_vars, GeneratedFile, Renderer};
+use crate::graph;
+use crate::spec::Spec;
+use crate::tools::Function;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+/// Words Go spells in capitals inside identifiers.
+const INITIALISMS: &[&str] = &["api", "cc", "http", "id", "json", "sql", "url"];
+
+/// `snake_case` as an exported Go identifier: `read_file` becomes `ReadFile`.
+fn go_ident(s: &str) -> String {
+    let mut ident: String = s
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            if INITIALISMS.contains(&part) {
+                return part.to_ascii_uppercase();
+            }
+            let mut chars = part.chars();
+            let first = chars.next().expect("parts are not empty");
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, 'X');
+    }
+    ident
+}
+
+/// A Go interpreted string literal; JSON string escapes are valid Go.
+fn go_str(s: &str) -> String {
+    serde_json::to_string(s).expect("strings serialize")
+}
+
+/// Template context for one function: its Go names, typed fields and the
+/// JSON Schema the model is shown.
+fn function_context(function: &Function) -> Value {
+    let method = go_ident(&function.name);
+    let params: Vec<Value> = function
+        .params
+        .iter()
+        .map(|p| {
+            let go_type = match p.kind {
+                "integer" => "int",
+                "boolean" => "bool",
+                _ => "string",
+            };
+            json!({
+                "name": p.name,
+                "field": go_ident(p.name),
+                "go_type": if p.optional { format!("*{go_type}") } else { go_type.to_string() },
+                "description": p.description,
+                "optional": p.optional,
+            })
+        })
+        .collect();
+    let properties: serde_json::Map<String, Value> = function
+        .params
+        .iter()
+        .map(|p| {
+            (
+                p.name.to_string(),
+                json!({ "type": p.kind, "description": p.description }),
+            )
+        })
+        .collect();
+    let required: Vec<&str> = function
+        .params
+        .iter()
+        .filter(|p| !p.optional)
+        .map(|p| p.name)
+        .collect();
+    let schema = json!({ "type": "object", "properties": properties, "required": required });
+    json!({
+        "name": function.name,
+        "name_literal": go_str(&function.name),
+        "method": method,
+        "args": format!("{method}Args"),
+        "description": function.description.replace('\n', " "),
+        "description_literal": go_str(&function.description),
+        "params": params,
+        "schema": go_str(&schema.to_string()),
+    })
+}
+
+pub(crate) fn render_files(tera: &Renderer, spec: &Spec) -> Result<Vec<GeneratedFile>> {
+    let mut files = Vec::new();
+    let names = spec.names();
+
+    // Generate go.mod
+    let mut ctx = tera::Context::new();
+    ctx.insert("module", &names.slug);
+    let go_mod = tera
+        .render("go.mod", &ctx)
+        .context("rendering go.mod template")?;
+    files.push(GeneratedFile::new("go.mod", go_mod));
+
+    // Generate main.go, client.go and config.go
+    let mut ctx = tera::Context::new();
+    ctx.insert("display_name", &go_str(&names.display));
+    for name in ["main.go", "client.go", "config.go"] {
+        let rendered = tera
+            .render(name, &ctx)
+            .with_context(|| format!("rendering {name} template"))?;
+        files.push(GeneratedFile::new(name, rendered));
+    }
+
+    // Generate agent.go
+    let instructions = "Answer the request, calling the tools when they help. \
+                        Once you have the answer, reply with it and call no tools.";
+    let system_message = match &spec.agent.description {
+        Some(description) => format!("{description}\n\n{instructions}"),
+        None => instructions.to_string(),
+    };
+    let mut ctx = tera::Context::new();
+    ctx.insert("system_message", &go_str(&system_message));
+    ctx.insert("model_id", &spec.openai.model);
+    ctx.insert("max_steps", &spec.openai.max_steps);
+    let agent_go = tera
+        .render("agent.go", &ctx)
+        .context("rendering agent.go template")?;
+    files.push(GeneratedFile::new("agent.go", agent_go));
+
+    // Generate tools.go, and a stub file per tool for the user to fill in
+    let mut all_functions = Vec::new();
+    let mut readme_tools = Vec::new();
+    for tool in &spec.agent.tools {
+        let functions: Vec<Value> = tool.functions().iter().map(function_context).collect();
+        let file = format!("tool_{}.go", tool.kind().name().replace('-', "_"));
+        let options = match serde_json::to_value(&tool.tool).context("serializing tool options")? {
+            Value::Object(options) => options.values().next().map(Value::to_string),
+            _ => None,
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("functions", &functions);
+        ctx.insert("options", &options);
+        let stub = tera
+            .render("tool.go", &ctx)
+            .context("rendering tool.go template")?;
+        files.push(GeneratedFile::scaffold(&file, stub));
+        let function_names: Vec<&Value> = functions.iter().map(|f| &f["name"]).collect();
+        readme_tools.push(json!({ "file": file, "functions": function_names }));
+        all_functions.extend(functions);
+    }
+    let mut ctx = tera::Context::new();
+    ctx.insert("functions", &all_functions);
+    let tools_go = tera
+        .render("tools.go", &ctx)
+        .context("rendering tools.go template")?;
+    files.push(GeneratedFile::new("tools.go", tools_go));
+
+    // Generate README.md
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", &spec.agent.name);
+    ctx.insert("description", &spec.agent.description);
+    ctx.insert("binary", &names.slug);
+    ctx.insert("tools", &readme_tools);
+    let readme = tera
+        .render("README_go.md", &ctx)
+        .context("rendering README_go.md template")?;
+    files.push(GeneratedFile::new("README.md", readme));
+
+    // Generate architecture.md
+    if spec.docs.architecture {
+        files.push(GeneratedFile::new(
+            "architecture.md",
+            graph::architecture_md(spec),
+        ));
+    }
+
+    // Generate .env.example
+    let env: Vec<String> = env_vars(spec)
+        .iter()
+        .map(|var| format!("# {}\n{}={}\n", var.comment, var.name, var.example))
+        .collect();
+    files.push(GeneratedFile::new(".env.example", env.join("\n")));
+
+    // Keep the built binary out of the repository
+    files.push(GeneratedFile::new(
+        ".gitignore",
+        format!("/{}\n.env\n", names.slug),
+    ));
+
+    Ok(files)
+}
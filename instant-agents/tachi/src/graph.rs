@@ -0,0 +1,116 @@
+use crate::spec::{Framework, Lang, Spec};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Mermaid,
+    Dot,
+}
+
+/// Diagram of the agent, its model provider and its tools. Web tools wrapped
+/// by the injection filter get a `guarded` edge.
+pub fn graph(spec: &Spec, format: GraphFormat) -> String {
+    let guarded = spec.wants_injection_filter();
+    let agent = &spec.agent;
+    let (agent_class, model_class) = match spec.framework {
+        Framework::Smolagents => ("CodeAgent", spec.model_class()),
+        Framework::OpenaiAssistants if spec.lang == Lang::Go => {
+            ("Agent", "OpenAI-compatible Chat Completions")
+        }
+        Framework::OpenaiAssistants => ("ResponsesAgent", "OpenAI Responses API"),
+        Framework::Autogen if spec.router.is_some() => ("GroupChatManager", "AG2 OpenAI client"),
+        Framework::Autogen => ("ConversableAgent", "AG2 OpenAI client"),
+        Framework::Llamaindex => ("ReActAgent", "HuggingFaceInferenceAPI"),
+        Framework::SemanticKernel => ("Kernel", "OpenAI chat completion"),
+        Framework::SpringAi => ("Agent", "Spring AI OpenAI ChatModel"),
+    };
+    let model_label = format!("{}\n{model_class}", spec.model_id());
+    let mut tools: Vec<(String, String, &str)> = agent
+        .tools
+        .iter()
+        .flat_map(|t| {
+            let edge = if guarded && t.kind().fetches_web_content() {
+                "guarded tool"
+            } else {
+                "tool"
+            };
+            // Python classes, C# plugin classes, or Go's and Java's stub functions
+            let names: Vec<String> = match spec.lang {
+                Lang::Python => t.py_classes().into_iter().map(String::from).collect(),
+                Lang::Csharp => t
+                    .cs_plugin()
+                    .map(|plugin| plugin.class.to_string())
+                    .into_iter()
+                    .collect(),
+                Lang::Go | Lang::Java => t.functions().into_iter().map(|f| f.name).collect(),
+            };
+            names
+                .into_iter()
+                .map(move |name| (format!("tool_{name}"), name, edge))
+        })
+        .collect();
+    if spec.knowledge.is_some() {
+        tools.push((
+            "tool_knowledge".to_string(),
+            "QueryEngineTool".to_string(),
+            "knowledge",
+        ));
+    }
+
+    let mut out = String::new();
+    match format {
+        GraphFormat::Mermaid => {
+            let label = |s: &str| s.replace('"', "#quot;").replace('\n', "<br/>");
+            out.push_str("flowchart LR\n");
+            let _ = writeln!(
+                out,
+                "    agent[\"{}\"]",
+                label(&format!("{}\n{agent_class}", agent.name))
+            );
+            let _ = writeln!(out, "    model([\"{}\"])", label(&model_label));
+            out.push_str("    agent -->|model| model\n");
+            for (id, name, edge) in &tools {
+                let _ = writeln!(out, "    {id}[[\"{}\"]]", label(name));
+                let _ = writeln!(out, "    agent -->|{edge}| {id}");
+            }
+        }
+        GraphFormat::Dot => {
+            let label = |s: &str| {
+                s.replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            };
+            out.push_str("digraph agent {\n    rankdir=LR;\n");
+            let _ = writeln!(
+                out,
+                "    agent [label=\"{}\", shape=box];",
+                label(&format!("{}\n{agent_class}", agent.name))
+            );
+            let _ = writeln!(
+                out,
+                "    model [label=\"{}\", shape=ellipse];",
+                label(&model_label)
+            );
+            out.push_str("    agent -> model [label=\"model\"];\n");
+            for (id, name, edge) in &tools {
+                let _ = writeln!(
+                    out,
+                    "    {id} [label=\"{}\", shape=component];",
+                    label(name)
+                );
+                let _ = writeln!(out, "    agent -> {id} [label=\"{edge}\"];");
+            }
+            out.push_str("}\n");
+        }
+    }
+    out
+}
+
+/// `architecture.md` for the generated project: the Mermaid graph in a fence.
+pub(crate) fn architecture_md(spec: &Spec) -> String {
+    format!(
+        "# {} architecture\n\n```mermaid\n{}```\n",
+        spec.agent.name,
+        graph(spec, GraphFormat::Mermaid)
+    )
+}
@@ -0,0 +1,257 @@
+use crate::spec::{Model, Tool, ToolKind};
+use anyhow::{bail, Context, Result};
+use serde_yaml_ng::Value;
+
+/// A best-effort spec plus everything that could not be carried over.
+#[derive(Debug)]
+pub struct Imported {
+    pub yaml: String,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    /// A smolagents `agent.py`
+    Smolagents,
+    /// A LangChain agent config saved as YAML or JSON
+    LangChain,
+}
+
+impl ImportSource {
+    /// Guess from the file extension: `.py` is smolagents, anything else LangChain.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("py") => ImportSource::Smolagents,
+            _ => ImportSource::LangChain,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Draft {
+    tools: Vec<Tool>,
+    model: Option<Model>,
+    injection_filter: bool,
+    notes: Vec<String>,
+}
+
+/// Convert `source` into a tachi spec for an agent called `name`.
+pub fn import(source: &str, kind: ImportSource, name: &str) -> Result<Imported> {
+    let draft = match kind {
+        ImportSource::Smolagents => from_smolagents(source),
+        ImportSource::LangChain => from_langchain(source)?,
+    };
+    let imported = draft.finish(name, kind)?;
+    crate::parse_spec(&imported.yaml).context("imported spec does not validate")?;
+    Ok(imported)
+}
+
+impl Draft {
+    fn add_tool(&mut self, tool: Tool) {
+        if !self.tools.contains(&tool) {
+            self.tools.push(tool);
+        }
+    }
+
+    fn set_model_id(&mut self, id: &str) {
+        let model = Model::ALL.iter().find(|m| m.model_id() == id);
+        if model.is_none() {
+            self.notes.push(format!(
+                "model {id:?} has no tachi equivalent; using the default model"
+            ));
+        }
+        self.model = Some(model.copied().unwrap_or(Model::QwenCoder));
+    }
+
+    fn finish(mut self, name: &str, kind: ImportSource) -> Result<Imported> {
+        let model = match self.model {
+            Some(model) => model,
+            None => {
+                self.notes
+                    .push("no model found; using the default model".to_string());
+                Model::QwenCoder
+            }
+        };
+
+        let origin = match kind {
+            ImportSource::Smolagents => "a smolagents agent.py",
+            ImportSource::LangChain => "a LangChain config",
+        };
+        let mut yaml = format!("# Imported from {origin} by `tachi import`\n");
+        for note in &self.notes {
+            yaml.push_str(&format!("# TODO: {note}\n"));
+        }
+        let tools: Vec<String> = self.tools.iter().map(scalar).collect::<Result<_>>()?;
+        yaml.push_str(&format!(
+            "agent:\n  name: {}\n  tools: [{}]\n  model: {}\n",
+            scalar(&name)?,
+            tools.join(", "),
+            scalar(&model)?
+        ));
+        if self.injection_filter {
+            yaml.push_str("guardrails:\n  injection-filter: true\n");
+        }
+        Ok(Imported {
+            yaml,
+            notes: self.notes,
+        })
+    }
+}
+
+/// One YAML scalar, quoted only if it has to be.
+fn scalar<T: serde::Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(serde_yaml_ng::to_string(value)?.trim_end().to_string())
+}
+
+// ----------------------
+// smolagents
+// ----------------------
+
+/// What an agent.py wires up: tool classes, model id and whether web tools
+/// are wrapped in `guard(...)`.
+pub(crate) struct AgentScan<'a> {
+    pub tools: Vec<&'a str>,
+    pub model_classes: Vec<&'a str>,
+    pub agent_classes: Vec<&'a str>,
+    pub model_id: Option<&'a str>,
+    pub guarded: bool,
+}
+
+pub(crate) fn scan_agent_py(source: &str) -> AgentScan<'_> {
+    AgentScan {
+        tools: called_classes(source, "Tool"),
+        model_classes: called_classes(source, "Model"),
+        agent_classes: called_classes(source, "Agent"),
+        model_id: keyword_string(source, "model_id"),
+        guarded: source.contains("from guardrails import guard"),
+    }
+}
+
+fn from_smolagents(source: &str) -> Draft {
+    let scan = scan_agent_py(source);
+    let mut draft = Draft::default();
+
+    for class in scan.tools {
+        match Tool::from_py_class(class) {
+            Some(tool) => draft.add_tool(tool),
+            None => draft
+                .notes
+                .push(format!("tool {class} is not supported and was dropped")),
+        }
+    }
+    for class in scan.model_classes {
+        if class != "InferenceClientModel" && class != "HfApiModel" {
+            draft.notes.push(format!(
+                "model class {class} is not supported; tachi uses Hugging Face inference"
+            ));
+        }
+    }
+    for class in scan.agent_classes {
+        if class != "CodeAgent" {
+            draft.notes.push(format!("{class} becomes a CodeAgent"));
+        }
+    }
+    if let Some(id) = scan.model_id {
+        draft.set_model_id(id);
+    }
+    draft.injection_filter = scan.guarded;
+    draft
+}
+
+/// Names of classes ending in `suffix` that are called somewhere in `source`.
+fn called_classes<'a>(source: &'a str, suffix: &str) -> Vec<&'a str> {
+    let needle = format!("{suffix}(");
+    let mut found = Vec::new();
+    for (at, _) in source.match_indices(&needle) {
+        let end = at + suffix.len();
+        let start = source[..at]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let class = &source[start..end];
+        if class.starts_with(char::is_uppercase) && !found.contains(&class) {
+            found.push(class);
+        }
+    }
+    found
+}
+
+/// The string literal passed as `keyword=` (or assigned to `keyword =`).
+fn keyword_string<'a>(source: &'a str, keyword: &str) -> Option<&'a str> {
+    for (at, _) in source.match_indices(keyword) {
+        let rest = source[at + keyword.len()..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let body = &rest[1..];
+        return body.find(quote).map(|end| &body[..end]);
+    }
+    None
+}
+
+// ----------------------
+// LangChain
+// ----------------------
+
+fn from_langchain(source: &str) -> Result<Draft> {
+    let config: Value = serde_yaml_ng::from_str(source).context("parsing LangChain config")?;
+    let Some(map) = config.as_mapping() else {
+        bail!("LangChain config must be a mapping");
+    };
+    let mut draft = Draft::default();
+
+    for (key, value) in map {
+        match key.as_str().unwrap_or_default() {
+            "tools" | "allowed_tools" => {
+                for tool in value.as_sequence().into_iter().flatten() {
+                    let tool_name = tool
+                        .as_str()
+                        .or_else(|| tool.get("name").and_then(Value::as_str))
+                        .unwrap_or("<unnamed>");
+                    match tool_name {
+                        "ddg-search" | "duckduckgo_search" | "duckduckgo_results_json" => {
+                            draft.add_tool(ToolKind::Search.into())
+                        }
+                        "llm-math" | "Calculator" => draft.add_tool(Tool::Math),
+                        "sql_db_query"
+                        | "sql_db_schema"
+                        | "sql_db_list_tables"
+                        | "sql_db_query_checker" => draft.add_tool(ToolKind::Sql.into()),
+                        "requests_get" | "requests_all" => {
+                            draft.add_tool(ToolKind::Webpage.into());
+                            draft.notes.push(format!(
+                                "{tool_name} became the webpage tool (markdown, GET only)"
+                            ));
+                        }
+                        other => draft
+                            .notes
+                            .push(format!("tool {other} is not supported and was dropped")),
+                    }
+                }
+            }
+            "llm" => {
+                let id = ["model_name", "model", "repo_id", "model_id"]
+                    .iter()
+                    .find_map(|k| value.get(*k).and_then(Value::as_str));
+                match id {
+                    Some(id) => draft.set_model_id(id),
+                    None => draft.notes.push("llm has no model name".to_string()),
+                }
+            }
+            "_type" | "agent_type" => {
+                if let Some(kind) = value.as_str() {
+                    draft
+                        .notes
+                        .push(format!("agent type {kind} becomes a smolagents CodeAgent"));
+                }
+            }
+            other => draft
+                .notes
+                .push(format!("key {other:?} has no tachi equivalent")),
+        }
+    }
+    Ok(draft)
+}
@@ -0,0 +1,300 @@
+//! `lang: java`: a Maven project running the agent as a Spring Boot service
+//! on Spring AI, with the tool functions as `@Tool` methods that hand off to
+//! a stub bean per tool for the user to fill in.
+
+use crate::generate::{env_vars, GeneratedFile, Renderer};
+use crate::graph;
+use crate::spec::Spec;
+use crate::tools::Function;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+/// Java keywords that are otherwise fine package, method or parameter names.
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "try",
+    "void",
+    "volatile",
+    "while",
+    "true",
+    "false",
+    "null",
+];
+
+/// Package of the generated classes: the slug in snake_case, so
+/// `ops-console` becomes `ops_console`.
+fn package(spec: &Spec) -> String {
+    let mut name = spec
+        .names()
+        .slug
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push_str("_app");
+    }
+    name
+}
+
+/// `snake_case` as a Java identifier; `upper` capitalizes the first word
+/// too, for class names.
+fn java_ident(s: &str, upper: bool) -> String {
+    let mut ident = String::new();
+    for (i, part) in s
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .enumerate()
+    {
+        let mut chars = part.chars();
+        let first = chars.next().expect("parts are not empty");
+        if i == 0 && !upper {
+            ident.push(first);
+        } else {
+            ident.push(first.to_ascii_uppercase());
+        }
+        ident.push_str(chars.as_str());
+    }
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+/// A Java string literal; the JSON string escapes serde_json writes are
+/// valid Java.
+fn java_str(s: &str) -> String {
+    serde_json::to_string(s).expect("strings serialize")
+}
+
+/// Template context for one function: its Java method and typed parameters.
+/// Parameters keep the spec's names, which Spring AI shows the model.
+fn function_context(function: &Function) -> Value {
+    let params: Vec<Value> = function
+        .params
+        .iter()
+        .map(|p| {
+            let java_type = match (p.kind, p.optional) {
+                ("integer", false) => "int",
+                ("integer", true) => "Integer",
+                ("boolean", false) => "boolean",
+                ("boolean", true) => "Boolean",
+                _ => "String",
+            };
+            let mut name = p.name.to_string();
+            if KEYWORDS.contains(&p.name) {
+                name.push('_');
+            }
+            json!({
+                "name": name,
+                "java_type": java_type,
+                "description_literal": java_str(p.description),
+                "optional": p.optional,
+            })
+        })
+        .collect();
+    json!({
+        "name": function.name,
+        "name_literal": java_str(&function.name),
+        "method": java_ident(&function.name, false),
+        // For a Javadoc comment, which `*/` would end early
+        "description": function.description.replace('\n', " ").replace("*/", "* /"),
+        "description_literal": java_str(&function.description),
+        "params": params,
+    })
+}
+
+pub(crate) fn render_files(tera: &Renderer, spec: &Spec) -> Result<Vec<GeneratedFile>> {
+    let mut files = Vec::new();
+    let names = spec.names();
+    let package = package(spec);
+    let source_dir = format!("src/main/java/{package}");
+
+    // Generate pom.xml
+    let mut ctx = tera::Context::new();
+    ctx.insert("artifact", &names.slug);
+    ctx.insert("package", &package);
+    ctx.insert("description", &spec.agent.description);
+    let pom = tera
+        .render("pom.xml", &ctx)
+        .context("rendering pom.xml template")?;
+    files.push(GeneratedFile::new("pom.xml", pom));
+
+    // Generate application.properties
+    let mut ctx = tera::Context::new();
+    ctx.insert("max_steps", &spec.spring_ai.max_steps);
+    let properties = tera
+        .render("application.properties", &ctx)
+        .context("rendering application.properties template")?;
+    files.push(GeneratedFile::new(
+        "src/main/resources/application.properties",
+        properties,
+    ));
+
+    // Generate Application.java
+    let mut ctx = tera::Context::new();
+    ctx.insert("package", &package);
+    ctx.insert("display_name", &java_str(&names.display));
+    let application = tera
+        .render("Application.java", &ctx)
+        .context("rendering Application.java template")?;
+    files.push(GeneratedFile::new(
+        &format!("{source_dir}/Application.java"),
+        application,
+    ));
+
+    // Generate Agent.java
+    let instructions = "Answer the request, calling the tools when they help. \
+                        Once you have the answer, reply with it and call no tools.";
+    let system_message = match &spec.agent.description {
+        Some(description) => format!("{description}\n\n{instructions}"),
+        None => instructions.to_string(),
+    };
+    let mut ctx = tera::Context::new();
+    ctx.insert("package", &package);
+    ctx.insert("system_message", &java_str(&system_message));
+    ctx.insert("model_id", &java_str(&spec.spring_ai.model));
+    ctx.insert("has_tools", &!spec.agent.tools.is_empty());
+    let agent = tera
+        .render("Agent.java", &ctx)
+        .context("rendering Agent.java template")?;
+    files.push(GeneratedFile::new(
+        &format!("{source_dir}/Agent.java"),
+        agent,
+    ));
+
+    // Generate AgentTools.java, and a stub bean per tool for the user to fill in
+    let mut beans = Vec::new();
+    for tool in &spec.agent.tools {
+        let kind = tool.kind().name();
+        let class = format!("{}Tool", java_ident(kind, true));
+        let functions: Vec<Value> = tool.functions().iter().map(function_context).collect();
+        let options = match serde_json::to_value(&tool.tool).context("serializing tool options")? {
+            Value::Object(options) => options.values().next().map(Value::to_string),
+            _ => None,
+        };
+        let mut ctx = tera::Context::new();
+        ctx.insert("package", &package);
+        ctx.insert("class", &class);
+        ctx.insert("kind", kind);
+        ctx.insert("functions", &functions);
+        ctx.insert("options", &options);
+        let stub = tera
+            .render("Tool.java", &ctx)
+            .context("rendering Tool.java template")?;
+        files.push(GeneratedFile::scaffold(
+            &format!("{source_dir}/{class}.java"),
+            stub,
+        ));
+        beans.push(json!({
+            "class": class,
+            "field": java_ident(kind, false),
+            "functions": functions,
+        }));
+    }
+    let mut ctx = tera::Context::new();
+    ctx.insert("package", &package);
+    ctx.insert("beans", &beans);
+    let agent_tools = tera
+        .render("AgentTools.java", &ctx)
+        .context("rendering AgentTools.java template")?;
+    files.push(GeneratedFile::new(
+        &format!("{source_dir}/AgentTools.java"),
+        agent_tools,
+    ));
+
+    // Generate README.md
+    let readme_tools: Vec<Value> = beans
+        .iter()
+        .map(|bean| {
+            let functions: Vec<&Value> = bean["functions"]
+                .as_array()
+                .expect("functions is an array")
+                .iter()
+                .map(|f| &f["name"])
+                .collect();
+            json!({ "class": bean["class"], "functions": functions })
+        })
+        .collect();
+    let mut ctx = tera::Context::new();
+    ctx.insert("name", &spec.agent.name);
+    ctx.insert("description", &spec.agent.description);
+    ctx.insert("artifact", &names.slug);
+    ctx.insert("source_dir", &source_dir);
+    ctx.insert("tools", &readme_tools);
+    let readme = tera
+        .render("README_java.md", &ctx)
+        .context("rendering README_java.md template")?;
+    files.push(GeneratedFile::new("README.md", readme));
+
+    // Generate architecture.md
+    if spec.docs.architecture {
+        files.push(GeneratedFile::new(
+            "architecture.md",
+            graph::architecture_md(spec),
+        ));
+    }
+
+    // Generate .env.example
+    let env: Vec<String> = env_vars(spec)
+        .iter()
+        .map(|var| format!("# {}\n{}={}\n", var.comment, var.name, var.example))
+        .collect();
+    files.push(GeneratedFile::new(".env.example", env.join("\n")));
+
+    // Keep Maven's build output out of the repository
+    files.push(GeneratedFile::new(".gitignore", "target/\n.env\n"));
+
+    Ok(files)
+}
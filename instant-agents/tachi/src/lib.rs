@@ -0,0 +1,143 @@
+//! tachi: compile a YAML agent spec into an agent project: Python on
+//! smolagents, the OpenAI Responses API, AG2 or LlamaIndex, C# on Semantic
+//! Kernel, Go on an OpenAI-compatible API, or Java on Spring AI.
+//!
+//! The `tachi` binary is a thin CLI over this crate; language bindings use
+//! the same entry points.
+
+// The spec's JSON schema is one json! literal, deeper than the default limit
+#![recursion_limit = "256"]
+
+mod attest;
+mod bundle;
+mod ci;
+mod csharp;
+mod diff;
+mod drift;
+mod error;
+mod fmt;
+mod generate;
+mod golang;
+mod graph;
+mod importer;
+mod java;
+mod lint;
+mod lock;
+mod lsp;
+mod mount;
+mod naming;
+mod pack;
+mod post_gen;
+mod project;
+mod quality;
+mod registry;
+mod report;
+mod schema;
+mod secrets;
+mod self_update;
+mod serve;
+mod snippets;
+mod spec;
+mod tasks;
+mod template_test;
+mod templates;
+mod tools;
+mod verify;
+mod yaml;
+
+pub use attest::{sign, Provenance, SIGNING_KEY_ENV};
+pub use bundle::bundle;
+pub use diff::unified_diff;
+pub use drift::{diff_spec, Drift};
+pub use error::{
+    DriftFound, ErrorKind, ErrorReport, InvalidPython, InvalidSnippets, LintFailed, MergeKeyError,
+    OverwriteConflict, SecretsFound, TemplateTestFailed, Unformatted, ValidationError,
+};
+pub use fmt::format_spec;
+pub use generate::{
+    changes, generate, project_dir, render, template_contexts, FileChange, GenerateOptions,
+    GeneratedFile, Resolution, WriteStatus,
+};
+pub use graph::{graph, GraphFormat};
+pub use importer::{import, ImportSource, Imported};
+pub use lint::{lint, rule_names, Finding, Severity};
+pub use lsp::run_language_server;
+pub use mount::{Mount, WebFramework};
+pub use naming::Names;
+pub use post_gen::{run_post_gen, HookRun};
+pub use registry::{pull_spec, push_spec, Registry, SpecRef, SpecSource, REGISTRY_ENV};
+pub use report::{report, SpecReport};
+pub use schema::json_schema;
+pub use self_update::{self_update, Channel, Update};
+pub use serve::serve;
+pub use snippets::check_python;
+pub use spec::*;
+pub use template_test::{example_names, test_templates, TemplateFailure, TemplateTest};
+pub use templates::{new_template_pack, new_tool_pack, vendor};
+pub use verify::verify;
+
+use anyhow::{bail, Context, Result};
+use yaml::Document;
+
+/// Parse and validate a YAML (or JSON) spec. Anchors, aliases and `<<` merge
+/// keys are resolved before validation.
+pub fn parse_spec(yaml: &str) -> Result<Spec> {
+    let document = Document {
+        first_line: 0,
+        text: yaml,
+    };
+    let spec: Spec = yaml::from_document(&document).context("parsing YAML")?;
+    validate(&spec, &document, "spec")?;
+    Ok(spec)
+}
+
+/// Parse and validate every `---`-separated document in `yaml`, each a full
+/// spec; empty ones are skipped. Two documents may not generate the same
+/// project directory.
+pub fn parse_specs(yaml: &str) -> Result<Vec<Spec>> {
+    let mut specs: Vec<Spec> = Vec::new();
+    for (index, document) in yaml::documents(yaml).iter().enumerate() {
+        let number = index + 1;
+        let Some(spec) = yaml::from_document::<Option<Spec>>(document)
+            .with_context(|| format!("parsing YAML document {number}"))?
+        else {
+            continue;
+        };
+        validate(&spec, document, &format!("document {number}"))?;
+        let slug = spec.names().slug;
+        if let Some(first) = specs.iter().position(|s| s.names().slug == slug) {
+            bail!(
+                "documents {} and {number} both generate {slug}/; give one a different agent.name",
+                first + 1
+            );
+        }
+        specs.push(spec);
+    }
+    if specs.is_empty() {
+        bail!("no spec in the file");
+    }
+    Ok(specs)
+}
+
+/// 1-based line that sets dotted `key` in the document of `yaml` defining
+/// agent `name`, for messages that point back into the spec.
+pub fn spec_line(yaml: &str, name: &str, key: &str) -> Option<usize> {
+    yaml::documents(yaml).iter().find_map(|document| {
+        let spec = yaml::from_document::<Option<Spec>>(document).ok()??;
+        if spec.agent.name != name {
+            return None;
+        }
+        yaml::key_line(document.text, key).map(|line| document.first_line + line + 1)
+    })
+}
+
+/// `spec.validate()`, naming the line that sets the offending key; for a key
+/// that comes from an anchor, the line in the anchor.
+fn validate(spec: &Spec, document: &Document, what: &str) -> Result<()> {
+    spec.validate().map_err(|err| {
+        let at = yaml::key_line(document.text, err.key)
+            .map(|line| format!(" ({} at line {})", err.key, document.first_line + line + 1))
+            .unwrap_or_default();
+        anyhow::Error::new(err).context(format!("validating {what}{at}"))
+    })
+}
@@ -0,0 +1,252 @@
+use crate::spec::{Capability, Framework, Model, Spec, Tool, ToolKind, Voice};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// One rule violation. `key` is the dotted spec path it points at.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.severity.label(),
+            self.rule,
+            self.message
+        )
+    }
+}
+
+struct Rule {
+    name: &'static str,
+    severity: Severity,
+    key: &'static str,
+    check: fn(&Spec) -> Option<String>,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        name: "no-tools",
+        severity: Severity::Warning,
+        key: "agent.tools",
+        check: |spec| {
+            spec.agent.tools.is_empty().then(|| match spec.framework {
+                Framework::Smolagents => "agent has no tools and can only run Python".into(),
+                _ => "agent has no tools and can only answer from the model".into(),
+            })
+        },
+    },
+    Rule {
+        name: "webpage-without-search",
+        severity: Severity::Warning,
+        key: "agent.tools",
+        check: |spec| {
+            (spec.has_tool(ToolKind::Webpage) && !spec.has_tool(ToolKind::Search)).then(|| {
+                "webpage tool without search: the agent can only visit URLs it is given".into()
+            })
+        },
+    },
+    Rule {
+        name: "unguarded-web-content",
+        severity: Severity::Info,
+        key: "agent.tools",
+        check: |spec| {
+            let web = spec
+                .agent
+                .tools
+                .iter()
+                .any(|t| t.kind().fetches_web_content());
+            (web && !spec.guardrails.injection_filter).then(|| {
+                "web tools feed third-party text to the model; consider guardrails.injection-filter"
+                    .into()
+            })
+        },
+    },
+    Rule {
+        name: "sql-without-scheme",
+        severity: Severity::Info,
+        key: "agent.tools",
+        check: |spec| {
+            spec.agent.tools.iter().find_map(|t| match &t.tool {
+                Tool::Sql(sql) if sql.scheme.is_none() => Some(
+                    "sql tool has no scheme, so no database driver is installed and only SQLite works"
+                        .into(),
+                ),
+                _ => None,
+            })
+        },
+    },
+    Rule {
+        name: "shell-allows-interpreter",
+        severity: Severity::Warning,
+        key: "agent.tools",
+        check: |spec| {
+            spec.agent.tools.iter().find_map(|t| match &t.tool {
+                Tool::Shell(shell) => shell
+                    .allow
+                    .iter()
+                    .find(|c| is_interpreter(c))
+                    .map(|c| format!("shell tool allows {c}, which runs anything it is given")),
+                _ => None,
+            })
+        },
+    },
+    Rule {
+        name: "voice-without-capability",
+        severity: Severity::Warning,
+        key: "voice",
+        check: |spec| {
+            (spec.voice != Voice::default() && !spec.has_capability(Capability::Voice))
+                .then(|| "voice settings have no effect without capabilities: [voice]".into())
+        },
+    },
+    Rule {
+        name: "missing-description",
+        severity: Severity::Warning,
+        key: "agent.description",
+        check: |spec| {
+            (spec.a2a.enabled && spec.agent.description.is_none()).then(|| {
+                "the A2A agent card tells other agents what this one is for; set agent.description"
+                    .into()
+            })
+        },
+    },
+    Rule {
+        name: "flaky-tool-model",
+        severity: Severity::Warning,
+        key: "agent.model",
+        check: |spec| {
+            FLAKY_COMBINATIONS
+                .iter()
+                .find_map(|&(model, kind, problem)| {
+                    (spec.agent.model == model && spec.has_tool(kind))
+                        .then(|| format!("{kind} tool on {}: {problem}", model.model_id()))
+                })
+        },
+    },
+    Rule {
+        name: "no-max-steps",
+        severity: Severity::Info,
+        key: "agent.max-steps",
+        check: |spec| {
+            (spec.framework == Framework::Smolagents && spec.agent.max_steps.is_none()).then(|| {
+                "agent.max-steps is unset, so runs stop only at smolagents' default of 20 steps"
+                    .into()
+            })
+        },
+    },
+    Rule {
+        name: "high-temperature",
+        severity: Severity::Warning,
+        key: "agent.temperature",
+        check: |spec| {
+            spec.agent
+                .temperature
+                .filter(|&t| t > 1.0 && spec.framework == Framework::Smolagents)
+                .map(|t| {
+                    format!("temperature {t} makes a code agent write broken code; use 1 or less")
+                })
+        },
+    },
+    Rule {
+        name: "deprecated-key",
+        severity: Severity::Warning,
+        key: "lint.allow",
+        check: |spec| {
+            (!spec.lint.allow.is_empty())
+                .then(|| "lint.allow is deprecated; move its rules to the top-level allow".into())
+        },
+    },
+    Rule {
+        name: "name-not-slug",
+        severity: Severity::Info,
+        key: "agent.name",
+        check: |spec| {
+            let names = spec.names();
+            (spec.agent.slug.is_none() && names.slug != names.display).then(|| {
+                format!(
+                    "agent.name {:?} is written to directory {:?}; set agent.slug to choose it",
+                    names.display, names.slug
+                )
+            })
+        },
+    },
+];
+
+/// Tools a model is known to drive badly, and how it goes wrong.
+const FLAKY_COMBINATIONS: &[(Model, ToolKind, &str)] = &[
+    (
+        Model::QwenVl,
+        ToolKind::Sql,
+        "the vision model often writes invalid SQL; keep qwen-coder for database work",
+    ),
+    (
+        Model::QwenVl,
+        ToolKind::Shell,
+        "the vision model often passes malformed arguments; keep qwen-coder for shell work",
+    ),
+];
+
+/// Programs that run arbitrary code given as arguments.
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "fish", "dash", "ksh", "env", "xargs", "sudo", "python", "node", "perl",
+    "ruby", "php", "deno", "bun",
+];
+
+/// `python3.12` and `/bin/bash` count as `python` and `bash`.
+fn is_interpreter(command: &str) -> bool {
+    let name = command.rsplit('/').next().unwrap_or(command);
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.contains(&name)
+}
+
+/// Names of every lint rule, for `lint.allow` validation.
+pub fn rule_names() -> impl Iterator<Item = &'static str> {
+    RULES.iter().map(|r| r.name)
+}
+
+/// Run every rule not listed in the spec's `allow` (or `lint.allow`).
+pub fn lint(spec: &Spec) -> Vec<Finding> {
+    RULES
+        .iter()
+        .filter(|rule| {
+            !spec
+                .allow
+                .iter()
+                .chain(&spec.lint.allow)
+                .any(|a| a == rule.name)
+        })
+        .filter_map(|rule| {
+            (rule.check)(spec).map(|message| Finding {
+                rule: rule.name,
+                severity: rule.severity,
+                key: rule.key,
+                message,
+            })
+        })
+        .collect()
+}
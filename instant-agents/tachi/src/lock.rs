@@ -0,0 +1,117 @@
+use crate::pack;
+use crate::registry::SpecSource;
+use crate::spec::Spec;
+use crate::templates::{self, toml_escape};
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Project file recording what the project was generated with.
+pub(crate) const LOCK_FILE: &str = "tachi.lock";
+
+fn sha256(data: impl AsRef<[u8]>) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+/// tachi.lock for `spec`: the tachi version, a hash of every template, of
+/// every asset the template pack copies in and of the registry entry behind
+/// each of the spec's tools. Any of them
+/// changing can change the generated code. A spec pulled from a registry
+/// adds its name, tag and digest, so a tag that moved fails `--locked`.
+pub(crate) fn render_lock(
+    spec: &Spec,
+    overrides: Option<&Path>,
+    source: Option<&SpecSource>,
+) -> Result<String> {
+    let mut out = format!(
+        "# Written by `tachi gen`; commit it. `tachi gen --locked` fails when\n\
+         # regenerating would change it.\n\
+         version = 1\n\
+         tachi = \"{}\"\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    if let Some(source) = source {
+        out.push_str("\n[spec]\n");
+        out.push_str(&format!(
+            "registry = \"{}\"\n",
+            toml_escape(&source.registry)
+        ));
+        out.push_str(&format!("name = \"{}\"\n", toml_escape(&source.name)));
+        if let Some(tag) = &source.tag {
+            out.push_str(&format!("tag = \"{}\"\n", toml_escape(tag)));
+        }
+        out.push_str(&format!("digest = \"{}\"\n", toml_escape(&source.digest)));
+    }
+    out.push_str("\n[templates]\n");
+    let mut hashes = templates::hashes(overrides)?;
+    hashes.sort();
+    for (name, hash) in hashes {
+        out.push_str(&format!("\"{}\" = \"{hash}\"\n", toml_escape(&name)));
+    }
+    let assets = match overrides {
+        Some(dir) => pack::assets(dir)?,
+        None => Vec::new(),
+    };
+    if !assets.is_empty() {
+        out.push_str("\n[assets]\n");
+        for (name, contents) in assets {
+            out.push_str(&format!(
+                "\"{}\" = \"{}\"\n",
+                toml_escape(&name),
+                sha256(contents)
+            ));
+        }
+    }
+    out.push_str("\n[tools]\n");
+    for tool in &spec.agent.tools {
+        let mut entry = format!("since {}\n", tool.kind().since());
+        for class in tool.py_classes() {
+            entry.push_str(&format!("class {class}\n"));
+        }
+        for req in tool.requirements() {
+            entry.push_str(&format!("requires {req}\n"));
+        }
+        for var in tool.env_vars() {
+            entry.push_str(&format!("env {}={}\n", var.name, var.example));
+        }
+        out.push_str(&format!("{} = \"{}\"\n", tool.kind(), sha256(entry)));
+    }
+    Ok(out)
+}
+
+/// Fail unless `project_dir/tachi.lock` already reads `expected`, naming
+/// the entries that would change.
+pub(crate) fn check_locked(project_dir: &Path, expected: &str) -> Result<()> {
+    let path = project_dir.join(LOCK_FILE);
+    let existing = match fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!(
+                "--locked needs {}; generate once without it",
+                path.display()
+            )
+        }
+        Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+    };
+    if existing == expected {
+        return Ok(());
+    }
+    let entry = |line: &&str| !line.is_empty() && !line.starts_with('#');
+    let mut changes = Vec::new();
+    for line in existing.lines().filter(entry) {
+        if !expected.lines().any(|l| l == line) {
+            changes.push(format!("  - {line}"));
+        }
+    }
+    for line in expected.lines().filter(entry) {
+        if !existing.lines().any(|l| l == line) {
+            changes.push(format!("  + {line}"));
+        }
+    }
+    bail!(
+        "{} would change with --locked:\n{}",
+        path.display(),
+        changes.join("\n")
+    )
+}
@@ -0,0 +1,343 @@
+use crate::error::{MergeKeyError, ValidationError};
+use crate::lint::{self, Severity};
+use crate::schema;
+use crate::spec::Spec;
+use crate::yaml::{self, indent_of, is_blank, key_line, key_of, Document};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// LSP enums
+const SYNC_FULL: u8 = 1;
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const SEVERITY_INFO: u8 = 3;
+const KIND_PROPERTY: u8 = 10;
+const KIND_VALUE: u8 = 12;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Speak the Language Server Protocol on stdin/stdout until the client exits.
+/// Diagnostics come from the same parser, validation and lint rules as
+/// `tachi gen`; completion and hover read the JSON schema.
+pub fn run_language_server() -> Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout().lock();
+    let schema = schema::json_schema();
+    let mut docs: HashMap<String, String> = HashMap::new();
+
+    while let Some(msg) = read_message(&mut input)? {
+        // Messages without a method are responses; we never send requests
+        let Some(method) = msg.get("method").and_then(Value::as_str) else {
+            continue;
+        };
+        let id = msg.get("id").cloned();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": SYNC_FULL,
+                    "completionProvider": { "triggerCharacters": [":", " ", "-", "["] },
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "tachi", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = if method == "textDocument/didOpen" {
+                    params["textDocument"]["text"].as_str()
+                } else {
+                    params["contentChanges"]
+                        .as_array()
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change["text"].as_str())
+                };
+                let text = text.unwrap_or_default().to_string();
+                publish(&mut output, &uri, diagnostics(&text))?;
+                docs.insert(uri, text);
+                continue;
+            }
+            "textDocument/didClose" => {
+                docs.remove(&uri);
+                publish(&mut output, &uri, Vec::new())?;
+                continue;
+            }
+            "textDocument/completion" => match cursor(&docs, &params) {
+                Some((text, line, character)) => {
+                    Value::Array(completions(&schema, text, line, character))
+                }
+                None => Value::Null,
+            },
+            "textDocument/hover" => cursor(&docs, &params)
+                .and_then(|(text, line, character)| hover(&schema, text, line, character))
+                .unwrap_or(Value::Null),
+            "shutdown" => Value::Null,
+            "exit" => return Ok(()),
+            _ => {
+                if let Some(id) = id {
+                    send(
+                        &mut output,
+                        &json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": {
+                                "code": METHOD_NOT_FOUND,
+                                "message": format!("unhandled method {method}"),
+                            },
+                        }),
+                    )?;
+                }
+                continue;
+            }
+        };
+        if let Some(id) = id {
+            send(
+                &mut output,
+                &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .context("bad Content-Length")?,
+            );
+        }
+    }
+    let mut body = vec![0; length.context("LSP message without Content-Length")?];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .context("malformed LSP message")
+}
+
+fn send(output: &mut impl Write, msg: &Value) -> Result<()> {
+    let body = msg.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()?;
+    Ok(())
+}
+
+fn publish(output: &mut impl Write, uri: &str, diagnostics: Vec<Value>) -> Result<()> {
+    send(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }),
+    )
+}
+
+fn cursor<'a>(
+    docs: &'a HashMap<String, String>,
+    params: &Value,
+) -> Option<(&'a str, usize, usize)> {
+    let text = docs.get(params["textDocument"]["uri"].as_str()?)?;
+    let line = params["position"]["line"].as_u64()? as usize;
+    let character = params["position"]["character"].as_u64()? as usize;
+    Some((text, line, character))
+}
+
+// ----------------------
+// Diagnostics
+// ----------------------
+
+fn diagnostics(text: &str) -> Vec<Value> {
+    let document = Document {
+        first_line: 0,
+        text,
+    };
+    let (line, message) = match yaml::from_document::<Spec>(&document) {
+        Err(err) => (
+            error_line(&err).map_or(0, |line| line.saturating_sub(1)),
+            err.to_string(),
+        ),
+        Ok(spec) => match spec.validate() {
+            Ok(()) => {
+                return lint::lint(&spec)
+                    .into_iter()
+                    .map(|f| {
+                        let line = key_line(text, f.key).unwrap_or(0);
+                        let severity = match f.severity {
+                            Severity::Error => SEVERITY_ERROR,
+                            Severity::Warning => SEVERITY_WARNING,
+                            Severity::Info => SEVERITY_INFO,
+                        };
+                        let mut diagnostic = diagnostic(text, line, severity, f.message);
+                        diagnostic["code"] = f.rule.into();
+                        diagnostic
+                    })
+                    .collect();
+            }
+            Err(ValidationError { key, message }) => (key_line(text, key).unwrap_or(0), message),
+        },
+    };
+    vec![diagnostic(text, line, SEVERITY_ERROR, message)]
+}
+
+/// 1-based line of a spec that failed to deserialize.
+fn error_line(err: &anyhow::Error) -> Option<usize> {
+    if let Some(err) = err.downcast_ref::<serde_yaml_ng::Error>() {
+        return err.location().map(|loc| loc.line());
+    }
+    err.downcast_ref::<MergeKeyError>()?.line
+}
+
+fn diagnostic(text: &str, line: usize, severity: u8, message: String) -> Value {
+    let end = text.lines().nth(line).map_or(0, |l| l.chars().count());
+    json!({
+        "range": {
+            "start": { "line": line, "character": 0 },
+            "end": { "line": line, "character": end },
+        },
+        "severity": severity,
+        "source": "tachi",
+        "message": message,
+    })
+}
+
+// ----------------------
+// Completion and hover
+// ----------------------
+
+fn completions(schema: &Value, text: &str, line: usize, character: usize) -> Vec<Value> {
+    let lines: Vec<&str> = text.lines().collect();
+    let current = lines.get(line).copied().unwrap_or_default();
+    let before: String = current.chars().take(character).collect();
+    let parents = parent_keys(&lines, line, &before);
+    let content = before.trim_start();
+
+    let values = |node: Option<&Value>| -> Vec<Value> {
+        let Some(node) = node else { return Vec::new() };
+        let allowed = enum_of(node)
+            .cloned()
+            .or_else(|| (node["type"] == "boolean").then(|| vec![json!(true), json!(false)]))
+            .unwrap_or_default();
+        allowed
+            .iter()
+            .map(|v| {
+                let label = v.as_str().map_or_else(|| v.to_string(), str::to_string);
+                json!({ "label": label, "kind": KIND_VALUE })
+            })
+            .collect()
+    };
+
+    if let Some(key) = key_of(content) {
+        let mut path = parents;
+        path.push(key.to_string());
+        return values(node_at(schema, &path));
+    }
+    if content.starts_with('-') {
+        return values(node_at(schema, &parents));
+    }
+    let Some(properties) = node_at(schema, &parents).and_then(|n| n["properties"].as_object())
+    else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, node)| {
+            json!({
+                "label": name,
+                "kind": KIND_PROPERTY,
+                "detail": node["description"],
+                "insertText": format!("{name}: "),
+            })
+        })
+        .collect()
+}
+
+fn hover(schema: &Value, text: &str, line: usize, character: usize) -> Option<Value> {
+    let lines: Vec<&str> = text.lines().collect();
+    let current = *lines.get(line)?;
+    let key = key_of(current)?;
+    let start = current.find(key)?;
+    if !(start..=start + key.len()).contains(&character) {
+        return None;
+    }
+    let mut path = parent_keys(&lines, line, current);
+    path.push(key.to_string());
+    let node = node_at(schema, &path)?;
+
+    let mut doc = format!("**{}**", path.join("."));
+    if let Some(description) = node["description"].as_str() {
+        doc.push_str("\n\n");
+        doc.push_str(description);
+    }
+    if let Some(allowed) = enum_of(node) {
+        let allowed: Vec<String> = allowed
+            .iter()
+            .map(|v| format!("`{}`", v.as_str().unwrap_or_default()))
+            .collect();
+        doc.push_str(&format!("\n\nOne of: {}", allowed.join(", ")));
+    }
+    Some(json!({ "contents": { "kind": "markdown", "value": doc } }))
+}
+
+/// Keys enclosing `line`, outermost first, judged by indentation. `current`
+/// is the text of the line as far as it matters (up to the cursor).
+fn parent_keys(lines: &[&str], line: usize, current: &str) -> Vec<String> {
+    let mut indent = indent_of(current);
+    let mut parents = Vec::new();
+    for above in lines[..line.min(lines.len())].iter().rev() {
+        if indent == 0 {
+            break;
+        }
+        if is_blank(above) || indent_of(above) >= indent {
+            continue;
+        }
+        if let Some(key) = key_of(above) {
+            parents.insert(0, key.to_string());
+        }
+        indent = indent_of(above);
+    }
+    parents
+}
+
+fn node_at<'a>(schema: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(schema, |node, key| {
+        node["properties"].get(key).or_else(|| {
+            // List items may be a map of options (`- sql: ...`)
+            node["items"]["anyOf"]
+                .as_array()?
+                .iter()
+                .find_map(|n| n["properties"].get(key))
+        })
+    })
+}
+
+/// Allowed values of a field, or of its list items.
+fn enum_of(node: &Value) -> Option<&Vec<Value>> {
+    let items = &node["items"];
+    node.get("enum")
+        .or_else(|| items.get("enum"))
+        .or_else(|| {
+            items["anyOf"]
+                .as_array()?
+                .iter()
+                .find_map(|n| n.get("enum"))
+        })
+        .and_then(Value::as_array)
+}
@@ -1,23 +1,44 @@
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use tera::Tera;
+use std::process::ExitCode;
+use tachi::{
+    DriftFound, ErrorReport, GenerateOptions, ImportSource, LintFailed, Resolution, Severity, Spec,
+    TemplateTestFailed, Unformatted,
+};
 
 // CLI
 #[derive(Parser)]
-#[command(name = "tachi", version, about = "Compile YAML ->Python smolagent")]
+#[command(
+    name = "tachi",
+    version,
+    about = "Compile YAML ->Python smolagent",
+    after_help = EXIT_CODES_HELP
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// How to print errors on stderr
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human, global = true)]
+    error_format: ErrorFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ErrorFormat {
+    Human,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    /// Generate a Python agent from YAML spec
+    /// Generate an agent project from YAML spec
     Gen {
-        /// Path to YAML spec
+        /// Path to YAML spec; each `---`-separated spec in it generates a project.
+        /// `registry:<name>[:<tag>]` pulls it from the registry in TACHI_REGISTRY
         input: PathBuf,
         /// Input directory (defaults to current directory)
         #[arg(short, long, default_value = ".")]
@@ -25,226 +46,1107 @@ enum Commands {
         /// Output directory (defaults to current directory)
         #[arg(short, long, default_value = ".")]
         out: PathBuf,
+        /// Generate into this existing project rather than a new directory under --out;
+        /// its pyproject.toml takes the requirements and its package.json gets scripts
+        #[arg(long, conflicts_with = "out")]
+        into: Option<PathBuf>,
+        /// Serve the agent from an existing web app in --into, as `fastapi:<app.py>` or
+        /// `django:<urls.py>`, through a generated agent_router.py instead of server.py
+        #[arg(long, requires = "into")]
+        mount: Option<tachi::Mount>,
+        /// Overwrite existing files if present; without it a terminal asks per file
+        #[arg(long)]
+        force: bool,
+        /// Directory of template overrides (`<name>.tera` replaces the built-in `<name>`)
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        /// Print a diff of each existing file that changes (always on with --force)
+        #[arg(long)]
+        diff: bool,
+        /// Fail if tachi.lock would change, writing nothing
+        #[arg(long)]
+        locked: bool,
+        /// Write values that look like API keys or tokens instead of failing
+        #[arg(long)]
+        allow_secrets: bool,
+        /// Fail on warnings instead of generating, for CI
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Run the spec's hooks.post-gen commands without asking
+        #[arg(long)]
+        run_hooks: bool,
+        /// Compile the generated Python with `python3 -m compileall` and fail if it does not parse
+        #[arg(long)]
+        verify: bool,
+        /// Write tachi.intoto.json, an attestation of the spec digest, tachi version and
+        /// file hashes, with a detached SSH signature in tachi.intoto.json.sig
+        #[arg(long)]
+        sign: bool,
+        /// SSH private key --sign signs with (defaults to TACHI_SIGNING_KEY)
+        #[arg(long, requires = "sign")]
+        signing_key: Option<PathBuf>,
+        /// Language to generate, replacing the spec's `lang`
+        #[arg(long, value_enum)]
+        lang: Option<Lang>,
+        /// Library the agent runs on, replacing the spec's `framework`
+        #[arg(long, value_enum)]
+        framework: Option<Framework>,
+    },
+    /// Serve generate/validate/render/schema as JSON-RPC over local HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        addr: String,
+    },
+    /// Run a language server for spec files over stdio
+    Lsp,
+    /// Print a diagram of the agent, its model and tools
+    Graph {
+        /// Path to YAML spec
+        input: PathBuf,
+        #[arg(long, value_enum, default_value_t = GraphFormat::Mermaid)]
+        format: GraphFormat,
+    },
+    /// Check a spec against best-practice rules
+    Lint {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// Lowest severity that makes the command fail
+        #[arg(long, value_enum, default_value_t = FailOn::Warning)]
+        fail_on: FailOn,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
+    /// Convert a smolagents agent.py or LangChain config into a spec on stdout
+    Import {
+        /// agent.py, or a LangChain agent config (.yaml/.json)
+        input: PathBuf,
+        /// Agent name (defaults to the input's directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Print the resolved spec and the context each template is rendered with
+    Explain {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// Only show the context of this template, such as agent.py
+        #[arg(long)]
+        template: Option<String>,
+        /// Directory of template overrides
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
+    /// Copy the templates into the project so regenerating it is pinned to them
+    Vendor {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// Directory the project was generated into
+        #[arg(short, long, default_value = ".")]
+        out: PathBuf,
+        /// Template overrides to vendor along with the built-ins
+        #[arg(long)]
+        templates: Option<PathBuf>,
+    },
+    /// Report where a generated project has drifted from its spec
+    DiffSpec {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// Directory the project was generated into
+        #[arg(short, long, default_value = ".")]
+        out: PathBuf,
+        /// Template overrides the project was generated with
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
+    /// Generate the project and build it into one file to ship (the spec's bundle section)
+    Bundle {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// Output directory (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        out: PathBuf,
         /// Overwrite existing files if present
         #[arg(long)]
         force: bool,
+        /// Directory of template overrides
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        /// Python that creates .venv, and so the version bundled
+        #[arg(long, default_value = "python3")]
+        python: String,
+    },
+    /// Replace this binary with the latest release from GitHub
+    SelfUpdate {
+        #[arg(long, value_enum, default_value_t = UpdateChannel::Stable)]
+        channel: UpdateChannel,
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+    },
+    /// Summarize the tools, providers and targets of many specs, without their names or prompts
+    Report {
+        /// Spec files, or directories to search for them
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Json)]
+        format: TableFormat,
+    },
+    /// Rewrite spec files in canonical form, keeping comments
+    Fmt {
+        /// Spec files to format in place
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Only report files that are not formatted
+        #[arg(long)]
+        check: bool,
+    },
+    /// Work with template packs: directories of overrides for --templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Create the skeleton of something to build on
+    New {
+        #[command(subcommand)]
+        command: NewCommand,
+    },
+    /// Share specs through an OCI or HTTP registry (--registry or TACHI_REGISTRY)
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommand,
     },
 }
 
-// SPEC
-#[derive(Debug, Serialize, Deserialize)]
-struct Spec {
-    agent: Agent,
+#[derive(Subcommand, Debug)]
+enum RegistryCommand {
+    /// Upload a spec under a name and tag
+    Push {
+        /// Path to YAML spec
+        input: PathBuf,
+        /// `<name>[:<tag>]`; the tag defaults to latest
+        reference: String,
+        /// oci://<host>/<namespace> or an http(s):// URL (defaults to TACHI_REGISTRY)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+    /// Download a spec by `<name>[:<tag>]` or `<name>@sha256:<digest>`
+    Pull {
+        reference: String,
+        /// File to write (defaults to stdout)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// oci://<host>/<namespace> or an http(s):// URL (defaults to TACHI_REGISTRY)
+        #[arg(long)]
+        registry: Option<String>,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Agent {
-    name: String,
-    tools: Vec<Tool>,
-    model: Model,
+#[derive(Subcommand, Debug)]
+enum NewCommand {
+    /// A template pack with a README and tests for `tachi template test`
+    TemplatePack {
+        /// Directory to create; must be empty if it exists
+        dir: PathBuf,
+        /// Built-in template to copy in as a starting point, such as cli.py
+        #[arg(long)]
+        copy: Vec<String>,
+    },
+    /// A tool pack: a template pack handing an example tool to the agent,
+    /// with pytest tests and tests for `tachi template test`
+    ToolPack {
+        /// Directory to create; must be empty if it exists
+        dir: PathBuf,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-enum Tool {
-    #[serde(alias = "search")]
-    Search,
-    #[serde(alias = "webpage")]
-    Webpage,
+#[derive(Subcommand, Debug)]
+enum TemplateCommand {
+    /// Render a pack against tachi's example specs and check the pack's
+    /// golden files (tests/<example>/) and expectations (tests/expect.yml)
+    Test {
+        /// Template pack directory
+        pack: PathBuf,
+        /// Rewrite the golden files from the render instead of comparing
+        #[arg(long)]
+        update: bool,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+    },
 }
 
-impl Tool {
-    fn py_import_name(&self) -> &'static str {
-        match self {
-            Tool::Search => "DuckDuckGoSearchTool",
-            Tool::Webpage => "VisitWebpageTool",
-        }
-    }
-    fn py_instance(&self) -> &'static str {
-        match self {
-            Tool::Search => "DuckDuckGoSearchTool()",
-            Tool::Webpage => "VisitWebpageTool()",
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    Error,
+    Warning,
+    Info,
+    Never,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-enum Model {
-    #[serde(alias = "qwen-coder")]
-    QwenCoder,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Human,
+    Json,
 }
 
-impl Model {
-    fn model_id(&self) -> &'static str {
-        match self {
-            Model::QwenCoder => "Qwen/Qwen2.5-Coder-32B-Instruct",
-        }
-    }
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Lang {
+    Python,
+    Csharp,
+    Go,
+    Java,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Framework {
+    Smolagents,
+    OpenaiAssistants,
+    Autogen,
+    Llamaindex,
+    SemanticKernel,
+    SpringAi,
 }
 
-// ----------------------
-// Template (embedded)
-// ----------------------
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Mermaid,
+    Dot,
+}
 
-const PY_AGENT_TEMPLATE: &str = r#"import os
-from dotenv import load_dotenv
-from smolagents import InferenceClientModel, CodeAgent, {{ tool_imports | join(sep=", ") }}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TableFormat {
+    Json,
+    Csv,
+}
 
-# Load environment variables from .env file
-load_dotenv()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum UpdateChannel {
+    /// The newest release
+    Stable,
+    /// The latest build of the main branch
+    Nightly,
+}
 
-def create_agent():
-    """Create and return a configured smolagents instance."""
-    hf_token = os.getenv("HUGGINGFACEHUB_API_TOKEN")
-    if not hf_token:
-        raise ValueError("HUGGINGFACEHUB_API_TOKEN environment variable not set")
+// ERRORS
 
-    model = InferenceClientModel(
-        model_id="{{ model_id }}",
-        token=hf_token
-    )
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  internal error
+  2  invalid command line
+  3  spec could not be parsed
+  4  spec failed validation
+  5  file system error
+  6  output file exists (use --force)
+  7  template rendering failed
+  8  lint findings at or above --fail-on
+  9  project has drifted from the spec (diff-spec)
+ 10  spec files need formatting (fmt --check)
+ 11  output holds values that look like credentials (use --allow-secrets)
+ 12  generated Python does not compile (gen --verify)
+ 13  template pack checks failed (template test)";
+
+fn report_error(err: &anyhow::Error, format: ErrorFormat) -> ExitCode {
+    let report = ErrorReport::new(err);
+    match format {
+        ErrorFormat::Human => eprintln!("Error: {err:?}"),
+        ErrorFormat::Json => match serde_json::to_string(&report) {
+            Ok(json) => eprintln!("{json}"),
+            Err(_) => eprintln!("Error: {err:?}"),
+        },
+    }
+    ExitCode::from(report.code)
+}
+
+// MAIN
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let error_format = cli.error_format;
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => report_error(&err, error_format),
+    }
+}
 
-    agent = CodeAgent(
-        tools=[{% for t in tool_instances %}{{ t }}{% if not loop.last %}, {% endif %}{% endfor %}],
-        model=model,
-    )
-    return agent
-"#;
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Commands::Gen {
+            input,
+            dir: _,
+            out,
+            into,
+            mount,
+            force,
+            templates,
+            diff,
+            locked,
+            allow_secrets,
+            deny_warnings,
+            run_hooks,
+            verify,
+            sign,
+            signing_key,
+            lang,
+            framework,
+        } => gen(
+            input,
+            into.clone().unwrap_or(out),
+            GenerateOptions {
+                force,
+                templates,
+                locked,
+                allow_secrets,
+                in_place: into.is_some(),
+                mount,
+                ..GenerateOptions::default()
+            },
+            GenFlags {
+                diff: diff || force,
+                deny_warnings,
+                run_hooks,
+                verify,
+                signing_key: match sign {
+                    true => Some(signing_key.map_or_else(signing_key_from_env, Ok)?),
+                    false => None,
+                },
+                lang: lang.map(|lang| match lang {
+                    Lang::Python => tachi::Lang::Python,
+                    Lang::Csharp => tachi::Lang::Csharp,
+                    Lang::Go => tachi::Lang::Go,
+                    Lang::Java => tachi::Lang::Java,
+                }),
+                framework: framework.map(|framework| match framework {
+                    Framework::Smolagents => tachi::Framework::Smolagents,
+                    Framework::OpenaiAssistants => tachi::Framework::OpenaiAssistants,
+                    Framework::Autogen => tachi::Framework::Autogen,
+                    Framework::Llamaindex => tachi::Framework::Llamaindex,
+                    Framework::SemanticKernel => tachi::Framework::SemanticKernel,
+                    Framework::SpringAi => tachi::Framework::SpringAi,
+                }),
+            },
+        ),
+        Commands::Serve { addr } => tachi::serve(&addr),
+        Commands::Lsp => tachi::run_language_server(),
+        Commands::Graph { input, format } => {
+            let spec = load_spec(&input)?;
+            let format = match format {
+                GraphFormat::Mermaid => tachi::GraphFormat::Mermaid,
+                GraphFormat::Dot => tachi::GraphFormat::Dot,
+            };
+            print!("{}", tachi::graph(&spec, format));
+            Ok(())
+        }
+        Commands::Lint {
+            input,
+            fail_on,
+            format,
+        } => lint(&input, fail_on, format),
+        Commands::Import { input, name } => import(&input, name),
+        Commands::Explain {
+            input,
+            template,
+            templates,
+            format,
+        } => explain(&input, template.as_deref(), templates.as_deref(), format),
+        Commands::Vendor {
+            input,
+            out,
+            templates,
+        } => vendor(&input, &out, templates.as_deref()),
+        Commands::DiffSpec {
+            input,
+            out,
+            templates,
+            format,
+        } => diff_spec(&input, &out, templates.as_deref(), format),
+        Commands::Bundle {
+            input,
+            out,
+            force,
+            templates,
+            python,
+        } => bundle(
+            &input,
+            &out,
+            GenerateOptions {
+                force,
+                templates,
+                ..GenerateOptions::default()
+            },
+            &python,
+        ),
+        Commands::SelfUpdate { channel, check } => {
+            let channel = match channel {
+                UpdateChannel::Stable => tachi::Channel::Stable,
+                UpdateChannel::Nightly => tachi::Channel::Nightly,
+            };
+            match tachi::self_update(channel, check)? {
+                tachi::Update::UpToDate { release } => {
+                    println!(
+                        "✔ tachi {} is up to date ({release})",
+                        env!("CARGO_PKG_VERSION")
+                    )
+                }
+                tachi::Update::Available { release } => {
+                    println!("{release} is available; run tachi self-update to install it")
+                }
+                tachi::Update::Updated { release, path } => {
+                    println!("✔ Updated {} to {release}", path.display())
+                }
+            }
+            Ok(())
+        }
+        Commands::Report { inputs, format } => report(&inputs, format),
+        Commands::Fmt { inputs, check } => fmt(&inputs, check),
+        Commands::Template {
+            command:
+                TemplateCommand::Test {
+                    pack,
+                    update,
+                    format,
+                },
+        } => template_test(&pack, update, format),
+        Commands::New {
+            command: NewCommand::TemplatePack { dir, copy },
+        } => {
+            let files = tachi::new_template_pack(&dir, &copy)?;
+            println!("✔ Created template pack {}", dir.display());
+            for file in &files {
+                println!("  created   {file}");
+            }
+            Ok(())
+        }
+        Commands::New {
+            command: NewCommand::ToolPack { dir },
+        } => {
+            let files = tachi::new_tool_pack(&dir)?;
+            println!("✔ Created tool pack {}", dir.display());
+            for file in &files {
+                println!("  created   {file}");
+            }
+            Ok(())
+        }
+        Commands::Registry {
+            command:
+                RegistryCommand::Push {
+                    input,
+                    reference,
+                    registry,
+                },
+        } => registry_push(&input, &reference, registry.as_deref()),
+        Commands::Registry {
+            command:
+                RegistryCommand::Pull {
+                    reference,
+                    out,
+                    registry,
+                },
+        } => registry_pull(&reference, out.as_deref(), registry.as_deref()),
+    }
+}
 
-const PY_CLI_TEMPLATE: &str = r#"#!/usr/bin/env python3
-"""
-Interactive CLI for the smolagent.
-Provides a classic chat interface with input/output loop.
-"""
+/// The one spec in `input`, for commands that work on a single agent.
+fn load_spec(input: &Path) -> Result<Spec> {
+    let mut specs = load_specs(input)?;
+    if specs.len() > 1 {
+        bail!(
+            "{} holds {} specs; this command takes a file with one",
+            input.display(),
+            specs.len()
+        );
+    }
+    Ok(specs.remove(0))
+}
 
-import sys
-from agent import create_agent
+/// How messages name `spec`: the file, and the agent when the file holds several.
+fn document_label(input: &Path, spec: &Spec, documents: usize) -> String {
+    if documents > 1 {
+        format!("{} ({})", input.display(), spec.agent.name)
+    } else {
+        input.display().to_string()
+    }
+}
 
+/// Every spec in `input`, one per `---`-separated document.
+fn load_specs(input: &Path) -> Result<Vec<Spec>> {
+    let yaml = fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+    tachi::parse_specs(&yaml).with_context(|| format!("loading {}", input.display()))
+}
 
-def print_banner():
-    """Print welcome banner."""
-    print("=" * 60)
-    print("HuggingFace Smolagent CLI")
-    print("=" * 60)
-    print("Type your requests and press Enter.")
-    print("Type 'exit', 'quit', or press Ctrl+C to exit.")
-    print("=" * 60)
-    print()
+fn lint(input: &Path, fail_on: FailOn, format: ReportFormat) -> Result<()> {
+    let specs = load_specs(input)?;
+    let mut findings = Vec::new();
+    for spec in &specs {
+        let label = document_label(input, spec, specs.len());
+        findings.extend(tachi::lint(spec).into_iter().map(|f| (label.clone(), f)));
+    }
+    match format {
+        ReportFormat::Human => {
+            for (label, finding) in &findings {
+                println!("{label}: {finding}");
+            }
+        }
+        ReportFormat::Json => {
+            let findings: Vec<_> = findings.iter().map(|(_, f)| f).collect();
+            println!("{}", serde_json::to_string(&findings)?);
+        }
+    }
 
+    let threshold = match fail_on {
+        FailOn::Error => Severity::Error,
+        FailOn::Warning => Severity::Warning,
+        FailOn::Info => Severity::Info,
+        FailOn::Never => return Ok(()),
+    };
+    let failing = findings
+        .iter()
+        .filter(|(_, f)| f.severity >= threshold)
+        .count();
+    if failing > 0 {
+        return Err(LintFailed(failing).into());
+    }
+    Ok(())
+}
 
-def main():
-    """Run the interactive CLI loop."""
-    try:
-        # Initialize agent once at startup
-        print("Initializing agent...")
-        agent = create_agent()
-        print("Agent ready!\n")
+fn import(input: &Path, name: Option<String>) -> Result<()> {
+    let source =
+        fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+    let name = name.unwrap_or_else(|| {
+        fs::canonicalize(input)
+            .ok()
+            .and_then(|p| Some(p.parent()?.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "agent".to_string())
+    });
+    let imported = tachi::import(&source, ImportSource::from_path(input), &name)?;
+    for note in &imported.notes {
+        eprintln!("warning: {note}");
+    }
+    print!("{}", imported.yaml);
+    Ok(())
+}
 
-        print_banner()
+fn explain(
+    input: &Path,
+    template: Option<&str>,
+    templates: Option<&Path>,
+    format: ReportFormat,
+) -> Result<()> {
+    let spec = load_spec(input)?;
+    let mut contexts = tachi::template_contexts(&spec, templates)?;
+    if let Some(name) = template {
+        contexts.retain(|(n, _)| n == name);
+        if contexts.is_empty() {
+            bail!("{name} is not rendered for this spec");
+        }
+    }
+    match format {
+        ReportFormat::Human => {
+            // Every field, with the defaults filled in
+            if template.is_none() {
+                print!("# spec\n{}", serde_yaml_ng::to_string(&spec)?);
+            }
+            for (name, ctx) in &contexts {
+                println!("\n# {name}\n{}", serde_json::to_string_pretty(ctx)?);
+            }
+        }
+        ReportFormat::Json => {
+            let templates: Vec<_> = contexts
+                .iter()
+                .map(|(name, ctx)| serde_json::json!({ "template": name, "context": ctx }))
+                .collect();
+            let report = serde_json::json!({ "spec": spec, "templates": templates });
+            println!("{report}");
+        }
+    }
+    Ok(())
+}
 
-        # Main interaction loop
-        while True:
-            try:
-                # Get user input
-                user_input = input("\nYou: ").strip()
+fn vendor(input: &Path, out: &Path, templates: Option<&Path>) -> Result<()> {
+    let spec = load_spec(input)?;
+    let project_dir = out.join(spec.names().slug);
+    let names = tachi::vendor(&project_dir, templates)?;
+    println!(
+        "✔ Vendored {} templates into {}",
+        names.len(),
+        project_dir.join(".tachi/templates").display()
+    );
+    Ok(())
+}
 
-                # Check for exit commands
-                if user_input.lower() in ["exit", "quit", "q"]:
-                    print("\nGoodbye!")
-                    break
+fn bundle(input: &Path, out: &Path, options: GenerateOptions, python: &str) -> Result<()> {
+    let spec = load_spec(input)?;
+    let written = tachi::generate(&spec, out, &options)?;
+    println!("✔ Generated {} project", spec.agent.name);
+    for (name, status) in &written {
+        println!("  {:<9} {name}", status.label());
+    }
+    let artifact = tachi::bundle(&spec, &out.join(spec.names().slug), python)?;
+    println!("✔ Bundled {} into {}", spec.agent.name, artifact.display());
+    Ok(())
+}
 
-                # Skip empty inputs
-                if not user_input:
-                    continue
+fn report(inputs: &[PathBuf], format: TableFormat) -> Result<()> {
+    let mut specs = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            find_specs(input, &mut specs)?;
+        } else {
+            specs.push(input.clone());
+        }
+    }
+    // A file that does not load gets a row with the error, so the
+    // inventory still accounts for it. Each document of a file with
+    // several gets its own row, as `path#<number>`.
+    let mut rows: Vec<(String, Result<tachi::SpecReport, String>)> = Vec::new();
+    for path in &specs {
+        match load_specs(path) {
+            Ok(loaded) if loaded.len() == 1 => {
+                rows.push((path.display().to_string(), Ok(tachi::report(&loaded[0]))));
+            }
+            Ok(loaded) => {
+                for (index, spec) in loaded.iter().enumerate() {
+                    let row = format!("{}#{}", path.display(), index + 1);
+                    rows.push((row, Ok(tachi::report(spec))));
+                }
+            }
+            Err(err) => rows.push((path.display().to_string(), Err(format!("{err:#}")))),
+        }
+    }
+    match format {
+        TableFormat::Json => {
+            let rows: Vec<serde_json::Value> = rows
+                .into_iter()
+                .map(|(spec, report)| {
+                    let mut row = match report {
+                        Ok(report) => serde_json::to_value(report)?,
+                        Err(error) => serde_json::json!({ "error": error }),
+                    };
+                    row["spec"] = spec.into();
+                    Ok(row)
+                })
+                .collect::<Result<_>>()?;
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+        TableFormat::Csv => {
+            let mut header = vec!["spec"];
+            header.extend(tachi::SpecReport::CSV_HEADER);
+            header.push("error");
+            println!("{}", header.join(","));
+            for (spec, report) in rows {
+                let mut fields = vec![spec];
+                match report {
+                    Ok(report) => {
+                        fields.extend(report.csv_fields());
+                        fields.push(String::new());
+                    }
+                    Err(error) => {
+                        fields.extend(tachi::SpecReport::CSV_HEADER.iter().map(|_| String::new()));
+                        fields.push(error);
+                    }
+                }
+                let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+    }
+    Ok(())
+}
 
-                # Run agent with user input
-                print("\nAgent: ", end="", flush=True)
-                result = agent.run(user_input)
-                print(result)
+/// YAML files under `dir` with a top-level `agent` key, in path order.
+/// Hidden directories (`.venv`, `.tachi`, `.git`) are skipped.
+fn find_specs(dir: &Path, specs: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()
+        .with_context(|| format!("reading {}", dir.display()))?;
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                find_specs(&path, specs)?;
+            }
+        } else if name.ends_with(".yml") || name.ends_with(".yaml") {
+            let is_spec = fs::read_to_string(&path).is_ok_and(|yaml| {
+                serde_yaml_ng::Deserializer::from_str(&yaml).any(|doc| {
+                    serde_yaml_ng::Value::deserialize(doc)
+                        .is_ok_and(|doc| doc.get("agent").is_some())
+                })
+            });
+            if is_spec {
+                specs.push(path);
+            }
+        }
+    }
+    Ok(())
+}
 
-            except KeyboardInterrupt:
-                print("\n\nGoodbye!")
-                break
-            except EOFError:
-                print("\n\nGoodbye!")
-                break
-
-    except Exception as e:
-        print(f"\nError initializing agent: {e}", file=sys.stderr)
-        sys.exit(1)
-
-
-if __name__ == "__main__":
-    main()
-"#;
-
-fn write_file(out_dir: &Path, name: &str, content: &str, force: bool) -> Result<PathBuf> {
-    fs::create_dir_all(out_dir)
-        .with_context(|| format!("creating output directory {}", out_dir.display()))?;
-    let path = out_dir.join(name);
-    if path.exists() && !force {
-        anyhow::bail!(
-            "refusing to overwrite existing file: {} (use --force)",
-            path.display()
-        );
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
     }
-    fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
-    Ok(path)
 }
 
-fn render_agent_py(spec: &Spec) -> Result<String> {
-    let mut ctx = tera::Context::new();
-    let tool_imports: Vec<String> = spec
-        .agent
-        .tools
-        .iter()
-        .map(|t| t.py_import_name().to_string())
-        .collect();
-    let tool_instances: Vec<String> = spec
-        .agent
-        .tools
-        .iter()
-        .map(|t| t.py_instance().to_string())
-        .collect();
+fn diff_spec(
+    input: &Path,
+    out: &Path,
+    templates: Option<&Path>,
+    format: ReportFormat,
+) -> Result<()> {
+    let spec = load_spec(input)?;
+    let project_dir = out.join(spec.names().slug);
+    let drift = tachi::diff_spec(&spec, &project_dir, templates)?;
+    match format {
+        ReportFormat::Human => {
+            for d in &drift {
+                println!("{}: {d}", project_dir.display());
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string(&drift)?),
+    }
+    if !drift.is_empty() {
+        return Err(DriftFound(drift.len()).into());
+    }
+    Ok(())
+}
 
-    ctx.insert("tool_imports", &tool_imports);
-    ctx.insert("tool_instances", &tool_instances);
-    ctx.insert("model_id", &spec.agent.model.model_id());
+fn fmt(inputs: &[PathBuf], check: bool) -> Result<()> {
+    let mut unformatted = 0;
+    for input in inputs {
+        let source =
+            fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+        let formatted = tachi::format_spec(&source)
+            .with_context(|| format!("formatting {}", input.display()))?;
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{} is not formatted", input.display());
+            unformatted += 1;
+        } else {
+            fs::write(input, formatted).with_context(|| format!("writing {}", input.display()))?;
+            println!("formatted {}", input.display());
+        }
+    }
+    if unformatted > 0 {
+        return Err(Unformatted(unformatted).into());
+    }
+    Ok(())
+}
 
-    // render one-off template from the embedded string
-    let py = Tera::one_off(PY_AGENT_TEMPLATE, &ctx, false).context("rendering agent.py template")?;
-    Ok(py)
+fn template_test(pack: &Path, update: bool, format: ReportFormat) -> Result<()> {
+    let result = tachi::test_templates(pack, update)?;
+    match format {
+        ReportFormat::Human => {
+            for path in &result.updated {
+                println!("updated {}", pack.join(path).display());
+            }
+            for failure in &result.failures {
+                println!("{}: {failure}", pack.display());
+            }
+            if result.checks == 0 && result.failures.is_empty() && !update {
+                println!(
+                    "{} has no golden files or expectations; record them with --update",
+                    pack.display()
+                );
+            } else if result.failures.is_empty() && !update {
+                println!(
+                    "✔ {} checks passed against the {} example specs",
+                    result.checks,
+                    tachi::example_names().join(", ")
+                );
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string(&result)?),
+    }
+    if !result.failures.is_empty() {
+        return Err(TemplateTestFailed(result.failures.len()).into());
+    }
+    Ok(())
 }
 
-// MAIN
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn registry_push(input: &Path, reference: &str, registry: Option<&str>) -> Result<()> {
+    let registry = tachi::Registry::resolve(registry)?;
+    let reference: tachi::SpecRef = reference.parse()?;
+    let yaml = fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+    // What lands in the registry should generate
+    tachi::parse_specs(&yaml).with_context(|| format!("loading {}", input.display()))?;
+    let source = tachi::push_spec(&registry, &reference, &yaml)?;
+    let tag = source.tag.as_deref().unwrap_or_default();
+    println!("✔ Pushed {}:{tag} to {registry}", source.name);
+    println!("  digest    {}", source.digest);
+    Ok(())
+}
 
-    let Commands::Gen { input, dir: _, out, force } = cli.command;
-    let yaml =
-        fs::read_to_string(&input).with_context(|| format!("reading {}", input.display()))?;
-    let spec: Spec = serde_yaml_ng::from_str(&yaml).context("parsing YAML")?;
+fn registry_pull(reference: &str, out: Option<&Path>, registry: Option<&str>) -> Result<()> {
+    let (yaml, source) = pull(reference, registry)?;
+    match out {
+        Some(out) => {
+            fs::write(out, &yaml).with_context(|| format!("writing {}", out.display()))?;
+            println!("✔ Pulled {reference} into {}", out.display());
+            println!("  digest    {}", source.digest);
+        }
+        None => print!("{yaml}"),
+    }
+    Ok(())
+}
 
-    // Create project directory with agent name
-    let project_dir = out.join(&spec.agent.name);
+/// The spec `reference` names, and where it came from.
+fn pull(reference: &str, registry: Option<&str>) -> Result<(String, tachi::SpecSource)> {
+    let registry = tachi::Registry::resolve(registry)?;
+    let parsed: tachi::SpecRef = reference.parse()?;
+    tachi::pull_spec(&registry, &parsed).with_context(|| format!("pulling {reference}"))
+}
 
-    // Generate agent.py
-    let agent_py = render_agent_py(&spec)?;
-    write_file(&project_dir, "agent.py", &agent_py, force)?;
+/// `tachi gen` switches that act around generation rather than on it.
+struct GenFlags {
+    diff: bool,
+    deny_warnings: bool,
+    run_hooks: bool,
+    verify: bool,
+    /// Key to sign the attestation with, for --sign
+    signing_key: Option<PathBuf>,
+    /// --lang and --framework, applied to every spec in the file
+    lang: Option<tachi::Lang>,
+    framework: Option<tachi::Framework>,
+}
 
-    // Generate cli.py
-    write_file(&project_dir, "cli.py", PY_CLI_TEMPLATE, force)?;
+fn signing_key_from_env() -> Result<PathBuf> {
+    match std::env::var_os(tachi::SIGNING_KEY_ENV) {
+        Some(key) if !key.is_empty() => Ok(PathBuf::from(key)),
+        _ => bail!(
+            "--sign needs a key; pass --signing-key or set {}",
+            tachi::SIGNING_KEY_ENV
+        ),
+    }
+}
 
-    // Generate requirements.txt
-    let reqs = "smolagents\npython-dotenv\nddgs\n";
-    write_file(&project_dir, "requirements.txt", reqs, force)?;
+/// Generate a project per spec in `input`, side by side in `out`.
+fn gen(input: PathBuf, out: PathBuf, mut options: GenerateOptions, flags: GenFlags) -> Result<()> {
+    let yaml = match input.to_str().and_then(|i| i.strip_prefix("registry:")) {
+        Some(reference) => {
+            let (yaml, source) = pull(reference, None)?;
+            options.source = Some(source);
+            yaml
+        }
+        None => {
+            fs::read_to_string(&input).with_context(|| format!("reading {}", input.display()))?
+        }
+    };
+    let mut specs =
+        tachi::parse_specs(&yaml).with_context(|| format!("loading {}", input.display()))?;
+    if flags.lang.is_some() || flags.framework.is_some() {
+        let count = specs.len();
+        for spec in &mut specs {
+            spec.lang = flags.lang.unwrap_or(spec.lang);
+            spec.framework = flags.framework.unwrap_or(spec.framework);
+            spec.validate().with_context(|| {
+                format!(
+                    "validating {} with --lang and --framework",
+                    document_label(&input, spec, count)
+                )
+            })?;
+        }
+    }
+    if options.in_place && specs.len() > 1 {
+        bail!(
+            "--into generates one project; {} has {}",
+            input.display(),
+            specs.len()
+        );
+    }
+    if flags.verify {
+        if let Some(spec) = specs.iter().find(|s| s.lang != tachi::Lang::Python) {
+            bail!(
+                "--verify compiles the generated Python; it does not check lang {}",
+                spec.lang.name()
+            );
+        }
+    }
+    // `tachi lint` shows the info-level findings too
+    let mut warnings = 0;
+    for spec in &specs {
+        for finding in tachi::lint(spec) {
+            if finding.severity >= Severity::Warning {
+                eprintln!("{}: {finding}", document_label(&input, spec, specs.len()));
+                warnings += 1;
+            }
+        }
+    }
+    if flags.deny_warnings && warnings > 0 {
+        return Err(LintFailed(warnings).into());
+    }
+    // Broken hook bodies and prompt code fail here rather than at runtime
+    for spec in &specs {
+        tachi::check_python(spec, &yaml).with_context(|| {
+            format!(
+                "checking Python in {}",
+                document_label(&input, spec, specs.len())
+            )
+        })?;
+    }
+    let provenance = tachi::Provenance {
+        spec_name: &input.to_string_lossy(),
+        spec: yaml.as_bytes(),
+        source: options.source.as_ref(),
+    };
+    for spec in &specs {
+        gen_project(spec, &out, options.clone(), &flags, &provenance)?;
+    }
+    Ok(())
+}
 
-    // Generate .env.example
-    let env = "# Put your Hugging Face token here\nHUGGINGFACEHUB_API_TOKEN=\n";
-    write_file(&project_dir, ".env.example", env, force)?;
+fn gen_project(
+    spec: &Spec,
+    out: &Path,
+    mut options: GenerateOptions,
+    flags: &GenFlags,
+    provenance: &tachi::Provenance,
+) -> Result<()> {
+    if flags.diff {
+        let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        for change in tachi::changes(spec, out, &options)? {
+            print!(
+                "{}",
+                tachi::unified_diff(
+                    &change.path,
+                    &String::from_utf8_lossy(&change.old),
+                    &String::from_utf8_lossy(&change.new),
+                    color,
+                )
+            );
+        }
+    }
+    if !options.force && io::stdin().is_terminal() {
+        options.resolve = resolve_conflicts(spec, out, &options)?;
+    }
+    let written = tachi::generate(spec, out, &options)?;
 
     println!("✔ Generated {} project", spec.agent.name);
+    for (name, status) in &written {
+        println!("  {:<9} {name}", status.label());
+    }
+    if let Some(mount) = &options.mount {
+        println!("✔ Include agent_router.py in {}:\n", mount.app.display());
+        for line in mount.include_code().lines() {
+            if line.is_empty() {
+                println!();
+            } else {
+                println!("    {line}");
+            }
+        }
+    }
+
+    let project_dir = tachi::project_dir(spec, out, &options);
+    if flags.verify {
+        tachi::verify(&project_dir, &written)?;
+        println!("✔ Generated Python compiles");
+    }
+    if let Some(key) = &flags.signing_key {
+        let attestation = tachi::sign(&project_dir, &written, provenance, key)?;
+        println!("✔ Signed {}", attestation.display());
+    }
+    if !spec.hooks.post_gen.is_empty() && confirm_post_gen(spec, &project_dir, flags.run_hooks)? {
+        let runs = tachi::run_post_gen(spec, &project_dir)?;
+        for run in &runs {
+            let label = if run.success { "ran" } else { "failed" };
+            println!("  {label:<9} {}", run.command);
+            for line in run.output.lines() {
+                println!("            {line}");
+            }
+        }
+        if let Some(failed) = runs.iter().find(|r| !r.success) {
+            bail!("post-gen hook `{}` failed", failed.command);
+        }
+    }
 
     Ok(())
 }
+
+/// Ask what to do with each existing file the generated output would
+/// change, rather than failing on the first one.
+fn resolve_conflicts(
+    spec: &Spec,
+    out: &Path,
+    options: &GenerateOptions,
+) -> Result<HashMap<String, Resolution>> {
+    let mut resolve = HashMap::new();
+    let mut all = false;
+    for change in tachi::changes(spec, out, options)? {
+        if change.merges {
+            continue;
+        }
+        if all {
+            resolve.insert(change.path, Resolution::Overwrite);
+            continue;
+        }
+        let resolution = loop {
+            eprint!("Overwrite {}? [y,n,a,q,d,m,?] ", change.path);
+            io::stderr().flush()?;
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer)? == 0 {
+                bail!("stopped; nothing was written");
+            }
+            match answer.trim() {
+                "y" => break Resolution::Overwrite,
+                "n" => break Resolution::Keep,
+                "a" => {
+                    all = true;
+                    break Resolution::Overwrite;
+                }
+                "q" => bail!("stopped; nothing was written"),
+                "d" => eprint!(
+                    "{}",
+                    tachi::unified_diff(
+                        &change.path,
+                        &String::from_utf8_lossy(&change.old),
+                        &String::from_utf8_lossy(&change.new),
+                        io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+                    )
+                ),
+                "m" => break Resolution::Merge,
+                _ => eprintln!(
+                    "  y - overwrite with the generated file\n  \
+                     n - keep your file\n  \
+                     a - overwrite this and every remaining file\n  \
+                     q - stop without writing anything\n  \
+                     d - show the diff\n  \
+                     m - write the generated file with conflict markers around your changes"
+                ),
+            }
+        };
+        resolve.insert(change.path, resolution);
+    }
+    Ok(resolve)
+}
+
+/// Post-gen hooks run arbitrary shell commands, so a spec from elsewhere
+/// only gets to run them once someone has seen them.
+fn confirm_post_gen(spec: &Spec, project_dir: &Path, run_hooks: bool) -> Result<bool> {
+    if run_hooks {
+        return Ok(true);
+    }
+    let commands = &spec.hooks.post_gen;
+    if !io::stdin().is_terminal() {
+        eprintln!(
+            "note: skipped {} post-gen hook(s); pass --run-hooks to run them",
+            commands.len()
+        );
+        return Ok(false);
+    }
+    eprintln!("The spec runs these commands in {}:", project_dir.display());
+    for command in commands {
+        eprintln!("  {command}");
+    }
+    eprint!("Run them? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
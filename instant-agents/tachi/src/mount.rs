@@ -0,0 +1,159 @@
+//! `gen --mount`: serving the agent from an existing FastAPI or Django app
+//! through a generated agent_router.py, instead of a standalone server.py.
+
+use crate::generate::{GeneratedFile, Renderer};
+use crate::spec::{Interface, Lang, Spec};
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The module `generate` writes the router to, at the project root
+const ROUTER: &str = "agent_router.py";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebFramework {
+    /// An `APIRouter` for `app.include_router`
+    Fastapi,
+    /// Views and `urlpatterns` for `include()` in a URLconf
+    Django,
+}
+
+impl WebFramework {
+    pub const ALL: &'static [WebFramework] = &[WebFramework::Fastapi, WebFramework::Django];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WebFramework::Fastapi => "fastapi",
+            WebFramework::Django => "django",
+        }
+    }
+
+    fn template(self) -> &'static str {
+        match self {
+            WebFramework::Fastapi => "agent_router.py",
+            WebFramework::Django => "agent_router_django.py",
+        }
+    }
+}
+
+/// Where the agent is mounted: the web framework and the file, relative to
+/// the project, that includes the router (the FastAPI app or Django URLconf).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub framework: WebFramework,
+    pub app: PathBuf,
+}
+
+impl FromStr for Mount {
+    type Err = String;
+
+    /// `fastapi:path/to/app.py` or `django:path/to/urls.py`
+    fn from_str(s: &str) -> Result<Self, String> {
+        let names: Vec<&str> = WebFramework::ALL.iter().map(|f| f.name()).collect();
+        let Some((framework, app)) = s.split_once(':') else {
+            return Err(format!(
+                "expected <framework>:<file>, such as fastapi:app.py; framework is one of {}",
+                names.join(", ")
+            ));
+        };
+        let Some(framework) = WebFramework::ALL
+            .iter()
+            .copied()
+            .find(|f| f.name() == framework)
+        else {
+            return Err(format!(
+                "unknown framework {framework}; expected one of {}",
+                names.join(", ")
+            ));
+        };
+        if app.is_empty() {
+            return Err(format!(
+                "{} needs the file that includes the router",
+                framework.name()
+            ));
+        }
+        Ok(Mount {
+            framework,
+            app: PathBuf::from(app),
+        })
+    }
+}
+
+impl fmt::Display for Mount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.framework.name(), self.app.display())
+    }
+}
+
+impl Mount {
+    /// The lines that include the router in the app file.
+    pub fn include_code(&self) -> &'static str {
+        match self.framework {
+            WebFramework::Fastapi => {
+                "from agent_router import router as agent_router\n\
+                 \n\
+                 app.include_router(agent_router, prefix=\"/agent\")\n"
+            }
+            WebFramework::Django => {
+                "from django.urls import include, path\n\
+                 \n\
+                 urlpatterns += [path(\"agent/\", include(\"agent_router\"))]\n"
+            }
+        }
+    }
+}
+
+/// Swap the standalone server for agent_router.py in the rendered `files`,
+/// and tell the README how to include it. Returns the router's path.
+pub(crate) fn mount(
+    tera: &Renderer,
+    spec: &Spec,
+    mount: &Mount,
+    project_dir: &Path,
+    files: &mut Vec<GeneratedFile>,
+) -> Result<&'static str> {
+    if spec.lang != Lang::Python {
+        bail!(
+            "--mount includes the agent in a Python web app; lang {} does not generate Python",
+            spec.lang.name()
+        );
+    }
+    if spec.has_interface(Interface::Server) {
+        bail!(
+            "--mount serves the agent from {}; drop interface server, which runs its own",
+            mount.app.display()
+        );
+    }
+    if !project_dir.join(&mount.app).is_file() {
+        bail!(
+            "--mount {mount}: {} is not a file",
+            project_dir.join(&mount.app).display()
+        );
+    }
+    let mut ctx = tera::Context::new();
+    ctx.insert("agent_name", &spec.agent.name);
+    ctx.insert("approval", &spec.approval.is_some());
+    let template = mount.framework.template();
+    let router = tera
+        .render(template, &ctx)
+        .with_context(|| format!("rendering {template} template"))?;
+    files.push(GeneratedFile::new(ROUTER, router));
+
+    if let Some(readme) = files.iter_mut().find(|f| f.path == "README.md") {
+        let mut text = String::from_utf8_lossy(&readme.contents).into_owned();
+        text.push_str(&format!(
+            "\n## Serving from {app}\n\n\
+             `{ROUTER}` serves the agent from the existing {framework} app. \
+             Include it in `{app}`:\n\n\
+             ```python\n{code}```\n\n\
+             Then `POST /agent/run` with `{{\"task\": \"...\"}}` answers with \
+             `{{\"answer\": \"...\"}}`, and `GET /agent/health` checks the app is up.\n",
+            app = mount.app.display(),
+            framework = mount.framework.name(),
+            code = mount.include_code(),
+        ));
+        readme.contents = text.into_bytes();
+    }
+    Ok(ROUTER)
+}
@@ -0,0 +1,223 @@
+use crate::error::ValidationError;
+use crate::spec::{Lang, Spec};
+
+const PY_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Module names a generated project imports; a package called one of these
+/// would shadow it.
+const RESERVED_PACKAGES: &[&str] = &[
+    "agent",
+    "cli",
+    "chat_session",
+    "guardrails",
+    "approval",
+    "hooks",
+    "cache",
+    "limits",
+    "response_format",
+    "jsonschema",
+    "replay",
+    "a2a_server",
+    "voice",
+    "memory",
+    "batch",
+    "worker",
+    "pipeline",
+    "router",
+    "huggingface_hub",
+    "rq",
+    "redis",
+    "celery",
+    "tools",
+    "sqlalchemy",
+    "requests",
+    "github",
+    "slack_sdk",
+    "email",
+    "smtplib",
+    "imaplib",
+    "sympy",
+    "ast",
+    "io",
+    "pypdf",
+    "docx",
+    "sounddevice",
+    "numpy",
+    "faster_whisper",
+    "pyttsx3",
+    "soundfile",
+    "youtube_transcript_api",
+    "tavily",
+    "markdownify",
+    "playwright",
+    "sqlite3",
+    "sentence_transformers",
+    "gradio_client",
+    "smolagents",
+    "openai",
+    "autogen",
+    "llama_index",
+    "pydantic",
+    "ingest",
+    "dotenv",
+    "ddgs",
+    "duckduckgo_search",
+    "typer",
+    "rich",
+    "prompt_toolkit",
+    "json",
+    "os",
+    "sys",
+    "re",
+    "pathlib",
+    "datetime",
+    "argparse",
+    "uuid",
+    "threading",
+    "http",
+];
+
+/// Every name derived from `agent.name`, each safe where it is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Names {
+    /// `agent.name` as written, for banners and cards
+    pub display: String,
+    /// Output directory name: lowercase ASCII, digits and dashes
+    pub slug: String,
+    /// Python package/module name
+    pub package: String,
+    /// Container image name
+    pub image: String,
+}
+
+impl Spec {
+    /// Names for the project, honouring `agent.slug` and `agent.package`.
+    pub fn names(&self) -> Names {
+        let agent = &self.agent;
+        let slug = agent.slug.clone().unwrap_or_else(|| slugify(&agent.name));
+        let package = agent.package.clone().unwrap_or_else(|| py_ident(&slug));
+        Names {
+            display: agent.name.clone(),
+            image: slug.clone(),
+            slug,
+            package,
+        }
+    }
+
+    pub(crate) fn validate_names(&self) -> Result<(), ValidationError> {
+        if self.agent.name.trim().is_empty() {
+            return Err(ValidationError::new(
+                "agent.name",
+                "agent.name must not be empty",
+            ));
+        }
+        let names = self.names();
+        let slug_key = if self.agent.slug.is_some() {
+            "agent.slug"
+        } else {
+            "agent.name"
+        };
+        let package_key = if self.agent.package.is_some() {
+            "agent.package"
+        } else {
+            slug_key
+        };
+        if !is_slug(&names.slug) {
+            let message = match &self.agent.slug {
+                Some(slug) => format!(
+                    "agent.slug {slug:?} must be lowercase letters, digits and dashes, starting with a letter or digit"
+                ),
+                None => format!(
+                    "agent.name {:?} has no ASCII letters or digits to build a directory name from; set agent.slug",
+                    self.agent.name
+                ),
+            };
+            return Err(ValidationError::new(slug_key, message));
+        }
+        // The C# project takes its namespace from the slug instead
+        if self.lang != Lang::Python {
+            return Ok(());
+        }
+        let package = &names.package;
+        if !is_py_ident(package) {
+            return Err(ValidationError::new(
+                package_key,
+                format!("agent.package {package:?} is not a valid Python identifier"),
+            ));
+        }
+        if RESERVED_PACKAGES.contains(&package.as_str()) {
+            return Err(ValidationError::new(
+                package_key,
+                format!(
+                    "package name {package:?} collides with a module the project imports; set agent.package"
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// "Weather Bot v2!" -> "weather-bot-v2"
+pub(crate) fn slugify(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+fn is_slug(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && s.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_' || c == '.'
+        })
+        && !s.contains("..")
+}
+
+pub(crate) fn is_py_ident(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !PY_KEYWORDS.contains(&s)
+}
+
+/// "Weather-Bot v2" / "weatherBot" -> "weather_bot_v2" / "weather_bot"
+pub(crate) fn snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut prev: Option<char> = None;
+    for c in s.chars() {
+        if c.is_alphanumeric() {
+            if c.is_uppercase() && prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+        prev = Some(c);
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// A valid, non-keyword Python identifier derived from `s`.
+pub(crate) fn py_ident(s: &str) -> String {
+    let mut ident: String = snake_case(s)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if PY_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
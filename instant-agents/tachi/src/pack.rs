@@ -0,0 +1,288 @@
+//! A template pack's `pack.yml`: where each file goes, as a Tera template
+//! rendered with the resolved spec, so packs can lay a project out in
+//! nested packages rather than flat in its root, and when it is generated
+//! at all, plus files the pack copies into the project as they are.
+
+use crate::generate::{GeneratedFile, Renderer};
+use crate::naming::is_py_ident;
+use crate::spec::Spec;
+use crate::templates;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path};
+use tera::Tera;
+
+/// The manifest's file name, in the pack directory.
+pub(crate) const MANIFEST: &str = "pack.yml";
+
+/// The built-in templates' own manifest, which holds when each optional
+/// file of a Python project is generated.
+const BUILTIN: &str = include_str!("templates/pack.yml");
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Manifest {
+    /// By the path tachi generates a file at, or the name of a template only
+    /// the pack has, which is then rendered with the resolved spec
+    #[serde(default)]
+    pub files: BTreeMap<String, PackFile>,
+    /// By the file's path in the pack: images, datasets, wheels and other
+    /// files copied byte for byte rather than rendered
+    #[serde(default)]
+    pub assets: BTreeMap<String, PackFile>,
+    /// Tool classes the pack's own Python files define, handed to a Python
+    /// agent after the spec's tools
+    #[serde(default)]
+    pub tools: Vec<PackTool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct PackTool {
+    /// Module agent.py imports the class from, such as `pack_tools`
+    pub module: String,
+    /// A smolagents `Tool` subclass whose constructor takes no arguments
+    pub class: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct PackFile {
+    /// Output path, relative to the project; the file's name when unset
+    pub path: Option<String>,
+    /// A Tera expression over the resolved spec, such as
+    /// `"server" in interfaces`; the file is left out when it is false
+    pub when: Option<String>,
+}
+
+/// The manifest in the pack at `dir`, or an empty one when it has none.
+pub(crate) fn read(dir: &Path) -> Result<Manifest> {
+    let path = dir.join(MANIFEST);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Manifest::default()),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+    };
+    serde_yaml_ng::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Every asset the pack at `dir` declares, by its path in the pack, with
+/// its contents.
+pub(crate) fn assets(dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut assets = Vec::new();
+    for name in read(dir)?.assets.into_keys() {
+        if !inside(&name) {
+            bail!("{MANIFEST}: asset {name} is not a path inside the pack");
+        }
+        if name.ends_with(templates::OVERRIDE_EXTENSION) {
+            bail!("{MANIFEST}: {name} is a template; list it under files");
+        }
+        let path = dir.join(&name);
+        let contents = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        assets.push((name, contents));
+    }
+    Ok(assets)
+}
+
+/// The tool classes the pack at `dir` hands to the agent, checked to be
+/// Python names.
+pub(crate) fn tools(dir: &Path) -> Result<Vec<PackTool>> {
+    let tools = read(dir)?.tools;
+    for tool in &tools {
+        if !tool.module.split('.').all(is_py_ident) {
+            bail!(
+                "{MANIFEST}: tool module {} is not a Python module name",
+                tool.module
+            );
+        }
+        if !is_py_ident(&tool.class) {
+            bail!(
+                "{MANIFEST}: tool class {} is not a Python class name",
+                tool.class
+            );
+        }
+    }
+    Ok(tools)
+}
+
+/// The spec as path templates and pack templates see it: every field with
+/// its default, `agent.slug` and `agent.package` as generated, whether any
+/// tool needs tools.py as `agent["local-tools"]`, and
+/// `guardrails["injection-filter"]` only when a tool fetches web content.
+pub(crate) fn spec_context(spec: &Spec) -> Result<tera::Context> {
+    let mut value = serde_json::to_value(spec).context("serializing the spec")?;
+    let names = spec.names();
+    value["agent"]["slug"] = names.slug.into();
+    value["agent"]["package"] = names.package.into();
+    value["agent"]["local-tools"] = spec.has_local_tools().into();
+    value["guardrails"]["injection-filter"] = spec.wants_injection_filter().into();
+    tera::Context::from_value(value).context("building the spec context")
+}
+
+/// Drop the listed files whose `when` is false, render the pack's own
+/// templates into `files`, move each listed file to its path and add the
+/// assets. Files this spec does not generate are skipped.
+pub(crate) fn apply(
+    tera: &Renderer,
+    spec: &Spec,
+    dir: &Path,
+    files: &mut Vec<GeneratedFile>,
+) -> Result<()> {
+    let manifest = read(dir)?;
+    if manifest.files.is_empty() && manifest.assets.is_empty() {
+        return Ok(());
+    }
+    let ctx = spec_context(spec)?;
+    let mut paths = Tera::default();
+    templates::register_filters(&mut paths);
+    for (name, file) in &manifest.files {
+        let generated = files.iter().position(|f| f.path == *name);
+        if let Some(when) = &file.when {
+            if !condition(&mut paths, name, when, &ctx)? {
+                if let Some(index) = generated {
+                    files.remove(index);
+                }
+                continue;
+            }
+        }
+        let index = match generated {
+            Some(index) => index,
+            None if !templates::is_builtin(name)
+                && dir.join(templates::file_name(name)).is_file() =>
+            {
+                let contents = tera
+                    .render(name, &ctx)
+                    .with_context(|| format!("rendering {name} template"))?;
+                files.push(GeneratedFile::new(name, contents));
+                files.len() - 1
+            }
+            None => continue,
+        };
+        if let Some(path) = &file.path {
+            files[index].path = place(&mut paths, name, path, &ctx)?;
+        }
+    }
+    for ((name, file), (_, contents)) in manifest.assets.iter().zip(assets(dir)?) {
+        if let Some(when) = &file.when {
+            if !condition(&mut paths, name, when, &ctx)? {
+                continue;
+            }
+        }
+        let path = match &file.path {
+            Some(path) => place(&mut paths, name, path, &ctx)?,
+            None => name.clone(),
+        };
+        files.push(GeneratedFile::asset(&path, contents));
+    }
+    let mut seen = HashSet::new();
+    if let Some(file) = files.iter().find(|f| !seen.insert(f.path.as_str())) {
+        bail!("{MANIFEST}: more than one file goes to {}", file.path);
+    }
+    Ok(())
+}
+
+/// Where the pack at `dir` puts built-in file `name` for the spec: its
+/// `path`, or `name` itself when the pack does not move it.
+pub(crate) fn placed(spec: &Spec, dir: Option<&Path>, name: &str) -> Result<String> {
+    let Some(dir) = dir else {
+        return Ok(name.to_string());
+    };
+    let Some(path) = read(dir)?.files.remove(name).and_then(|f| f.path) else {
+        return Ok(name.to_string());
+    };
+    let mut paths = Tera::default();
+    templates::register_filters(&mut paths);
+    place(&mut paths, name, &path, &spec_context(spec)?)
+}
+
+/// The built-in manifest's `when` expressions, evaluated for one spec.
+pub(crate) struct Conditions {
+    manifest: Manifest,
+    paths: Tera,
+    ctx: tera::Context,
+}
+
+impl Conditions {
+    pub(crate) fn builtin(spec: &Spec) -> Result<Self> {
+        let manifest = serde_yaml_ng::from_str(BUILTIN)
+            .with_context(|| format!("parsing the built-in {MANIFEST}"))?;
+        let mut paths = Tera::default();
+        templates::register_filters(&mut paths);
+        Ok(Conditions {
+            manifest,
+            paths,
+            ctx: spec_context(spec)?,
+        })
+    }
+
+    /// Whether built-in file `name` is generated for the spec.
+    pub(crate) fn holds(&mut self, name: &str) -> Result<bool> {
+        let Some(when) = self
+            .manifest
+            .files
+            .get(name)
+            .and_then(|f| f.when.as_deref())
+        else {
+            bail!("the built-in {MANIFEST} has no when for {name}");
+        };
+        condition(&mut self.paths, name, when, &self.ctx)
+    }
+}
+
+/// Whether the `when` expression of file `name` holds for the spec.
+fn condition(paths: &mut Tera, name: &str, when: &str, ctx: &tera::Context) -> Result<bool> {
+    let template = format!("{name}:when");
+    paths
+        .add_raw_template(&template, &format!("{{% if {when} %}}true{{% endif %}}"))
+        .with_context(|| format!("{MANIFEST}: parsing the when of {name}"))?;
+    let rendered = paths
+        .render(&template, ctx)
+        .with_context(|| format!("{MANIFEST}: evaluating the when of {name}"))?;
+    Ok(rendered == "true")
+}
+
+/// The path template `path` of file `name` rendered for the spec, checked
+/// to stay inside the project.
+fn place(paths: &mut Tera, name: &str, path: &str, ctx: &tera::Context) -> Result<String> {
+    let template = format!("{name}:path");
+    paths
+        .add_raw_template(&template, path)
+        .with_context(|| format!("{MANIFEST}: parsing the path of {name}"))?;
+    let rendered = paths
+        .render(&template, ctx)
+        .with_context(|| format!("{MANIFEST}: rendering the path of {name}"))?;
+    let rendered = rendered.trim();
+    if !inside(rendered) {
+        bail!("{MANIFEST}: {name} goes to {rendered:?}, which is not a path inside the project");
+    }
+    Ok(rendered.trim_start_matches("./").to_string())
+}
+
+/// Whether `path` is relative and stays below the directory it is joined to.
+fn inside(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inside;
+
+    #[test]
+    fn inside_takes_relative_paths_below_the_directory() {
+        assert!(inside("./a/b"));
+        assert!(inside("a/b.py"));
+    }
+
+    #[test]
+    fn inside_refuses_paths_that_leave_the_directory() {
+        assert!(!inside("../x"));
+        assert!(!inside("a/../../x"));
+        assert!(!inside("/abs"));
+        assert!(!inside(""));
+    }
+}
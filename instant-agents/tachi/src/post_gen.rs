@@ -0,0 +1,50 @@
+use crate::spec::Spec;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// One `hooks.post-gen` command and what it printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRun {
+    pub command: String,
+    pub success: bool,
+    /// stdout then stderr
+    pub output: String,
+}
+
+/// Run the spec's `hooks.post-gen` commands through the shell in
+/// `project_dir`, in order, stopping after the first one that fails.
+/// The caller decides whether to trust them; this runs what it is given.
+pub fn run_post_gen(spec: &Spec, project_dir: &Path) -> Result<Vec<HookRun>> {
+    let mut runs = Vec::new();
+    for command in &spec.hooks.post_gen {
+        let output = shell(command)
+            .current_dir(project_dir)
+            .output()
+            .with_context(|| format!("running post-gen hook `{command}`"))?;
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let success = output.status.success();
+        runs.push(HookRun {
+            command: command.clone(),
+            success,
+            output: text,
+        });
+        if !success {
+            break;
+        }
+    }
+    Ok(runs)
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
@@ -0,0 +1,404 @@
+//! `gen --into`: generating into an existing project. Its pyproject.toml
+//! takes the agent's requirements and entry point in place of
+//! requirements.txt, and its package.json gets scripts to install and run
+//! the agent, so the repository keeps one dependency file.
+
+use crate::generate::GeneratedFile;
+use crate::spec::{DeployTarget, Interface, Lang, Spec};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+const PYPROJECT: &str = "pyproject.toml";
+const PACKAGE_JSON: &str = "package.json";
+const REQUIREMENTS: &str = "requirements.txt";
+
+/// Fold the rendered `files` into the manifests already in `project_dir`.
+/// Only Python projects have requirements to merge.
+pub(crate) fn adopt(spec: &Spec, project_dir: &Path, files: &mut Vec<GeneratedFile>) -> Result<()> {
+    if spec.lang != Lang::Python {
+        return Ok(());
+    }
+    let read = |name: &str| -> Result<Option<String>> {
+        let path = project_dir.join(name);
+        match fs::read_to_string(&path) {
+            Ok(text) => Ok(Some(text)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+        }
+    };
+    let pyproject = read(PYPROJECT)?;
+    let install = if let Some(existing) = &pyproject {
+        if spec.deploy.contains(&DeployTarget::Devcontainer) {
+            bail!(
+                "the devcontainer image installs requirements.txt, which --into folds into {PYPROJECT}; drop deploy devcontainer or generate a new project"
+            );
+        }
+        let requirements = take(files, REQUIREMENTS).expect("Python projects have requirements");
+        let requirements: Vec<&str> = requirements
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let mut merged = add_dependencies(existing, &requirements)
+            .with_context(|| format!("merging the requirements into {PYPROJECT}"))?;
+        if spec.has_interface(Interface::Cli) {
+            merged = add_script(&merged, &spec.names().slug, "cli:main");
+        }
+        // The quality section's tool tables, where the project has none yet
+        if let Some(tables) = take(files, PYPROJECT) {
+            merged = add_tables(&merged, &tables);
+        }
+        // Everything that installed requirements.txt installs the project
+        for file in files.iter_mut() {
+            if let Ok(text) = std::str::from_utf8(&file.contents) {
+                if text.contains("-r requirements.txt") {
+                    file.contents = text.replace("-r requirements.txt", "-e .").into_bytes();
+                }
+            }
+        }
+        files.push(GeneratedFile::existing(PYPROJECT, merged));
+        "pip install -e ."
+    } else {
+        "pip install -r requirements.txt"
+    };
+
+    if let Some(existing) = read(PACKAGE_JSON)? {
+        let slug = spec.names().slug;
+        let mut scripts = vec![(format!("{slug}:install"), install.to_string())];
+        for (interface, suffix, script) in [
+            (Interface::Cli, "", "cli.py"),
+            (Interface::Server, ":server", "server.py"),
+            (Interface::Gradio, ":gradio", "gradio_app.py"),
+        ] {
+            if spec.has_interface(interface) {
+                scripts.push((format!("{slug}{suffix}"), format!("python {script}")));
+            }
+        }
+        let merged = add_npm_scripts(&existing, &scripts)
+            .with_context(|| format!("adding scripts to {PACKAGE_JSON}"))?;
+        files.push(GeneratedFile::existing(PACKAGE_JSON, merged));
+    }
+    Ok(())
+}
+
+/// Remove the file at `path` from `files`, returning its text.
+fn take(files: &mut Vec<GeneratedFile>, path: &str) -> Option<String> {
+    let index = files.iter().position(|f| f.path == path)?;
+    let file = files.remove(index);
+    Some(String::from_utf8_lossy(&file.contents).into_owned())
+}
+
+/// A requirement's distribution name as pip compares them: `Foo_Bar[x]>=1`
+/// and `foo-bar` are the same package.
+fn package_name(requirement: &str) -> String {
+    requirement
+        .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+        .replace(['_', '.'], "-")
+}
+
+/// Byte ranges of the TOML tables in `text`: the header's name, where the
+/// header line starts and where the table's last line ends.
+fn tables(text: &str) -> Vec<(String, usize, usize)> {
+    let mut tables: Vec<(String, usize, usize)> = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let end = offset + line.len();
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .filter(|rest| !rest.starts_with('['))
+            .and_then(|rest| rest.split(']').next())
+        {
+            tables.push((name.trim().to_string(), offset, end));
+        } else if trimmed.starts_with("[[") {
+            tables.push((String::new(), offset, end));
+        } else if let Some(last) = tables.last_mut().filter(|_| !trimmed.is_empty()) {
+            last.2 = end;
+        }
+        offset += line.len();
+    }
+    tables
+}
+
+/// The first key `key = ...` in `text[start..end]`, as the offset just
+/// after its `=`.
+fn find_key(text: &str, start: usize, end: usize, key: &str) -> Option<usize> {
+    let mut offset = start;
+    for line in text[start..end].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let name = trimmed
+            .split('=')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches('"');
+        if name == key && trimmed.contains('=') {
+            return Some(offset + (line.len() - trimmed.len()) + trimmed.find('=')? + 1);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The TOML array starting at or after `from`: the strings in it, where its
+/// `]` is and the last byte before that which is not space or a comment.
+fn parse_array(text: &str, from: usize) -> Result<(Vec<String>, usize, usize)> {
+    let open = from
+        + text[from..]
+            .find('[')
+            .context("dependencies is not an array")?;
+    let bytes = text.as_bytes();
+    let (mut strings, mut last) = (Vec::new(), open);
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b']' => return Ok((strings, i, last)),
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' && quote == b'"' {
+                        2
+                    } else {
+                        1
+                    };
+                }
+                strings.push(text[start..i.min(text.len())].to_string());
+                last = i;
+            }
+            b if !b.is_ascii_whitespace() => last = i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bail!("dependencies has no closing ]")
+}
+
+/// `[project] dependencies` in `pyproject` with the `requirements` it does
+/// not list yet appended.
+fn add_dependencies(pyproject: &str, requirements: &[&str]) -> Result<String> {
+    let tables = tables(pyproject);
+    let Some((_, start, end)) = tables.iter().find(|(name, ..)| name == "project") else {
+        bail!("it has no [project] table to list them in");
+    };
+    let entry = |req: &str| serde_json::to_string(req).expect("strings serialize");
+    let Some(value) = find_key(pyproject, *start, *end, "dependencies") else {
+        let list: String = requirements
+            .iter()
+            .map(|req| format!("    {},\n", entry(req)))
+            .collect();
+        let mut out = pyproject.to_string();
+        let at = if out[..*end].ends_with('\n') {
+            *end
+        } else {
+            out.insert(*end, '\n');
+            *end + 1
+        };
+        out.insert_str(at, &format!("dependencies = [\n{list}]\n"));
+        return Ok(out);
+    };
+    let (listed, close, last) = parse_array(pyproject, value)?;
+    let listed: Vec<String> = listed.iter().map(|req| package_name(req)).collect();
+    let missing: Vec<&&str> = requirements
+        .iter()
+        .filter(|req| !listed.contains(&package_name(req)))
+        .collect();
+    if missing.is_empty() {
+        return Ok(pyproject.to_string());
+    }
+    let mut out = pyproject.to_string();
+    let comma = if matches!(pyproject.as_bytes()[last], b'[' | b',') {
+        ""
+    } else {
+        ","
+    };
+    if pyproject[last..close].contains('\n') {
+        // One requirement per line, before the line with the `]`
+        let line_start = pyproject[..close].rfind('\n').map_or(0, |i| i + 1);
+        let indent = pyproject[value..]
+            .lines()
+            .nth(1)
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .filter(|indent| !indent.is_empty())
+            .unwrap_or("    ");
+        let list: String = missing
+            .iter()
+            .map(|req| format!("{indent}{},\n", entry(req)))
+            .collect();
+        out.insert_str(line_start, &list);
+        out.insert_str(last + 1, comma);
+    } else {
+        let list: Vec<String> = missing.iter().map(|req| entry(req)).collect();
+        let space = if comma.is_empty() && pyproject.as_bytes()[last] == b'[' {
+            ""
+        } else {
+            " "
+        };
+        out.insert_str(close, &format!("{comma}{space}{}", list.join(", ")));
+    }
+    Ok(out)
+}
+
+/// `pyproject` with a `[project.scripts]` entry point `name`, unless it has
+/// one by that name or declares its scripts inline.
+fn add_script(pyproject: &str, name: &str, target: &str) -> String {
+    let tables = tables(pyproject);
+    let line = format!("{name} = \"{target}\"\n");
+    if let Some((_, start, end)) = tables.iter().find(|(table, ..)| table == "project") {
+        if find_key(pyproject, *start, *end, "scripts").is_some() {
+            return pyproject.to_string();
+        }
+    }
+    let mut out = pyproject.to_string();
+    match tables.iter().find(|(table, ..)| table == "project.scripts") {
+        Some((_, start, end)) => {
+            if find_key(pyproject, *start, *end, name).is_none() {
+                let at = if out[..*end].ends_with('\n') {
+                    *end
+                } else {
+                    out.insert(*end, '\n');
+                    *end + 1
+                };
+                out.insert_str(at, &line);
+            }
+        }
+        None => {
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(&format!("\n[project.scripts]\n{line}"));
+        }
+    }
+    out
+}
+
+/// `pyproject` followed by each table in `generated` it does not have.
+fn add_tables(pyproject: &str, generated: &str) -> String {
+    let existing: Vec<String> = tables(pyproject).into_iter().map(|t| t.0).collect();
+    let mut out = pyproject.to_string();
+    for (name, start, end) in tables(generated) {
+        if !existing.contains(&name) {
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push('\n');
+            out.push_str(generated[start..end].trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `package_json` with the `scripts` it does not define yet added to its
+/// `scripts` object, leaving the rest of the file as written.
+fn add_npm_scripts(package_json: &str, scripts: &[(String, String)]) -> Result<String> {
+    let parsed: serde_json::Value =
+        serde_json::from_str(package_json).context("it is not valid JSON")?;
+    let Some(object) = parsed.as_object() else {
+        bail!("it is not a JSON object");
+    };
+    let defined = object.get("scripts").and_then(|s| s.as_object());
+    let entries: Vec<String> = scripts
+        .iter()
+        .filter(|(name, _)| defined.is_none_or(|d| !d.contains_key(name)))
+        .map(|(name, command)| {
+            format!(
+                "{}: {}",
+                serde_json::to_string(name).expect("strings serialize"),
+                serde_json::to_string(command).expect("strings serialize")
+            )
+        })
+        .collect();
+    if entries.is_empty() {
+        return Ok(package_json.to_string());
+    }
+    // npm writes two spaces; follow whatever the file's first key uses
+    let indent = package_json
+        .lines()
+        .skip(1)
+        .find(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or("  ");
+    let mut out = package_json.to_string();
+    let (close, text, closing) = match scripts_object(package_json) {
+        Some(close) => {
+            let inner = indent.repeat(2);
+            let text: Vec<String> = entries
+                .iter()
+                .map(|entry| format!("\n{inner}{entry}"))
+                .collect();
+            (close, text.join(","), format!("\n{indent}"))
+        }
+        None => {
+            let close = package_json.rfind('}').expect("a JSON object ends with }");
+            let inner = indent.repeat(2);
+            let body: Vec<String> = entries
+                .iter()
+                .map(|entry| format!("{inner}{entry}"))
+                .collect();
+            let text = format!(
+                "\n{indent}\"scripts\": {{\n{}\n{indent}}}",
+                body.join(",\n")
+            );
+            (close, text, "\n".to_string())
+        }
+    };
+    // After the last entry, keeping the space before the closing brace
+    let last = package_json[..close].trim_end().len() - 1;
+    if package_json.as_bytes()[last] == b'{' {
+        out.replace_range(last + 1..close, &format!("{text}{closing}"));
+    } else {
+        out.insert_str(last + 1, &format!(",{text}"));
+    }
+    Ok(out)
+}
+
+/// Where the top-level `"scripts"` object of a package.json closes.
+fn scripts_object(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let (mut depth, mut i) = (0, 0);
+    let mut key: Option<(usize, usize)> = None;
+    let mut open = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                key = Some((start, i));
+            }
+            b':' if depth == 1
+                && key.is_some_and(|(start, end)| &text[start..end] == "scripts") =>
+            {
+                let next = i + 1 + text[i + 1..].find(|c: char| !c.is_whitespace())?;
+                if bytes[next] != b'{' {
+                    return None;
+                }
+                open = true;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 1 && open && bytes[i] == b'}' {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
@@ -0,0 +1,93 @@
+use crate::generate::{GeneratedFile, PYTHON_VERSION};
+use crate::spec::{Formatter, Quality};
+use anyhow::{bail, Context, Result};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Hook versions pinned in .pre-commit-config.yaml; `pre-commit autoupdate` bumps them.
+const RUFF_PRE_COMMIT_REV: &str = "v0.6.9";
+const BLACK_PRE_COMMIT_REV: &str = "24.8.0";
+
+/// `py312` from `3.12`, as ruff and black spell target versions.
+fn target_version() -> String {
+    format!("py{}", PYTHON_VERSION.replace('.', ""))
+}
+
+pub(crate) fn render_pyproject(quality: &Quality) -> String {
+    let target = target_version();
+    let mut out = format!(
+        "[tool.ruff]\nline-length = {}\ntarget-version = \"{target}\"\n",
+        quality.line_length
+    );
+    if quality.formatter == Formatter::Black {
+        out.push_str(&format!(
+            "\n[tool.black]\nline-length = {}\ntarget-version = [\"{target}\"]\n",
+            quality.line_length
+        ));
+    }
+    out
+}
+
+pub(crate) fn render_pre_commit(quality: &Quality) -> String {
+    let mut out = format!(
+        "# Install the hooks with `pre-commit install`\n\
+         repos:\n  \
+         - repo: https://github.com/astral-sh/ruff-pre-commit\n    \
+         rev: {RUFF_PRE_COMMIT_REV}\n    \
+         hooks:\n      \
+         - id: ruff\n        \
+         args: [--fix]\n"
+    );
+    match quality.formatter {
+        Formatter::Ruff => out.push_str("      - id: ruff-format\n"),
+        Formatter::Black => out.push_str(&format!(
+            "  - repo: https://github.com/psf/black-pre-commit-mirror\n    \
+             rev: {BLACK_PRE_COMMIT_REV}\n    \
+             hooks:\n      \
+             - id: black\n"
+        )),
+    }
+    out
+}
+
+/// Format the Python files through `ruff format` with the spec's settings.
+/// Leaves them as rendered when ruff is not installed.
+pub(crate) fn format_python(files: &mut [GeneratedFile], quality: &Quality) -> Result<()> {
+    for file in files
+        .iter_mut()
+        .filter(|f| !f.asset && f.path.ends_with(".py"))
+    {
+        let child = Command::new("ruff")
+            .args(["format", "--isolated", "--quiet"])
+            .arg(format!("--line-length={}", quality.line_length))
+            .arg(format!("--target-version={}", target_version()))
+            .arg(format!("--stdin-filename={}", file.path))
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err).context("running ruff format"),
+        };
+        // ruff reads all of stdin before writing, so this cannot deadlock
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(&file.contents)
+            .context("running ruff format")?;
+        let output = child.wait_with_output().context("running ruff format")?;
+        if !output.status.success() {
+            bail!(
+                "ruff could not format {}: {}",
+                file.path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        file.contents = output.stdout;
+    }
+    Ok(())
+}
@@ -0,0 +1,417 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// Registry `tachi registry` and `tachi gen registry:<ref>` use when none is
+/// given: `oci://<host>/<namespace>` or an `http(s)://` URL
+pub const REGISTRY_ENV: &str = "TACHI_REGISTRY";
+/// Sent as a bearer token with every registry request when set
+const TOKEN_ENV: &str = "TACHI_REGISTRY_TOKEN";
+/// Media type of a spec stored as an OCI artifact
+const SPEC_MEDIA_TYPE: &str = "application/vnd.tachi.spec.v1+yaml";
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// OCI artifacts without a config point at this two-byte blob
+const EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const EMPTY_CONFIG: &[u8] = b"{}";
+const DEFAULT_TAG: &str = "latest";
+
+/// Where specs are pushed to and pulled from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Registry {
+    /// Plain files under `base`: `<name>/<tag>` and `<name>/sha256:<hex>`,
+    /// written with PUT. Any server or bucket that takes PUT will do.
+    Http { base: String },
+    /// An OCI distribution registry, with each spec an artifact in
+    /// `<namespace>/<name>`
+    Oci { host: String, namespace: String },
+}
+
+impl Registry {
+    /// The registry `url` names: `oci://<host>/<namespace>` or `http(s)://...`.
+    pub fn from_url(url: &str) -> Result<Self> {
+        let url = url.trim_end_matches('/');
+        if let Some(rest) = url.strip_prefix("oci://") {
+            let (host, namespace) = rest.split_once('/').unwrap_or((rest, ""));
+            if host.is_empty() {
+                bail!("registry {url} has no host");
+            }
+            return Ok(Registry::Oci {
+                host: host.to_string(),
+                namespace: namespace.to_string(),
+            });
+        }
+        if url.starts_with("https://") || url.starts_with("http://") {
+            return Ok(Registry::Http {
+                base: url.to_string(),
+            });
+        }
+        bail!("registry {url} is neither oci://<host>/<namespace> nor an http(s):// URL")
+    }
+
+    /// `url`, or the registry in `TACHI_REGISTRY` without one.
+    pub fn resolve(url: Option<&str>) -> Result<Self> {
+        match url {
+            Some(url) => Registry::from_url(url),
+            None => match std::env::var(REGISTRY_ENV) {
+                Ok(url) if !url.is_empty() => {
+                    Registry::from_url(&url).with_context(|| format!("reading {REGISTRY_ENV}"))
+                }
+                _ => bail!("no registry; pass --registry or set {REGISTRY_ENV}"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Registry::Http { base } => write!(f, "{base}"),
+            Registry::Oci { host, namespace } if namespace.is_empty() => write!(f, "oci://{host}"),
+            Registry::Oci { host, namespace } => write!(f, "oci://{host}/{namespace}"),
+        }
+    }
+}
+
+/// A spec in a registry: `name`, `name:tag` or `name@sha256:<hex>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecRef {
+    pub name: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl FromStr for SpecRef {
+    type Err = anyhow::Error;
+
+    fn from_str(reference: &str) -> Result<Self> {
+        let (name, tag, digest) = match reference.split_once('@') {
+            Some((name, digest)) => {
+                let hex = digest.strip_prefix("sha256:").unwrap_or_default();
+                if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    bail!("{digest} is not a sha256:<64 hex digits> digest");
+                }
+                (name, None, Some(digest.to_ascii_lowercase()))
+            }
+            None => match reference.rsplit_once(':') {
+                Some((name, tag)) => (name, Some(tag.to_string()), None),
+                None => (reference, None, None),
+            },
+        };
+        let valid_name = !name.is_empty()
+            && name
+                .split('/')
+                .all(|part| !part.is_empty() && part.bytes().all(is_name_byte));
+        if !valid_name {
+            bail!("{name:?} is not a spec name; use lowercase letters, digits, `-`, `_` and `.`, with `/` between parts");
+        }
+        if let Some(tag) = &tag {
+            let valid_tag = !tag.is_empty()
+                && tag.len() <= 128
+                && tag
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+                && !tag.starts_with(['-', '.']);
+            if !valid_tag {
+                bail!("{tag:?} is not a tag; use letters, digits, `-`, `_` and `.`");
+            }
+        }
+        Ok(SpecRef {
+            name: name.to_string(),
+            tag,
+            digest,
+        })
+    }
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'-' | b'_' | b'.')
+}
+
+impl SpecRef {
+    /// The tag, or digest, the reference points at; `latest` without either.
+    fn version(&self) -> &str {
+        self.digest
+            .as_deref()
+            .or(self.tag.as_deref())
+            .unwrap_or(DEFAULT_TAG)
+    }
+}
+
+impl fmt::Display for SpecRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.digest, &self.tag) {
+            (Some(digest), _) => write!(f, "{}@{digest}", self.name),
+            (None, Some(tag)) => write!(f, "{}:{tag}", self.name),
+            (None, None) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Where a spec came from, as tachi.lock records it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecSource {
+    pub registry: String,
+    pub name: String,
+    /// The tag pulled; `None` when pulled by digest
+    pub tag: Option<String>,
+    /// `sha256:<hex>` of the spec file
+    pub digest: String,
+}
+
+/// Store `yaml` in `registry` as `reference`, which takes a tag (`latest`
+/// without one) rather than a digest. The spec can be pulled by the tag or
+/// by the digest returned in the source.
+pub fn push_spec(registry: &Registry, reference: &SpecRef, yaml: &str) -> Result<SpecSource> {
+    if reference.digest.is_some() {
+        bail!("push to a tag, not a digest: the digest is the spec's hash");
+    }
+    let tag = reference.tag.as_deref().unwrap_or(DEFAULT_TAG);
+    let digest = sha256(yaml.as_bytes());
+    match registry {
+        Registry::Http { base } => {
+            for version in [tag, &digest] {
+                let url = format!("{base}/{}/{version}", reference.name);
+                request("PUT", &url, &[], Some(yaml.as_bytes()))?;
+            }
+        }
+        Registry::Oci { .. } => {
+            let repo = oci_repo(registry, reference);
+            push_blob(&repo, EMPTY_CONFIG)?;
+            push_blob(&repo, yaml.as_bytes())?;
+            let manifest = serde_json::json!({
+                "schemaVersion": 2,
+                "mediaType": MANIFEST_MEDIA_TYPE,
+                "artifactType": SPEC_MEDIA_TYPE,
+                "config": {
+                    "mediaType": EMPTY_MEDIA_TYPE,
+                    "digest": sha256(EMPTY_CONFIG),
+                    "size": EMPTY_CONFIG.len(),
+                },
+                "layers": [{
+                    "mediaType": SPEC_MEDIA_TYPE,
+                    "digest": digest,
+                    "size": yaml.len(),
+                    "annotations": { "org.opencontainers.image.title": "spec.yml" },
+                }],
+            });
+            request(
+                "PUT",
+                &format!("{repo}/manifests/{tag}"),
+                &[format!("Content-Type: {MANIFEST_MEDIA_TYPE}")],
+                Some(manifest.to_string().as_bytes()),
+            )?;
+        }
+    }
+    Ok(SpecSource {
+        registry: registry.to_string(),
+        name: reference.name.clone(),
+        tag: Some(tag.to_string()),
+        digest,
+    })
+}
+
+/// The spec `reference` names in `registry`, once its hash matches the
+/// digest it was pulled by or the registry lists for it.
+pub fn pull_spec(registry: &Registry, reference: &SpecRef) -> Result<(String, SpecSource)> {
+    let (body, expected) = match registry {
+        Registry::Http { base } => {
+            let url = format!("{base}/{}/{}", reference.name, reference.version());
+            (
+                request("GET", &url, &[], None)?.body,
+                reference.digest.clone(),
+            )
+        }
+        Registry::Oci { .. } => {
+            let repo = oci_repo(registry, reference);
+            // A digest is the spec's own, so it names the blob directly
+            let digest = match &reference.digest {
+                Some(digest) => digest.clone(),
+                None => spec_layer(&repo, reference.version())?,
+            };
+            let body = request("GET", &format!("{repo}/blobs/{digest}"), &[], None)?.body;
+            (body, Some(digest))
+        }
+    };
+    let digest = sha256(&body);
+    if let Some(expected) = expected {
+        if digest != expected {
+            bail!("{reference} from {registry} has digest {digest}, not {expected}");
+        }
+    }
+    let yaml = String::from_utf8(body)
+        .with_context(|| format!("{reference} from {registry} is not UTF-8"))?;
+    let source = SpecSource {
+        registry: registry.to_string(),
+        name: reference.name.clone(),
+        tag: match reference.digest {
+            Some(_) => None,
+            None => Some(reference.version().to_string()),
+        },
+        digest,
+    };
+    Ok((yaml, source))
+}
+
+/// `https://<host>/v2/<namespace>/<name>`; registries on localhost are
+/// reached over plain HTTP, as Docker does.
+fn oci_repo(registry: &Registry, reference: &SpecRef) -> String {
+    let Registry::Oci { host, namespace } = registry else {
+        unreachable!("oci_repo on an HTTP registry");
+    };
+    let local = ["localhost", "127.0.0.1", "[::1]"]
+        .iter()
+        .any(|name| host == name || host.starts_with(&format!("{name}:")));
+    let scheme = if local { "http" } else { "https" };
+    if namespace.is_empty() {
+        format!("{scheme}://{host}/v2/{}", reference.name)
+    } else {
+        format!("{scheme}://{host}/v2/{namespace}/{}", reference.name)
+    }
+}
+
+/// Upload `data` to `repo` unless the registry already has it.
+fn push_blob(repo: &str, data: &[u8]) -> Result<()> {
+    let digest = sha256(data);
+    if request("HEAD", &format!("{repo}/blobs/{digest}"), &[], None).is_ok() {
+        return Ok(());
+    }
+    let started = request("POST", &format!("{repo}/blobs/uploads/"), &[], None)?;
+    let location = started
+        .header("location")
+        .with_context(|| format!("{repo} started an upload without a Location"))?;
+    // The location may be relative to the registry's root
+    let upload = if location.starts_with('/') {
+        let root = repo.find("/v2/").map_or(repo, |at| &repo[..at]);
+        format!("{root}{location}")
+    } else {
+        location.to_string()
+    };
+    let separator = if upload.contains('?') { '&' } else { '?' };
+    request(
+        "PUT",
+        &format!("{upload}{separator}digest={digest}"),
+        &["Content-Type: application/octet-stream".to_string()],
+        Some(data),
+    )?;
+    Ok(())
+}
+
+/// Digest of the spec layer in the manifest tagged `tag`.
+fn spec_layer(repo: &str, tag: &str) -> Result<String> {
+    let url = format!("{repo}/manifests/{tag}");
+    let response = request(
+        "GET",
+        &url,
+        &[format!("Accept: {MANIFEST_MEDIA_TYPE}")],
+        None,
+    )?;
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&response.body).with_context(|| format!("parsing {url}"))?;
+    let layers = manifest["layers"].as_array().map(Vec::as_slice);
+    let layer = layers
+        .unwrap_or_default()
+        .iter()
+        .find(|layer| layer["mediaType"] == SPEC_MEDIA_TYPE)
+        .with_context(|| format!("{url} is not a tachi spec; it has no {SPEC_MEDIA_TYPE} layer"))?;
+    layer["digest"]
+        .as_str()
+        .map(str::to_string)
+        .with_context(|| format!("{url} lists the spec without a digest"))
+}
+
+fn sha256(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+struct Response {
+    /// (lowercased name, value)
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// One request through `curl`, failing unless the response is a 2xx.
+fn request(method: &str, url: &str, headers: &[String], body: Option<&[u8]>) -> Result<Response> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--include"])
+        .args(["--proto", "=https,http"])
+        // No `100 Continue` round trip before a body
+        .args(["--header", "Expect:"]);
+    match method {
+        "HEAD" => command.arg("--head"),
+        _ => command.args(["--request", method]),
+    };
+    for header in headers {
+        command.args(["--header", header]);
+    }
+    if let Ok(token) = std::env::var(TOKEN_ENV) {
+        command.args(["--header", &format!("Authorization: Bearer {token}")]);
+    }
+    if body.is_some() {
+        command.args(["--data-binary", "@-"]);
+    }
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("running curl, which the registry is reached with")?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(body) = body {
+        stdin
+            .write_all(body)
+            .with_context(|| format!("sending the body of {method} {url}"))?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "{method} {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let (status, response) = parse_response(&output.stdout)
+        .with_context(|| format!("{method} {url}: curl printed no HTTP response"))?;
+    if !(200..300).contains(&status) {
+        let text = String::from_utf8_lossy(&response.body);
+        let text: String = text.trim().chars().take(200).collect();
+        bail!("{method} {url}: HTTP {status} {text}");
+    }
+    Ok(response)
+}
+
+/// Status, headers and body of curl's `--include` output, past any 1xx.
+fn parse_response(raw: &[u8]) -> Option<(u16, Response)> {
+    let mut rest = raw;
+    loop {
+        let end = rest.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&rest[..end]);
+        rest = &rest[end + 4..];
+        let mut lines = head.lines();
+        let status: u16 = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+        if (100..200).contains(&status) {
+            continue;
+        }
+        let headers = lines
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+        let body = rest.to_vec();
+        return Some((status, Response { headers, body }));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::spec::{Spec, Tool};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// What one spec uses, for inventories across many agents. It holds no
+/// names, descriptions, prompts, URLs or values, only which features are on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SpecReport {
+    pub lang: String,
+    pub framework: String,
+    pub model: String,
+    pub tools: Vec<String>,
+    /// `kind:provider` for each backend the agent talks to, such as `search:brave`
+    pub providers: Vec<String>,
+    pub interfaces: Vec<String>,
+    pub deploy: Vec<String>,
+    pub ci: Option<String>,
+    pub packaging: String,
+    pub tasks: Option<String>,
+    pub smolagents_version: Option<String>,
+    /// Spec sections and switches that are on, by their spec key
+    pub features: Vec<String>,
+}
+
+impl SpecReport {
+    /// CSV columns, in the order `csv_fields` returns them.
+    pub const CSV_HEADER: &'static [&'static str] = &[
+        "lang",
+        "framework",
+        "model",
+        "tools",
+        "providers",
+        "interfaces",
+        "deploy",
+        "ci",
+        "packaging",
+        "tasks",
+        "smolagents-version",
+        "features",
+    ];
+
+    /// One CSV row's fields, lists joined with spaces; unescaped.
+    pub fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.lang.clone(),
+            self.framework.clone(),
+            self.model.clone(),
+            self.tools.join(" "),
+            self.providers.join(" "),
+            self.interfaces.join(" "),
+            self.deploy.join(" "),
+            self.ci.clone().unwrap_or_default(),
+            self.packaging.clone(),
+            self.tasks.clone().unwrap_or_default(),
+            self.smolagents_version.clone().unwrap_or_default(),
+            self.features.join(" "),
+        ]
+    }
+}
+
+/// Summarize which tools, providers, targets and features `spec` uses.
+pub fn report(spec: &Spec) -> SpecReport {
+    let mut providers = Vec::new();
+    for tool in &spec.agent.tools {
+        if let Tool::Search(search) = &tool.tool {
+            providers.push(format!("search:{}", search.provider.name()));
+        }
+    }
+    if let Some(cache) = &spec.cache {
+        providers.push(format!("cache:{}", variant(&cache.backend)));
+    }
+    if spec.worker.enabled {
+        providers.push(format!("worker:{}", variant(&spec.worker.backend)));
+    }
+    if let Some(memory) = &spec.memory {
+        providers.push(format!("memory:{}", variant(&memory.kind)));
+    }
+    for environment in spec.environments.values() {
+        if let Some(provider) = &environment.model_provider {
+            providers.push(format!("inference:{provider}"));
+        }
+        if environment.model_endpoint.is_some() {
+            providers.push("inference:endpoint".to_string());
+        }
+    }
+    let mut seen = HashSet::new();
+    providers.retain(|p| seen.insert(p.clone()));
+
+    let mut features: Vec<String> = spec
+        .agent
+        .capabilities
+        .iter()
+        .map(|c| c.name().to_string())
+        .collect();
+    let switches = [
+        ("add-base-tools", spec.agent.add_base_tools),
+        ("response-format", spec.agent.response_format.is_some()),
+        ("injection-filter", spec.guardrails.injection_filter),
+        ("approval", spec.approval.is_some()),
+        ("hooks", spec.hooks.agent),
+        ("post-gen", !spec.hooks.post_gen.is_empty()),
+        ("planning", spec.planning.enabled),
+        ("cache", spec.cache.is_some()),
+        ("limits", spec.limits.is_some()),
+        ("security", spec.security.is_some()),
+        ("a2a", spec.a2a.enabled),
+        ("batch", spec.batch.enabled),
+        ("worker", spec.worker.enabled),
+        ("pipeline", spec.pipeline.is_some()),
+        ("router", spec.router.is_some()),
+        ("memory", spec.memory.is_some()),
+        ("knowledge", spec.knowledge.is_some()),
+        ("environments", !spec.environments.is_empty()),
+        ("quality", spec.quality.is_some()),
+        ("license", spec.license.is_some()),
+    ];
+    features.extend(
+        switches
+            .iter()
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| name.to_string()),
+    );
+
+    SpecReport {
+        lang: spec.lang.name().to_string(),
+        framework: spec.framework.name().to_string(),
+        model: spec.model_id().to_string(),
+        tools: spec
+            .agent
+            .tools
+            .iter()
+            .map(|t| t.kind().to_string())
+            .collect(),
+        providers,
+        interfaces: spec
+            .interfaces
+            .iter()
+            .map(|i| i.name().to_string())
+            .collect(),
+        deploy: spec.deploy.iter().map(|d| d.name().to_string()).collect(),
+        ci: spec.ci.as_ref().map(|ci| variant(&ci.provider)),
+        packaging: variant(&spec.packaging),
+        tasks: spec.tasks.as_ref().map(variant),
+        smolagents_version: spec.runtime.smolagents_version.map(|v| v.to_string()),
+        features,
+    }
+}
+
+/// A unit enum's name as the spec spells it.
+fn variant<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,1113 @@
+use crate::spec::{
+    BaseTool, BundleFormat, CacheBackend, Capability, CiDocker, CiProvider, CliFramework, CliInput,
+    CliUi, DeployTarget, Formatter, Framework, HttpMethod, Interface, Lang, License, MemoryKind,
+    MemoryStore, Model, Packaging, SearchProvider, ServerAuth, TaskRunner, TimerKind, ToolKind,
+    WhisperModel, WorkerBackend,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// JSON Schema for spec files. Enum values come from serde so they always
+/// match what the parser accepts.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "tachi spec",
+        "type": "object",
+        "required": ["agent"],
+        "properties": {
+            "agent": {
+                "type": "object",
+                "description": "The agent to generate",
+                "required": ["name", "tools"],
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Display name; the slug and package name derive from it",
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "What the agent is for, shown in its A2A agent card",
+                    },
+                    "slug": {
+                        "type": "string",
+                        "pattern": "^[a-z0-9][a-z0-9._-]*$",
+                        "description": "Output directory and image name",
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Python package name",
+                    },
+                    "tools": {
+                        "type": "array",
+                        "uniqueItems": true,
+                        "description": "Tools handed to the agent: a name, or a one-key map of name to options",
+                        "items": {
+                            "anyOf": [
+                                { "enum": variants(ToolKind::ALL) },
+                                {
+                                    "type": "object",
+                                    "minProperties": 1,
+                                    "maxProperties": 1,
+                                    "properties": tool_options(),
+                                },
+                            ],
+                        },
+                    },
+                    "add-base-tools": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Also hand the agent smolagents' base toolbox",
+                    },
+                    "exclude-base-tools": {
+                        "type": "array",
+                        "uniqueItems": true,
+                        "items": { "enum": variants(BaseTool::ALL) },
+                        "description": "Base tools to leave out again; needs add-base-tools",
+                    },
+                    "model": {
+                        "enum": variants(Model::ALL),
+                        "description": "Hugging Face inference model; qwen-coder when unset",
+                    },
+                    "capabilities": {
+                        "type": "array",
+                        "uniqueItems": true,
+                        "description": "Input beyond text that the generated CLI accepts",
+                        "items": { "enum": variants(Capability::ALL) },
+                    },
+                    "response-format": {
+                        "description": "Make answers JSON, checked after each run",
+                        "oneOf": [
+                            { "const": "json" },
+                            {
+                                "type": "object",
+                                "required": ["json-schema"],
+                                "additionalProperties": false,
+                                "properties": {
+                                    "json-schema": {
+                                        "type": "object",
+                                        "description": "JSON Schema every answer must match",
+                                    },
+                                },
+                            },
+                        ],
+                    },
+                    "max-steps": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Steps one run may take before it gives up; smolagents' 20 when unset",
+                    },
+                    "temperature": {
+                        "type": "number",
+                        "minimum": 0,
+                        "maximum": 2,
+                        "description": "Sampling temperature of the model; the provider's default when unset",
+                    },
+                },
+            },
+            "lang": {
+                "enum": variants(Lang::ALL),
+                "description": "Language of the generated project; python when unset. csharp needs framework semantic-kernel, go needs openai-assistants, java needs spring-ai",
+            },
+            "framework": {
+                "enum": variants(Framework::ALL),
+                "description": "Library the generated agent runs on; smolagents when unset",
+            },
+            "openai": {
+                "type": "object",
+                "description": "Settings for framework openai-assistants",
+                "properties": {
+                    "model": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "Model the Responses API runs, such as gpt-4.1; OPENAI_MODEL overrides it",
+                    },
+                    "max-steps": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Model turns one run may take before it gives up",
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "autogen": {
+                "type": "object",
+                "description": "Settings for framework autogen",
+                "properties": {
+                    "model": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "OpenAI model every AG2 agent runs, such as gpt-4.1; AUTOGEN_MODEL overrides it",
+                    },
+                    "max-rounds": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Rounds one run may take before the chat stops",
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "semantic-kernel": {
+                "type": "object",
+                "description": "Settings for framework semantic-kernel",
+                "properties": {
+                    "model": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "OpenAI chat model the kernel runs, such as gpt-4.1; SEMANTIC_KERNEL_MODEL overrides it",
+                    },
+                    "max-steps": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Model turns one run may take before it gives up",
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "spring-ai": {
+                "type": "object",
+                "description": "Settings for framework spring-ai",
+                "properties": {
+                    "model": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "OpenAI chat model the agent runs, such as gpt-4.1; OPENAI_MODEL overrides it",
+                    },
+                    "max-steps": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Model turns one run may take before it gives up",
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "llamaindex": {
+                "type": "object",
+                "description": "Settings for framework llamaindex, which runs agent.model",
+                "properties": {
+                    "max-iterations": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Reasoning steps one run may take before it gives up",
+                    },
+                },
+                "additionalProperties": false,
+            },
+            "guardrails": {
+                "type": "object",
+                "description": "Safety filters applied around tools",
+                "properties": {
+                    "injection-filter": {
+                        "type": "boolean",
+                        "description": "Strip instruction-like content from web tool output before the model sees it",
+                    },
+                },
+            },
+            "hooks": {
+                "description": "Agent hooks in hooks.py, and commands to run after generating; true is short for {agent: true}",
+                "oneOf": [
+                    { "type": "boolean" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "agent": {
+                                "type": "boolean",
+                                "description": "Call the step, tool-call and final-answer hooks in hooks.py",
+                            },
+                            "on-step": {
+                                "type": "string",
+                                "description": "Python body of on_step(step); any hook body makes hooks.py generated rather than user-owned",
+                            },
+                            "on-tool-call": {
+                                "type": "string",
+                                "description": "Python body of on_tool_call(name, arguments, result)",
+                            },
+                            "on-final-answer": {
+                                "type": "string",
+                                "description": "Python body of on_final_answer(answer); return a value to replace the answer",
+                            },
+                            "post-gen": {
+                                "type": "array",
+                                "description": "Shell commands run in the project directory after tachi gen, such as `ruff format .`",
+                                "items": { "type": "string", "minLength": 1 },
+                            },
+                        },
+                    },
+                ],
+            },
+            "cache": {
+                "type": "object",
+                "description": "Replay model responses for repeated calls; NO_CACHE=1 skips it",
+                "properties": {
+                    "backend": {
+                        "enum": variants(CacheBackend::ALL),
+                        "description": "disk keeps one JSON file per response; redis uses CACHE_REDIS_URL",
+                    },
+                    "ttl": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 86400,
+                        "description": "Seconds a cached response stays valid",
+                    },
+                    "path": {
+                        "type": "string",
+                        "default": ".cache/model",
+                        "description": "Directory of the disk cache, relative to the project",
+                    },
+                },
+            },
+            "packaging": {
+                "enum": variants(Packaging::ALL),
+                "description": "pip writes requirements.txt; conda adds an environment.yml",
+            },
+            "deploy": {
+                "description": "Where the project is meant to run, each adding its own files",
+                "oneOf": [
+                    { "enum": variants(DeployTarget::ALL) },
+                    {
+                        "type": "array",
+                        "uniqueItems": true,
+                        "items": { "enum": variants(DeployTarget::ALL) },
+                    },
+                ],
+            },
+            "tasks": {
+                "enum": variants(TaskRunner::ALL),
+                "description": "Write a justfile or Makefile with install, run, test and lint recipes",
+            },
+            "quality": {
+                "type": "object",
+                "description": "pyproject.toml and pre-commit config, with the Python formatted by ruff",
+                "properties": {
+                    "formatter": {
+                        "enum": variants(Formatter::ALL),
+                        "description": "Formatter configured in pyproject.toml and run by pre-commit",
+                    },
+                    "line-length": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 320,
+                        "default": 100,
+                    },
+                    "pre-commit": {
+                        "type": "boolean",
+                        "default": true,
+                        "description": "Write .pre-commit-config.yaml",
+                    },
+                },
+            },
+            "license": {
+                "enum": variants(License::ALL),
+                "description": "Write LICENSE and an SPDX header on each generated Python file",
+            },
+            "copyright": {
+                "type": "string",
+                "description": "Year and holder, such as `2026 Acme Inc.`, for LICENSE and the file headers",
+            },
+            "ci": {
+                "description": "CI workflow for the generated project; `github` is short for {provider: github}",
+                "oneOf": [
+                    { "enum": variants(CiProvider::ALL) },
+                    {
+                        "type": "object",
+                        "required": ["provider"],
+                        "properties": {
+                            "provider": { "enum": variants(CiProvider::ALL) },
+                            "docker": {
+                                "enum": variants(CiDocker::ALL),
+                                "description": "Build the dev container image after the checks; push also pushes it to ghcr.io from the default branch",
+                            },
+                        },
+                    },
+                ],
+            },
+            "limits": {
+                "type": "object",
+                "description": "Abort runs that go over a token, time or spending budget",
+                "properties": {
+                    "max-tokens-per-run": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Input plus output tokens one run may use",
+                    },
+                    "max-seconds-per-run": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Wall-clock seconds one run may take",
+                    },
+                    "max-dollars-per-day": {
+                        "type": "number",
+                        "exclusiveMinimum": 0,
+                        "description": "Spend across all runs in a calendar day; needs dollars-per-million-tokens",
+                    },
+                    "dollars-per-million-tokens": {
+                        "type": "number",
+                        "exclusiveMinimum": 0,
+                        "description": "The model's price, used to turn tokens into dollars",
+                    },
+                },
+            },
+            "security": {
+                "type": "object",
+                "description": "What the Python the agent writes may import and how long it may run",
+                "properties": {
+                    "authorized-imports": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Modules executed code may import beyond smolagents' safe list; pkg.* allows submodules",
+                    },
+                    "allow-network": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Allow network modules such as requests or socket in authorized-imports",
+                    },
+                    "max-execution-seconds": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Seconds one code step may run before it is stopped",
+                    },
+                },
+            },
+            "planning": {
+                "type": "object",
+                "description": "smolagents' planning step, guided by planning_prompt.md",
+                "properties": {
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Plan before acting, guided by planning_prompt.md",
+                    },
+                    "interval": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 3,
+                        "description": "Steps between plan updates",
+                    },
+                },
+            },
+            "approval": {
+                "type": "object",
+                "description": "Tools that wait for the operator's go-ahead before each call",
+                "required": ["tools"],
+                "properties": {
+                    "tools": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": { "enum": variants(ToolKind::ALL) },
+                        "description": "Tools from agent.tools whose calls need approval",
+                    },
+                    "timeout": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 300,
+                        "description": "Seconds a2a_server.py waits for a decision before denying the call",
+                    },
+                },
+            },
+            "docs": {
+                "type": "object",
+                "description": "Extra documentation written into the project",
+                "properties": {
+                    "architecture": {
+                        "type": "boolean",
+                        "description": "Write architecture.md with a Mermaid diagram of the agent",
+                    },
+                },
+            },
+            "server": {
+                "type": "object",
+                "description": "How server.py admits clients and bounds runs",
+                "properties": {
+                    "auth": {
+                        "enum": variants(ServerAuth::ALL),
+                        "default": "none",
+                        "description": "api-key answers 401 unless a request carries SERVER_API_KEY as a Bearer token or X-API-Key",
+                    },
+                    "rate-limit": {
+                        "type": "object",
+                        "description": "Requests one client address may make per window; more get 429",
+                        "required": ["requests"],
+                        "properties": {
+                            "requests": { "type": "integer", "minimum": 1 },
+                            "per": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "default": 60,
+                                "description": "Window length in seconds",
+                            },
+                        },
+                    },
+                    "max-concurrent-runs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Runs in progress or waiting for the agent at once; more get 503",
+                    },
+                    "timeout": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Seconds a run may take once it starts before it is interrupted and answered with 504",
+                    },
+                    "streaming": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Add a /ws/chat WebSocket streaming each run's steps and tokens as JSON events, and chat.html to try it",
+                    },
+                },
+            },
+            "a2a": {
+                "type": "object",
+                "description": "Agent-to-Agent protocol server output",
+                "properties": {
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Generate a2a_server.py and agent_card.json",
+                    },
+                    "port": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 65535,
+                        "description": "Default port for a2a_server.py",
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "Public URL advertised in the agent card",
+                    },
+                },
+            },
+            "batch": {
+                "type": "object",
+                "description": "Offline batch runner output",
+                "properties": {
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Generate batch.py",
+                    },
+                    "input": {
+                        "type": "string",
+                        "pattern": "\\.(csv|jsonl)$",
+                        "description": "Prompts to run: CSV with a prompt column, or JSONL with a prompt key",
+                    },
+                    "output": {
+                        "type": "string",
+                        "pattern": "\\.(csv|jsonl)$",
+                        "description": "Results file, CSV or JSONL by extension",
+                    },
+                    "retries": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Extra attempts for a prompt whose run raises",
+                    },
+                    "timer": {
+                        "type": "object",
+                        "description": "Also generate a cron entry or systemd timer that runs the batch",
+                        "required": ["kind", "schedule"],
+                        "properties": {
+                            "kind": { "enum": variants(TimerKind::ALL) },
+                            "schedule": {
+                                "type": "string",
+                                "description": "Five-field cron expression for cron, an OnCalendar= value for systemd",
+                            },
+                        },
+                    },
+                },
+            },
+            "worker": {
+                "type": "object",
+                "description": "Queue worker output",
+                "properties": {
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Generate worker.py and docker-compose.yml",
+                    },
+                    "backend": {
+                        "enum": variants(WorkerBackend::ALL),
+                        "description": "RQ or Celery, both on Redis",
+                    },
+                    "queue": {
+                        "type": "string",
+                        "pattern": "^[A-Za-z0-9_.-]+$",
+                        "description": "Queue the worker consumes",
+                    },
+                    "timeout": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Seconds a task may run before it is killed",
+                    },
+                },
+            },
+            "pipeline": {
+                "type": "object",
+                "description": "Fixed sequence of steps compiled into pipeline.py",
+                "required": ["steps"],
+                "properties": {
+                    "steps": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "required": ["name"],
+                            "properties": {
+                                "name": {
+                                    "type": "string",
+                                    "pattern": "^[A-Za-z_][A-Za-z0-9_]*$",
+                                    "description": "Later steps use the output as {name}",
+                                },
+                                "agent": {
+                                    "type": "string",
+                                    "description": "Prompt for a fresh agent run; {input} is the pipeline input",
+                                },
+                                "tool": {
+                                    "type": "string",
+                                    "description": "Tool to call directly instead, by its name in the agent",
+                                },
+                                "args": {
+                                    "type": "object",
+                                    "additionalProperties": { "type": "string" },
+                                    "description": "Arguments for tool",
+                                },
+                            },
+                            "oneOf": [{ "required": ["agent"] }, { "required": ["tool"] }],
+                        },
+                    },
+                },
+            },
+            "router": {
+                "type": "object",
+                "description": "Triage requests to route-specific agents with router.py",
+                "required": ["routes"],
+                "properties": {
+                    "model": {
+                        "enum": variants(Model::ALL),
+                        "description": "Model that classifies requests; agent.model when unset",
+                    },
+                    "routes": {
+                        "type": "array",
+                        "minItems": 2,
+                        "items": {
+                            "type": "object",
+                            "required": ["name", "description"],
+                            "properties": {
+                                "name": { "type": "string", "pattern": "^[a-z][a-z0-9_-]*$" },
+                                "description": {
+                                    "type": "string",
+                                    "description": "What belongs on this route, shown to the classifier",
+                                },
+                                "instructions": {
+                                    "type": "string",
+                                    "description": "Put before each request this route's agent runs",
+                                },
+                                "tools": {
+                                    "type": "array",
+                                    "items": { "enum": variants(ToolKind::ALL) },
+                                    "description": "Tools from agent.tools this route gets; all when empty",
+                                },
+                            },
+                        },
+                    },
+                    "fallback": {
+                        "type": "string",
+                        "description": "Route for requests the classifier cannot place; the first route when unset",
+                    },
+                },
+            },
+            "voice": {
+                "type": "object",
+                "description": "Settings for the voice capability",
+                "properties": {
+                    "whisper-model": {
+                        "enum": variants(WhisperModel::ALL),
+                        "description": "faster-whisper model size: larger is more accurate and slower",
+                    },
+                    "language": {
+                        "type": "string",
+                        "pattern": "^[a-z]{2}$",
+                        "description": "Spoken language as an ISO 639-1 code; detected when unset",
+                    },
+                    "input-device": {
+                        "type": "string",
+                        "description": "Microphone, by index or part of its name",
+                    },
+                    "output-device": {
+                        "type": "string",
+                        "description": "Speakers for tts, by index or part of its name",
+                    },
+                    "tts": {
+                        "type": "boolean",
+                        "description": "Read answers aloud",
+                    },
+                },
+            },
+            "memory": {
+                "type": "object",
+                "description": "Long-term memory searched before every turn",
+                "required": ["kind"],
+                "properties": {
+                    "kind": {
+                        "enum": variants(MemoryKind::ALL),
+                        "description": "How memories are matched to the prompt",
+                    },
+                    "embedding-model": {
+                        "type": "string",
+                        "pattern": "^[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+$",
+                        "description": "sentence-transformers model used to embed memories and queries",
+                    },
+                    "path": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "JSONL file holding the memories, relative to the project",
+                    },
+                    "top-k": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Memories appended to each prompt",
+                    },
+                    "retention-days": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Days a memory is kept; unset keeps it forever",
+                    },
+                    "max-entries": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Most memories kept; the oldest are dropped first",
+                    },
+                },
+            },
+            "knowledge": {
+                "type": "object",
+                "description": "Documents indexed by ingest.py and queried as a tool; needs framework llamaindex",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "Directory of documents to index, relative to the project",
+                    },
+                    "index": {
+                        "type": "string",
+                        "minLength": 1,
+                        "description": "Directory the index is persisted to, relative to the project",
+                    },
+                    "embedding-model": {
+                        "type": "string",
+                        "pattern": "^[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+$",
+                        "description": "Hugging Face embedding model for documents and queries",
+                    },
+                    "top-k": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Chunks retrieved for each query",
+                    },
+                },
+            },
+            "runtime": {
+                "type": "object",
+                "description": "Target Python runtime",
+                "properties": {
+                    "smolagents-version": {
+                        "type": "string",
+                        "pattern": "^[0-9]+\\.[0-9]+(\\.[0-9]+)?$",
+                        "description": "Pin smolagents and target its API; latest when unset",
+                    },
+                },
+            },
+            "allow": {
+                "type": "array",
+                "description": "Warnings to silence, by rule name",
+                "items": { "enum": crate::lint::rule_names().collect::<Vec<_>>() },
+            },
+            "lint": {
+                "type": "object",
+                "description": "Lint settings for this spec",
+                "properties": {
+                    "allow": {
+                        "type": "array",
+                        "description": "Deprecated: use the top-level allow",
+                        "deprecated": true,
+                        "items": { "enum": crate::lint::rule_names().collect::<Vec<_>>() },
+                    },
+                },
+            },
+            "interfaces": {
+                "type": "array",
+                "minItems": 1,
+                "uniqueItems": true,
+                "items": { "enum": variants(Interface::ALL) },
+                "default": ["cli"],
+                "description": "Entry points sharing agent.py: cli.py, server.py (HTTP) and gradio_app.py",
+            },
+            "bundle": {
+                "type": "object",
+                "description": "What tachi bundle builds into dist/",
+                "properties": {
+                    "format": {
+                        "enum": variants(BundleFormat::ALL),
+                        "default": "shiv",
+                        "description": "shiv for a .pyz zipapp that needs Python on the target, pyinstaller for a native executable",
+                    },
+                    "entry": {
+                        "enum": variants(Interface::ALL),
+                        "description": "Interface the artifact starts; the first in interfaces when unset",
+                    },
+                },
+            },
+            "environments": {
+                "type": "object",
+                "description": "Deployment environments by name; APP_ENV picks one and config.py loads .env.<name> over .env",
+                "propertyNames": { "pattern": "^[a-z][a-z0-9-]*$" },
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        "model-provider": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Hugging Face inference provider serving the model, such as together",
+                        },
+                        "model-endpoint": {
+                            "type": "string",
+                            "pattern": "^https?://",
+                            "description": "URL of a dedicated endpoint serving the model, used instead of its id",
+                        },
+                        "env": {
+                            "type": "object",
+                            "propertyNames": { "pattern": "^[A-Z_][A-Z0-9_]*$" },
+                            "additionalProperties": { "type": "string" },
+                            "description": "Values for other variables the project reads, such as CACHE_REDIS_URL",
+                        },
+                    },
+                },
+            },
+            "cli": {
+                "type": "object",
+                "description": "Shape of the generated cli.py",
+                "properties": {
+                    "framework": {
+                        "enum": variants(CliFramework::ALL),
+                        "description": "plain: bare input() loop; typer: chat/ask/tools/version subcommands",
+                    },
+                    "ui": {
+                        "enum": variants(CliUi::ALL),
+                        "description": "plain text, or markdown answers and spinners via rich",
+                    },
+                    "transcripts": {
+                        "type": "boolean",
+                        "description": "Record each chat session as JSONL and generate replay.py",
+                    },
+                    "input": {
+                        "enum": variants(CliInput::ALL),
+                        "description": "input(), or prompt_toolkit with history and multi-line editing",
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Option schemas for each tool, keyed by tool name. Every tool takes a
+/// `name` and `description` override, and like the Rust side, each one
+/// refuses keys it does not know.
+fn tool_options() -> Value {
+    let mut options = json!({
+        "math": {
+            "type": "object",
+            "description": "Evaluate expressions with sympy",
+            "properties": {},
+        },
+        "sql": {
+            "type": "object",
+            "description": "SQLAlchemy query tool with a schema listing",
+            "properties": {
+                "url-env": {
+                    "type": "string",
+                    "pattern": "^[A-Z_][A-Z0-9_]*$",
+                    "description": "Environment variable holding the database URL",
+                },
+                "readonly": {
+                    "type": "boolean",
+                    "description": "Refuse anything but single queries and never commit",
+                },
+                "scheme": {
+                    "type": "string",
+                    "description": "URL scheme such as postgresql or mysql+pymysql; picks the driver package",
+                },
+                "max-rows": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Rows returned per query before the result is cut off",
+                },
+            },
+        },
+        "http": {
+            "type": "object",
+            "description": "HTTP requests to allowlisted hosts, for internal APIs",
+            "required": ["domains"],
+            "properties": {
+                "domains": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Hosts the tool may call; *.example.com allows any subdomain",
+                },
+                "methods": {
+                    "type": "array",
+                    "items": { "enum": variants(HttpMethod::ALL) },
+                    "description": "Methods the tool may send; GET when unset",
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string", "pattern": "^[A-Z_][A-Z0-9_]*$" },
+                    "description": "Header name to the environment variable holding its value",
+                },
+                "timeout": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Seconds before a request is abandoned",
+                },
+            },
+        },
+        "files": {
+            "type": "object",
+            "description": "Read, write and list files inside a sandbox directory",
+            "properties": {
+                "root": {
+                    "type": "string",
+                    "description": "Sandbox directory; relative paths start at the project directory",
+                },
+                "readonly": {
+                    "type": "boolean",
+                    "description": "Leave out the write tool",
+                },
+            },
+        },
+        "shell": {
+            "type": "object",
+            "description": "Run allowlisted executables without a shell",
+            "required": ["allow"],
+            "properties": {
+                "allow": {
+                    "type": "array",
+                    "items": { "type": "string", "pattern": "^\\S+$" },
+                    "description": "Executables the agent may run, by name or exact path",
+                },
+                "timeout": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Seconds before a command is killed",
+                },
+            },
+        },
+        "github": {
+            "type": "object",
+            "description": "GitHub issue search, pull request reading and file fetching",
+            "properties": {
+                "token-env": {
+                    "type": "string",
+                    "pattern": "^[A-Z_][A-Z0-9_]*$",
+                    "description": "Environment variable holding the GitHub token",
+                },
+                "repo": {
+                    "type": "string",
+                    "pattern": "^[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+$",
+                    "description": "owner/name used when the agent doesn't name a repository",
+                },
+            },
+        },
+        "slack": {
+            "type": "object",
+            "description": "Post to and search allowlisted Slack channels",
+            "required": ["channels"],
+            "properties": {
+                "token-env": {
+                    "type": "string",
+                    "pattern": "^[A-Z_][A-Z0-9_]*$",
+                    "description": "Environment variable holding the bot token",
+                },
+                "channels": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Channels the agent may use, by name or ID",
+                },
+            },
+        },
+        "email": {
+            "type": "object",
+            "description": "Send mail over SMTP and optionally read the inbox over IMAP",
+            "required": ["smtp-host"],
+            "properties": {
+                "smtp-host": { "type": "string", "description": "SMTP server" },
+                "smtp-port": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 65535,
+                    "description": "465 means implicit TLS; anything else uses STARTTLS",
+                },
+                "imap-host": {
+                    "type": "string",
+                    "description": "IMAP server; leave unset to send only",
+                },
+                "imap-port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+                "user-env": {
+                    "type": "string",
+                    "pattern": "^[A-Z_][A-Z0-9_]*$",
+                    "description": "Environment variable holding the login name",
+                },
+                "password-env": {
+                    "type": "string",
+                    "pattern": "^[A-Z_][A-Z0-9_]*$",
+                    "description": "Environment variable holding the password",
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Sender address; the login name when unset",
+                },
+                "dry-run": {
+                    "type": "boolean",
+                    "description": "Draft messages instead of sending them",
+                },
+            },
+        },
+        "documents": {
+            "type": "object",
+            "description": "Extract text from PDF and Word documents by path or URL",
+            "properties": {
+                "root": {
+                    "type": "string",
+                    "description": "Sandbox directory; relative paths start at the project directory",
+                },
+                "max-size-mb": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Larger documents are refused before they are parsed",
+                },
+                "max-chars": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Extracted text is cut off after this many characters",
+                },
+                "urls": {
+                    "type": "boolean",
+                    "description": "Accept http(s) URLs as well as paths",
+                },
+            },
+        },
+        "text-to-image": {
+            "type": "object",
+            "description": "Generate images with a Hugging Face Space",
+            "properties": {
+                "space": {
+                    "type": "string",
+                    "pattern": "^[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+$",
+                    "description": "owner/name of the Space",
+                },
+                "outputs": {
+                    "type": "string",
+                    "description": "Where images are saved; relative paths start at the project directory",
+                },
+            },
+        },
+        "youtube-transcript": {
+            "type": "object",
+            "description": "Fetch the transcript of a YouTube video",
+            "properties": {
+                "languages": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": { "type": "string", "pattern": "^[A-Za-z0-9]+(-[A-Za-z0-9]+)*$" },
+                    "description": "Language codes in order of preference",
+                },
+            },
+        },
+        "search": {
+            "type": "object",
+            "description": "Web search through a chosen backend",
+            "properties": {
+                "provider": {
+                    "enum": variants(SearchProvider::ALL),
+                    "description": "Search backend; all but duckduckgo need an API key",
+                },
+                "max-results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 100,
+                    "description": "Results returned per query; serpapi has no setting, brave and tavily take up to 20, google-cse 10",
+                },
+                "region": {
+                    "type": "string",
+                    "pattern": "^[a-z]{2}-[a-z]{2}$",
+                    "description": "Country and language of the results, such as us-en; brave and google-cse only",
+                },
+            },
+        },
+        "webpage": {
+            "type": "object",
+            "description": "Fetch web pages as markdown",
+            "properties": {
+                "max-length": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Characters of page text returned before truncating",
+                },
+                "user-agent": {
+                    "type": "string",
+                    "minLength": 1,
+                    "description": "User-Agent header sent with each request",
+                },
+                "render-js": {
+                    "type": "boolean",
+                    "description": "Load pages in headless Chromium (playwright) so scripts run first",
+                },
+                "domains": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Hosts the tool may visit, `*.example.com` for any subdomain; empty allows all",
+                },
+            },
+        },
+        "memory": {
+            "type": "object",
+            "description": "Remember and recall key-value facts across conversations",
+            "properties": {
+                "store": {
+                    "enum": variants(MemoryStore::ALL),
+                    "description": "Keep memories in a JSON file or a SQLite database",
+                },
+                "path": {
+                    "type": "string",
+                    "minLength": 1,
+                    "description": "File holding the memories, relative to the project; memory.json or memory.db by default",
+                },
+                "retention-days": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Days a memory is kept after it was last written; unset keeps it forever",
+                },
+                "max-entries": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Most memories kept; the least recently written are dropped first",
+                },
+            },
+        },
+    });
+    for schema in options
+        .as_object_mut()
+        .expect("object literal")
+        .values_mut()
+    {
+        schema["properties"]["name"] = json!({
+            "type": "string",
+            "pattern": "^[A-Za-z_][A-Za-z0-9_]*$",
+            "description": "Name the model calls the tool by; only for tools that add one class",
+        });
+        schema["properties"]["description"] = json!({
+            "type": "string",
+            "minLength": 1,
+            "description": "Description the model sees instead of the tool's own",
+        });
+        schema["additionalProperties"] = json!(false);
+    }
+    options
+}
+
+fn variants<T: Serialize>(all: &[T]) -> Vec<Value> {
+    all.iter()
+        .map(|v| serde_json::to_value(v).expect("unit variants serialize to strings"))
+        .collect()
+}
@@ -0,0 +1,99 @@
+use crate::generate::GeneratedFile;
+
+/// Prefixes of credentials issued in a recognizable shape: what follows the
+/// prefix, how long it is at least, and what the key is.
+const KEY_PREFIXES: &[(&str, usize, &str)] = &[
+    ("hf_", 30, "Hugging Face token"),
+    ("sk-", 20, "OpenAI or Anthropic API key"),
+    ("ghp_", 36, "GitHub token"),
+    ("gho_", 36, "GitHub token"),
+    ("ghs_", 36, "GitHub token"),
+    ("ghu_", 36, "GitHub token"),
+    ("github_pat_", 22, "GitHub token"),
+    ("glpat-", 20, "GitLab token"),
+    ("xoxb-", 20, "Slack token"),
+    ("xoxp-", 20, "Slack token"),
+    ("xapp-", 20, "Slack token"),
+    ("AKIA", 16, "AWS access key"),
+    ("AIza", 35, "Google API key"),
+    ("tvly-", 20, "Tavily API key"),
+];
+
+/// Suffixes of variable names whose values are credentials.
+const SECRET_NAMES: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD"];
+
+/// A value in rendered output that looks like a real credential.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Secret {
+    pub path: String,
+    /// 1-based
+    pub line: usize,
+    pub kind: &'static str,
+    /// The first characters of the value, enough to find it
+    pub preview: String,
+}
+
+/// Every credential-shaped value in `files`. Templates only ever leave
+/// these empty, so a hit came from the spec or a template override.
+pub(crate) fn scan(files: &[GeneratedFile]) -> Vec<Secret> {
+    let mut found = Vec::new();
+    for file in files {
+        let Ok(text) = std::str::from_utf8(&file.contents) else {
+            continue;
+        };
+        for (index, line) in text.lines().enumerate() {
+            for (kind, value) in scan_line(line) {
+                found.push(Secret {
+                    path: file.path.clone(),
+                    line: index + 1,
+                    kind,
+                    preview: value.chars().take(6).collect(),
+                });
+            }
+        }
+    }
+    found
+}
+
+fn scan_line(line: &str) -> Vec<(&'static str, &str)> {
+    if line.contains("-----BEGIN") && line.contains("PRIVATE KEY-----") {
+        return vec![("private key", "-----BEGIN")];
+    }
+    let mut found = Vec::new();
+    for word in line.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-')) {
+        let prefixed = KEY_PREFIXES.iter().find(|(prefix, min_len, _)| {
+            word.strip_prefix(prefix).is_some_and(|rest| {
+                rest.len() >= *min_len && rest.chars().any(|c| c.is_ascii_digit())
+            })
+        });
+        if let Some((_, _, kind)) = prefixed {
+            found.push((*kind, word));
+        }
+    }
+    if found.is_empty() {
+        if let Some(value) = assigned_secret(line) {
+            found.push(("credential", value));
+        }
+    }
+    found
+}
+
+/// The value of `NAME=value` or `NAME: value` when NAME ends in KEY, TOKEN,
+/// SECRET or PASSWORD and the value looks generated rather than a
+/// placeholder or a variable name.
+fn assigned_secret(line: &str) -> Option<&str> {
+    let (name, value) = line.split_once('=').or_else(|| line.split_once(": "))?;
+    let name = name.trim().trim_matches('"');
+    let is_secret_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        && SECRET_NAMES.iter().any(|suffix| name.ends_with(suffix));
+    let value = value.trim().trim_end_matches(',').trim_matches('"');
+    let generated = value.len() >= 16
+        && !value.contains(char::is_whitespace)
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_alphabetic())
+        && !value.contains("${");
+    (is_secret_name && generated).then_some(value)
+}
@@ -0,0 +1,303 @@
+use crate::attest::sha256_hex;
+use crate::spec::Version;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// GitHub repository tachi is released from
+const REPO: &str = "Ghostlock-AI/agents";
+/// Stable releases are tagged `tachi-v<version>`; the repository holds
+/// other projects' releases too
+const TAG_PREFIX: &str = "tachi-v";
+/// One prerelease whose assets are rebuilt every night
+const NIGHTLY_TAG: &str = "tachi-nightly";
+/// Release asset with the SHA-256 of every other asset, as `sha256sum` prints it
+const CHECKSUMS: &str = "SHA256SUMS";
+/// Release asset with an SSH signature over `SHA256SUMS`
+const SIGNATURE: &str = "SHA256SUMS.sig";
+/// Principal and namespace releases are signed under, so no other signature
+/// by the same key, such as a project attestation's, passes for one
+const RELEASE_SIGNER: &str = "tachi-release";
+/// The SSH public key releases are signed with, as `ssh-ed25519 AAAA...`,
+/// pinned when the release workflow builds tachi
+const RELEASE_KEY: Option<&str> = option_env!("TACHI_RELEASE_KEY");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// The newest `tachi-v*` release
+    Stable,
+    /// The latest build of the main branch
+    Nightly,
+}
+
+/// What `self_update` found, and did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    /// The running binary already is the channel's release, or newer
+    UpToDate { release: String },
+    /// The channel has a different build; only reported when checking
+    Available { release: String },
+    /// The binary at `path` was replaced with the release
+    Updated { release: String, path: PathBuf },
+}
+
+struct Release {
+    tag: String,
+    /// (name, download URL)
+    assets: Vec<(String, String)>,
+}
+
+impl Release {
+    fn asset_url(&self, name: &str) -> Result<&str> {
+        match self.assets.iter().find(|(n, _)| n == name) {
+            Some((_, url)) => Ok(url),
+            None => bail!("release {} has no {name}", self.tag),
+        }
+    }
+}
+
+/// Replace the running tachi with the channel's release for this platform,
+/// once the release's `SHA256SUMS` carries a signature by the pinned release
+/// key and the binary's checksum matches it. Downloads go through `curl`,
+/// the signature is checked with `ssh-keygen`. A binary a package manager
+/// installed is left to it.
+pub fn self_update(channel: Channel, check_only: bool) -> Result<Update> {
+    let exe = std::env::current_exe().context("locating the tachi binary")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    if let Some((manager, command)) = package_manager(&exe) {
+        bail!("tachi was installed with {manager}; update it with `{command}`");
+    }
+    let Some(key) = RELEASE_KEY else {
+        bail!("this tachi was built without a release key to check releases against; download one from https://github.com/{REPO}/releases");
+    };
+    let release = find_release(channel)?;
+    let asset = asset_name();
+    let sums = fetch(release.asset_url(CHECKSUMS)?)?;
+    let signature = fetch(release.asset_url(SIGNATURE)?)?;
+    verify_signature(&sums, &signature, key).with_context(|| {
+        format!(
+            "checking {CHECKSUMS} of release {}; nothing was replaced",
+            release.tag
+        )
+    })?;
+    let expected = checksum_for(&String::from_utf8_lossy(&sums), &asset)
+        .with_context(|| format!("{CHECKSUMS} of release {} has no {asset}", release.tag))?;
+    let running = fs::read(&exe).with_context(|| format!("reading {}", exe.display()))?;
+    let current = Version::try_from(env!("CARGO_PKG_VERSION").to_string()).ok();
+    let older = channel == Channel::Stable && release_version(&release.tag) <= current;
+    if older || sha256_hex(&running) == expected {
+        return Ok(Update::UpToDate {
+            release: release.tag,
+        });
+    }
+    if check_only {
+        return Ok(Update::Available {
+            release: release.tag,
+        });
+    }
+    let binary = fetch(release.asset_url(&asset)?)?;
+    let actual = sha256_hex(&binary);
+    if actual != expected {
+        bail!("{asset} from release {} has SHA-256 {actual}, but {CHECKSUMS} says {expected}; nothing was replaced", release.tag);
+    }
+    replace(&exe, &binary)?;
+    Ok(Update::Updated {
+        release: release.tag,
+        path: exe,
+    })
+}
+
+fn find_release(channel: Channel) -> Result<Release> {
+    let api = format!("https://api.github.com/repos/{REPO}/releases");
+    match channel {
+        Channel::Nightly => {
+            let json = fetch_json(&format!("{api}/tags/{NIGHTLY_TAG}"))?;
+            parse_release(&json).with_context(|| format!("reading release {NIGHTLY_TAG}"))
+        }
+        Channel::Stable => {
+            let json = fetch_json(&format!("{api}?per_page=100"))?;
+            let newest = json
+                .as_array()
+                .context("GitHub did not return a list of releases")?
+                .iter()
+                .filter(|r| r["draft"] == false && r["prerelease"] == false)
+                .filter_map(|r| {
+                    let version = release_version(r["tag_name"].as_str()?)?;
+                    Some((version, r))
+                })
+                .max_by_key(|(version, _)| *version);
+            match newest {
+                Some((_, json)) => parse_release(json),
+                None => bail!("{REPO} has no {TAG_PREFIX}* release"),
+            }
+        }
+    }
+}
+
+fn parse_release(json: &serde_json::Value) -> Result<Release> {
+    let tag = json["tag_name"]
+        .as_str()
+        .context("release without a tag_name")?;
+    let assets = json["assets"]
+        .as_array()
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|a| {
+                    let name = a["name"].as_str()?;
+                    let url = a["browser_download_url"].as_str()?;
+                    Some((name.to_string(), url.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Release {
+        tag: tag.to_string(),
+        assets,
+    })
+}
+
+fn release_version(tag: &str) -> Option<Version> {
+    Version::try_from(tag.strip_prefix(TAG_PREFIX)?.to_string()).ok()
+}
+
+/// `tachi-<arch>-<os>`, as the release workflow names its binaries.
+fn asset_name() -> String {
+    format!(
+        "tachi-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// The hash `sums` lists for `name`, in `sha256sum` output format.
+fn checksum_for(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        // `sha256sum -b` marks binary files with a leading `*`
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+/// Check `signature` over `sums` against the pinned release `key` with
+/// `ssh-keygen -Y verify`, as `gen --sign` attestations are checked.
+fn verify_signature(sums: &[u8], signature: &[u8], key: &str) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("tachi-update-{}", std::process::id()));
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let verified = ssh_keygen_verify(&dir, sums, signature, key);
+    let _ = fs::remove_dir_all(&dir);
+    verified
+}
+
+fn ssh_keygen_verify(dir: &Path, sums: &[u8], signature: &[u8], key: &str) -> Result<()> {
+    let allowed_signers = dir.join("allowed_signers");
+    fs::write(
+        &allowed_signers,
+        format!("{RELEASE_SIGNER} {}\n", key.trim()),
+    )
+    .with_context(|| format!("writing {}", allowed_signers.display()))?;
+    let signature_path = dir.join(SIGNATURE);
+    fs::write(&signature_path, signature)
+        .with_context(|| format!("writing {}", signature_path.display()))?;
+    let mut child = Command::new("ssh-keygen")
+        .args([
+            "-Y",
+            "verify",
+            "-n",
+            RELEASE_SIGNER,
+            "-I",
+            RELEASE_SIGNER,
+            "-f",
+        ])
+        .arg(&allowed_signers)
+        .arg("-s")
+        .arg(&signature_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("running ssh-keygen, which self-update checks release signatures with")?;
+    child
+        .stdin
+        .take()
+        .context("opening ssh-keygen's stdin")?
+        .write_all(sums)
+        .with_context(|| format!("writing {CHECKSUMS} to ssh-keygen"))?;
+    let output = child.wait_with_output().context("running ssh-keygen")?;
+    if !output.status.success() {
+        // An unknown key is reported on stdout, a bad signature on stderr
+        let mut report = String::from_utf8_lossy(&output.stdout).into_owned();
+        report.push_str(&String::from_utf8_lossy(&output.stderr));
+        bail!(
+            "{SIGNATURE} is not a signature by the release key: {}",
+            report.trim()
+        );
+    }
+    Ok(())
+}
+
+/// The package manager that owns `exe` and its update command, judged by
+/// where they install binaries.
+fn package_manager(exe: &Path) -> Option<(&'static str, &'static str)> {
+    let path = exe.to_string_lossy().replace('\\', "/").to_lowercase();
+    if ["/cellar/", "/homebrew/", "/linuxbrew/"]
+        .iter()
+        .any(|dir| path.contains(dir))
+    {
+        return Some(("Homebrew", "brew upgrade tachi"));
+    }
+    if path.contains("/scoop/") {
+        return Some(("Scoop", "scoop update tachi"));
+    }
+    None
+}
+
+fn fetch_json(url: &str) -> Result<serde_json::Value> {
+    let body = fetch(url)?;
+    serde_json::from_slice(&body).with_context(|| format!("parsing {url}"))
+}
+
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args([
+            "--proto",
+            "=https",
+            "--header",
+            "Accept: application/vnd.github+json",
+        ])
+        .arg(url)
+        .output()
+        .context("running curl, which self-update downloads with")?;
+    if !output.status.success() {
+        bail!(
+            "downloading {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Swap `binary` in for `exe` through a file next to it, so a failed write
+/// leaves the old binary working.
+fn replace(exe: &Path, binary: &[u8]) -> Result<()> {
+    let staged = exe.with_extension("new");
+    fs::write(&staged, binary).with_context(|| format!("writing {}", staged.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("making {} executable", staged.display()))?;
+    }
+    // Windows will not overwrite a running executable, but it will rename one
+    if cfg!(windows) {
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old).with_context(|| format!("moving {} aside", exe.display()))?;
+    }
+    fs::rename(&staged, exe).with_context(|| format!("replacing {}", exe.display()))
+}
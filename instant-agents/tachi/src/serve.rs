@@ -0,0 +1,327 @@
+use crate::error::ErrorReport;
+use crate::generate::{self, GenerateOptions};
+use crate::schema;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Generation failed; `data` carries the `ErrorReport`.
+const TACHI_ERROR: i64 = -32000;
+
+/// Largest request body accepted, to keep a stray client from exhausting memory.
+const MAX_BODY: usize = 4 * 1024 * 1024;
+/// Connections handled at once; more are turned away until one finishes.
+const MAX_CONNECTIONS: usize = 16;
+/// How long a client may take to send its request or read the reply.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serve JSON-RPC over HTTP POST on `addr` until the process is killed.
+/// Each connection gets its own thread, up to 16 at once;
+/// requests are independent.
+///
+/// `generate` writes files, so only local, non-browser clients are served:
+/// requests from a web page (with an `Origin`), for another host (DNS
+/// rebinding) or without a JSON body are refused, and `out` and `templates`
+/// must be inside the directory the server was started in.
+pub fn serve(addr: &str) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("binding {addr}"))?;
+    eprintln!("tachi serve listening on http://{}", listener.local_addr()?);
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let busy = active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS;
+        let slot = Slot(Arc::clone(&active));
+        if busy {
+            drop(slot);
+            let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+            let _ = respond(&mut stream, "503 Service Unavailable", "");
+            continue;
+        }
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(err) = handle_connection(stream) {
+                eprintln!("tachi serve: {err:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// One of the `MAX_CONNECTIONS` slots, given back when dropped, so a
+/// connection thread that panics still frees its slot.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether a `Host` header names this machine, with or without a port.
+fn local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    ["localhost", "127.0.0.1", "::1"]
+        .iter()
+        .any(|local| name.eq_ignore_ascii_case(local))
+}
+
+fn handle_connection(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let method = request_line.split_whitespace().next().unwrap_or_default();
+
+    let mut content_length = 0;
+    let mut origin = false;
+    let mut host = None;
+    let mut json = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().context("bad Content-Length")?;
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = true;
+            } else if name.eq_ignore_ascii_case("host") {
+                host = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("content-type") {
+                let media = value.split(';').next().unwrap_or_default().trim();
+                json = media.eq_ignore_ascii_case("application/json");
+            }
+        }
+    }
+
+    let mut stream = stream;
+    if method != "POST" {
+        return respond(&mut stream, "405 Method Not Allowed", "");
+    }
+    // Browsers always send Origin on a cross-site POST, and a rebound DNS
+    // name still shows up in Host
+    if origin || !host.as_deref().is_some_and(local_host) {
+        return respond(&mut stream, "403 Forbidden", "");
+    }
+    // A form post cannot set this without a CORS preflight, which is refused
+    if !json {
+        return respond(&mut stream, "415 Unsupported Media Type", "");
+    }
+    if content_length > MAX_BODY {
+        return respond(&mut stream, "413 Payload Too Large", "");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    match handle_body(&body) {
+        Some(reply) => respond(&mut stream, "200 OK", &reply.to_string()),
+        None => respond(&mut stream, "204 No Content", ""),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Handle one JSON-RPC payload (single request or batch). `None` means every
+/// request was a notification and nothing should be sent back.
+fn handle_body(body: &[u8]) -> Option<Value> {
+    let payload: Value = match serde_json::from_slice(body) {
+        Ok(v) => v,
+        Err(err) => return Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())),
+    };
+    match payload {
+        Value::Array(batch) if !batch.is_empty() => {
+            let replies: Vec<Value> = batch.into_iter().filter_map(handle_request).collect();
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        request => handle_request(request),
+    }
+}
+
+fn handle_request(request: Value) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let (Some(method), Some("2.0")) = (
+        request.get("method").and_then(Value::as_str),
+        request.get("jsonrpc").and_then(Value::as_str),
+    ) else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "expected a JSON-RPC 2.0 request",
+        ));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = dispatch(method, params);
+    // Notifications get no reply, even on error
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(RpcError::MethodNotFound) => {
+            error_response(id, METHOD_NOT_FOUND, &format!("unknown method {method:?}"))
+        }
+        Err(RpcError::InvalidParams(msg)) => error_response(id, INVALID_PARAMS, &msg),
+        Err(RpcError::Tachi(err)) => {
+            let report = ErrorReport::new(&err);
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": TACHI_ERROR, "message": report.message, "data": report },
+            })
+        }
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+enum RpcError {
+    MethodNotFound,
+    InvalidParams(String),
+    Tachi(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        RpcError::Tachi(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct SpecParams {
+    /// Spec as YAML (or JSON) text
+    spec: String,
+    templates: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct GenerateParams {
+    spec: String,
+    out: PathBuf,
+    #[serde(default)]
+    force: bool,
+    templates: Option<PathBuf>,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    allow_secrets: bool,
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError::InvalidParams(err.to_string()))
+}
+
+fn dispatch(method: &str, raw: Value) -> Result<Value, RpcError> {
+    match method {
+        // Diagnostics are the expected outcome here, so they are a result, not an error
+        "validate" => {
+            let p: SpecParams = params(raw)?;
+            Ok(match crate::parse_spec(&p.spec) {
+                Ok(spec) => json!({ "valid": true, "warnings": crate::lint::lint(&spec) }),
+                Err(err) => json!({ "valid": false, "error": ErrorReport::new(&err) }),
+            })
+        }
+        "render" => {
+            let p: SpecParams = params(raw)?;
+            let spec = crate::parse_spec(&p.spec)?;
+            let templates = p
+                .templates
+                .map(|t| contained(&t, "templates"))
+                .transpose()?;
+            let files = generate::render(&spec, templates.as_deref())?;
+            let files: Vec<Value> = files
+                .into_iter()
+                .map(|f| {
+                    json!({
+                        "path": f.path,
+                        "contents": String::from_utf8_lossy(&f.contents),
+                    })
+                })
+                .collect();
+            Ok(json!({ "files": files }))
+        }
+        "generate" => {
+            let p: GenerateParams = params(raw)?;
+            let out = contained(&p.out, "out")?;
+            let templates = p
+                .templates
+                .map(|t| contained(&t, "templates"))
+                .transpose()?;
+            let spec = crate::parse_spec(&p.spec)?;
+            let options = GenerateOptions {
+                force: p.force,
+                templates,
+                locked: p.locked,
+                allow_secrets: p.allow_secrets,
+                ..GenerateOptions::default()
+            };
+            let written = generate::generate(&spec, &out, &options)?;
+            let files: serde_json::Map<String, Value> = written
+                .into_iter()
+                .map(|(path, status)| (path, status.label().into()))
+                .collect();
+            Ok(json!({ "files": files }))
+        }
+        "schema" => Ok(schema::json_schema()),
+        _ => Err(RpcError::MethodNotFound),
+    }
+}
+
+/// `path`, the `param` parameter, resolved against the working directory and
+/// refused unless it stays inside it, symlinks included.
+fn contained(path: &Path, param: &str) -> Result<PathBuf, RpcError> {
+    let cwd = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .context("reading the working directory")?;
+    let outside = || {
+        RpcError::InvalidParams(format!(
+            "{param} must be inside {}, where tachi serve runs",
+            cwd.display()
+        ))
+    };
+    let path = cwd.join(path);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(outside());
+    }
+    // The part that exists may be a symlink out of the working directory
+    let existing = path
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(&cwd)
+        .canonicalize()
+        .with_context(|| format!("resolving {}", path.display()))?;
+    if !existing.starts_with(&cwd) {
+        return Err(outside());
+    }
+    Ok(path)
+}
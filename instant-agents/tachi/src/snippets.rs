@@ -0,0 +1,176 @@
+use crate::error::InvalidSnippets;
+use crate::generate::indent_body;
+use crate::spec::Spec;
+use anyhow::{bail, Context, Result};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+const PYTHON: &str = "python3";
+
+/// Compiles each string of a JSON list from stdin and prints, per string,
+/// null or the `[line, message]` of its syntax error.
+const CHECK: &str = r#"
+import json, sys
+errors = []
+for source in json.load(sys.stdin):
+    try:
+        compile(source, "<spec>", "exec")
+        errors.append(None)
+    except SyntaxError as err:
+        errors.append([err.lineno or 1, err.msg])
+json.dump(errors, sys.stdout)
+"#;
+
+/// A snippet's syntax error: its 1-based line as compiled, and the message.
+type SyntaxError = (usize, String);
+
+/// Python a spec embeds, as the compiler sees it.
+struct Snippet {
+    /// Dotted spec key whose value holds the code
+    key: String,
+    /// 0-based line of the value the code starts on
+    start: usize,
+    /// Lines tachi puts in front of the code, such as a hook's `def`
+    wrapper: usize,
+    source: String,
+}
+
+/// Syntax-check the Python `spec` embeds: hook bodies, and ```python blocks
+/// in pipeline and route prompts. `yaml` is the spec's source, for the lines
+/// errors are reported at. Does nothing when `python3` is not installed.
+pub fn check_python(spec: &Spec, yaml: &str) -> Result<()> {
+    let snippets = snippets(spec);
+    if snippets.is_empty() {
+        return Ok(());
+    }
+    let sources: Vec<&str> = snippets.iter().map(|s| s.source.as_str()).collect();
+    let Some(errors) = compile(&sources)? else {
+        return Ok(());
+    };
+    let lines: Vec<&str> = yaml.lines().collect();
+    let mut messages = Vec::new();
+    for (snippet, error) in snippets.iter().zip(errors) {
+        let Some((line, message)) = error else {
+            continue;
+        };
+        let at = crate::spec_line(yaml, &spec.agent.name, &snippet.key).map(|key_line| {
+            // A `|` or `>` block scalar starts below its key
+            let block = lines[key_line - 1]
+                .split_once(": ")
+                .is_some_and(|(_, value)| value.trim_start().starts_with(['|', '>']));
+            let offset = (snippet.start + line - 1).saturating_sub(snippet.wrapper);
+            key_line + usize::from(block) + offset
+        });
+        messages.push(match at {
+            Some(at) => format!("line {at} ({}): {message}", snippet.key),
+            None => format!("{}: {message}", snippet.key),
+        });
+    }
+    if messages.is_empty() {
+        return Ok(());
+    }
+    Err(InvalidSnippets(messages).into())
+}
+
+fn snippets(spec: &Spec) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+    for (key, _, body) in spec.hooks.bodies() {
+        snippets.push(Snippet {
+            key: key.to_string(),
+            start: 0,
+            wrapper: 1,
+            source: format!("def hook():\n{}\n", indent_body(body)),
+        });
+    }
+    let mut prompts = Vec::new();
+    if let Some(pipeline) = &spec.pipeline {
+        for (i, step) in pipeline.steps.iter().enumerate() {
+            if let Some(prompt) = &step.agent {
+                prompts.push((format!("pipeline.steps[{i}].agent"), prompt));
+            }
+        }
+    }
+    if let Some(router) = &spec.router {
+        for (i, route) in router.routes.iter().enumerate() {
+            if let Some(instructions) = &route.instructions {
+                prompts.push((format!("router.routes[{i}].instructions"), instructions));
+            }
+        }
+    }
+    for (key, prompt) in prompts {
+        for (start, source) in fenced_python(prompt) {
+            snippets.push(Snippet {
+                key: key.clone(),
+                start,
+                wrapper: 0,
+                source,
+            });
+        }
+    }
+    snippets
+}
+
+/// Each ```python (or ```py) block in `text`: the 0-based line its code
+/// starts on, and the code with its common indentation removed.
+fn fenced_python(text: &str) -> Vec<(usize, String)> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(usize, Vec<&str>)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let fence = line.trim();
+        match &mut open {
+            Some((start, code)) if fence.starts_with("```") => {
+                blocks.push((*start, dedent(code)));
+                open = None;
+            }
+            Some((_, code)) => code.push(line),
+            None if matches!(fence, "```python" | "```py") => open = Some((i + 1, Vec::new())),
+            None => {}
+        }
+    }
+    blocks
+}
+
+fn dedent(lines: &[&str]) -> String {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut code = String::new();
+    for line in lines {
+        code.push_str(line.get(common..).unwrap_or_default());
+        code.push('\n');
+    }
+    code
+}
+
+/// The syntax error of each source, or None when `python3` is not installed.
+fn compile(sources: &[&str]) -> Result<Option<Vec<Option<SyntaxError>>>> {
+    let child = Command::new(PYTHON)
+        .args(["-c", CHECK])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context(format!("running {PYTHON}")),
+    };
+    child
+        .stdin
+        .take()
+        .context("opening python3's stdin")?
+        .write_all(serde_json::to_string(sources)?.as_bytes())
+        .context("writing the spec's Python to python3")?;
+    let output = child.wait_with_output().context("running python3")?;
+    if !output.status.success() {
+        bail!(
+            "{PYTHON} failed checking the spec's Python: {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    let errors = serde_json::from_slice(&output.stdout).context("reading python3's report")?;
+    Ok(Some(errors))
+}
@@ -0,0 +1,2714 @@
+use crate::error::ValidationError;
+use crate::lint;
+use crate::naming::is_py_ident;
+use crate::tools::is_repo_id;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+pub use crate::tools::{
+    AgentTool, DocumentsTool, EmailTool, FilesTool, GithubTool, HttpMethod, HttpTool, MemoryStore,
+    MemoryTool, SearchProvider, SearchTool, ShellTool, SlackTool, SqlTool, TextToImageTool, Tool,
+    ToolKind, WebpageTool, YoutubeTranscriptTool,
+};
+
+/// Oldest smolagents release the templates are known to work with.
+const MIN_SMOLAGENTS: Version = Version::new(1, 0, 0);
+/// First smolagents with the `initial_plan` / `update_plan_*` planning prompts
+const PLANNING_PROMPTS_SINCE: Version = Version::new(1, 13, 0);
+/// First smolagents whose agents call `model.generate`, which cache and limits wrap
+const MODEL_GENERATE_SINCE: Version = Version::new(1, 15, 0);
+/// First smolagents whose local executor takes `timeout_seconds`
+const EXECUTION_TIMEOUT_SINCE: Version = Version::new(1, 21, 0);
+/// First smolagents whose Hugging Face model class takes `provider`
+const MODEL_PROVIDER_SINCE: Version = Version::new(1, 10, 0);
+/// Modules that open connections, refused in `security.authorized-imports`
+/// unless `security.allow-network` is set
+const NETWORK_MODULES: &[&str] = &[
+    "aiohttp",
+    "ftplib",
+    "http",
+    "httpx",
+    "imaplib",
+    "paramiko",
+    "poplib",
+    "requests",
+    "smtplib",
+    "socket",
+    "ssl",
+    "telnetlib",
+    "urllib",
+    "urllib3",
+    "websocket",
+    "websockets",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Spec {
+    pub agent: Agent,
+    /// Language of the generated project
+    #[serde(default)]
+    pub lang: Lang,
+    /// Library the generated agent runs on
+    #[serde(default)]
+    pub framework: Framework,
+    /// Settings for `framework: openai-assistants`
+    #[serde(default)]
+    pub openai: OpenAi,
+    /// Settings for `framework: autogen`
+    #[serde(default)]
+    pub autogen: Autogen,
+    /// Settings for `framework: llamaindex`
+    #[serde(default)]
+    pub llamaindex: LlamaIndex,
+    /// Settings for `framework: semantic-kernel`
+    #[serde(default, rename = "semantic-kernel")]
+    pub semantic_kernel: SemanticKernel,
+    /// Settings for `framework: spring-ai`
+    #[serde(default, rename = "spring-ai")]
+    pub spring_ai: SpringAi,
+    #[serde(default)]
+    pub guardrails: Guardrails,
+    /// Tools that wait for the operator's go-ahead; off when unset
+    #[serde(default)]
+    pub approval: Option<Approval>,
+    /// Agent hooks in hooks.py, and commands to run after generating
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub planning: Planning,
+    /// Replay model responses for repeated calls; off when unset
+    #[serde(default)]
+    pub cache: Option<Cache>,
+    /// Abort runs that go over a token, time or spending budget
+    #[serde(default)]
+    pub limits: Option<Limits>,
+    /// What the Python the agent writes may import and how long it may run
+    #[serde(default)]
+    pub security: Option<Security>,
+    /// How the environment is set up: requirements.txt, plus environment.yml for conda
+    #[serde(default)]
+    pub packaging: Packaging,
+    /// Where the project is meant to run, each adding its own files
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub deploy: Vec<DeployTarget>,
+    /// Task runner file with install, run, test and lint recipes; none when unset
+    #[serde(default)]
+    pub tasks: Option<TaskRunner>,
+    /// pyproject.toml and pre-commit config, with the Python formatted by ruff
+    #[serde(default)]
+    pub quality: Option<Quality>,
+    /// LICENSE file and an SPDX header on each generated Python file
+    #[serde(default)]
+    pub license: Option<License>,
+    /// Year and holder, such as `2026 Acme Inc.`, for LICENSE and the file headers
+    #[serde(default)]
+    pub copyright: Option<String>,
+    /// CI workflow for the generated project; none when unset
+    #[serde(default)]
+    pub ci: Option<Ci>,
+    /// Entry points sharing agent.py, each listed in the README
+    #[serde(default = "Spec::default_interfaces")]
+    pub interfaces: Vec<Interface>,
+    /// Deployment environments by name; `APP_ENV` picks one at startup
+    #[serde(default)]
+    pub environments: BTreeMap<String, Environment>,
+    /// What `tachi bundle` builds
+    #[serde(default)]
+    pub bundle: Bundle,
+    #[serde(default)]
+    pub server: Server,
+    #[serde(default)]
+    pub cli: CliOptions,
+    #[serde(default)]
+    pub docs: Docs,
+    /// Warnings to silence, by rule name
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub lint: LintOptions,
+    #[serde(default)]
+    pub runtime: Runtime,
+    #[serde(default)]
+    pub a2a: A2a,
+    #[serde(default)]
+    pub batch: Batch,
+    #[serde(default)]
+    pub worker: Worker,
+    /// Fixed sequence of steps compiled into pipeline.py
+    #[serde(default)]
+    pub pipeline: Option<Pipeline>,
+    /// Triage requests to route-specific agents with router.py
+    #[serde(default)]
+    pub router: Option<Router>,
+    #[serde(default)]
+    pub voice: Voice,
+    /// Long-term memory searched before every turn; off when unset
+    #[serde(default)]
+    pub memory: Option<Memory>,
+    /// Documents indexed by ingest.py and queried as a tool; off when unset
+    #[serde(default)]
+    pub knowledge: Option<Knowledge>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Agent {
+    pub name: String,
+    /// What the agent is for, shown in its A2A agent card
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Output directory and image name; derived from `name` when unset
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Python package name; derived from the slug when unset
+    #[serde(default)]
+    pub package: Option<String>,
+    pub tools: Vec<AgentTool>,
+    /// Also hand the agent smolagents' base toolbox
+    #[serde(default)]
+    pub add_base_tools: bool,
+    /// Base tools to leave out again; needs `add-base-tools`
+    #[serde(default)]
+    pub exclude_base_tools: Vec<BaseTool>,
+    /// Hugging Face model; qwen-coder when unset
+    #[serde(default)]
+    pub model: Model,
+    /// Input beyond text that the generated CLI accepts
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+    /// Make answers JSON, checked after each run; free text when unset
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    /// Steps one run may take before it gives up; smolagents' 20 when unset
+    #[serde(default)]
+    pub max_steps: Option<u32>,
+    /// Sampling temperature of the model; the provider's default when unset
+    #[serde(default)]
+    pub temperature: Option<f64>,
+}
+
+/// JSON answers: `json` for any JSON value, or `json-schema: {...}` for one
+/// that matches an inline JSON Schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, try_from = "serde_yaml_ng::Value")]
+pub enum ResponseFormat {
+    Json(JsonFormat),
+    JsonSchema {
+        #[serde(rename = "json-schema")]
+        json_schema: serde_yaml_ng::Value,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonFormat {
+    Json,
+}
+
+impl TryFrom<serde_yaml_ng::Value> for ResponseFormat {
+    type Error = String;
+
+    fn try_from(value: serde_yaml_ng::Value) -> Result<Self, String> {
+        match value {
+            serde_yaml_ng::Value::String(s) if s == "json" => {
+                Ok(ResponseFormat::Json(JsonFormat::Json))
+            }
+            serde_yaml_ng::Value::Mapping(map) if map.len() == 1 => match map.get("json-schema") {
+                Some(schema) => Ok(ResponseFormat::JsonSchema {
+                    json_schema: schema.clone(),
+                }),
+                None => Err("expected `json` or `json-schema: {...}`".to_string()),
+            },
+            _ => Err("expected `json` or `json-schema: {...}`".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Model {
+    #[default]
+    #[serde(alias = "qwen-coder")]
+    QwenCoder,
+    QwenVl,
+}
+
+impl Model {
+    pub const ALL: &'static [Model] = &[Model::QwenCoder, Model::QwenVl];
+
+    pub(crate) fn model_id(&self) -> &'static str {
+        match self {
+            Model::QwenCoder => "Qwen/Qwen2.5-Coder-32B-Instruct",
+            Model::QwenVl => "Qwen/Qwen2.5-VL-72B-Instruct",
+        }
+    }
+
+    /// Whether the model accepts images alongside the prompt.
+    pub(crate) fn supports_vision(&self) -> bool {
+        matches!(self, Model::QwenVl)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Framework {
+    /// A smolagents CodeAgent on Hugging Face inference
+    #[default]
+    Smolagents,
+    /// The tools as function schemas in an OpenAI Responses API loop; with
+    /// lang go, a Chat Completions loop any OpenAI-compatible server answers
+    OpenaiAssistants,
+    /// AG2 ConversableAgents, with router routes as a group chat
+    Autogen,
+    /// A LlamaIndex ReActAgent, with the knowledge section as a vector index
+    Llamaindex,
+    /// A Semantic Kernel console app with the tools as plugins; needs lang csharp
+    SemanticKernel,
+    /// A Spring Boot service with the tools as `@Tool` beans; needs lang java
+    SpringAi,
+}
+
+impl Framework {
+    pub const ALL: &'static [Framework] = &[
+        Framework::Smolagents,
+        Framework::OpenaiAssistants,
+        Framework::Autogen,
+        Framework::Llamaindex,
+        Framework::SemanticKernel,
+        Framework::SpringAi,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Framework::Smolagents => "smolagents",
+            Framework::OpenaiAssistants => "openai-assistants",
+            Framework::Autogen => "autogen",
+            Framework::Llamaindex => "llamaindex",
+            Framework::SemanticKernel => "semantic-kernel",
+            Framework::SpringAi => "spring-ai",
+        }
+    }
+
+    /// Whether the framework generates the spec section at `key`; sections
+    /// missing from [`FRAMEWORK_FEATURES`] work with every framework.
+    pub fn supports(self, key: &str) -> bool {
+        FRAMEWORK_FEATURES
+            .iter()
+            .find(|(feature, _)| *feature == key)
+            .is_none_or(|(_, frameworks)| frameworks.contains(&self))
+    }
+
+    /// The frameworks that support `key`, as `a, b or c`, for errors that
+    /// suggest switching.
+    fn supporting(key: &str) -> String {
+        let names: Vec<&str> = Framework::ALL
+            .iter()
+            .filter(|framework| framework.supports(key))
+            .map(|framework| framework.name())
+            .collect();
+        match names.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+            None => String::new(),
+        }
+    }
+}
+
+/// Spec sections that hook into one library's agent, model or executor,
+/// and the frameworks that generate code for them.
+pub const FRAMEWORK_FEATURES: &[(&str, &[Framework])] = &[
+    ("agent.add-base-tools", &[Framework::Smolagents]),
+    ("agent.capabilities", &[Framework::Smolagents]),
+    ("agent.response-format", &[Framework::Smolagents]),
+    ("agent.max-steps", &[Framework::Smolagents]),
+    ("agent.temperature", &[Framework::Smolagents]),
+    ("interfaces.gradio", &[Framework::Smolagents]),
+    ("guardrails", &[Framework::Smolagents]),
+    ("approval", &[Framework::Smolagents]),
+    ("hooks.agent", &[Framework::Smolagents]),
+    ("planning", &[Framework::Smolagents]),
+    ("cache", &[Framework::Smolagents]),
+    ("limits", &[Framework::Smolagents]),
+    ("server.timeout", &[Framework::Smolagents]),
+    ("server.streaming", &[Framework::Smolagents]),
+    ("security", &[Framework::Smolagents]),
+    ("memory", &[Framework::Smolagents]),
+    ("a2a", &[Framework::Smolagents]),
+    ("pipeline", &[Framework::Smolagents]),
+    ("router", &[Framework::Smolagents, Framework::Autogen]),
+    ("environments.model", &[Framework::Smolagents]),
+    ("knowledge", &[Framework::Llamaindex]),
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lang {
+    #[default]
+    Python,
+    /// A .NET console project; only framework semantic-kernel generates it
+    Csharp,
+    /// A Go module with the openai-assistants tool loop and typed tool stubs
+    Go,
+    /// A Maven project; only framework spring-ai generates it
+    Java,
+}
+
+impl Lang {
+    pub const ALL: &'static [Lang] = &[Lang::Python, Lang::Csharp, Lang::Go, Lang::Java];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lang::Python => "python",
+            Lang::Csharp => "csharp",
+            Lang::Go => "go",
+            Lang::Java => "java",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OpenAi {
+    /// Model the Responses API runs; OPENAI_MODEL overrides it
+    #[serde(default = "OpenAi::default_model")]
+    pub model: String,
+    /// Model turns one run may take before it gives up
+    #[serde(default = "OpenAi::default_max_steps")]
+    pub max_steps: u32,
+}
+
+impl OpenAi {
+    fn default_model() -> String {
+        "gpt-4.1".to_string()
+    }
+    fn default_max_steps() -> u32 {
+        20
+    }
+}
+
+impl Default for OpenAi {
+    fn default() -> Self {
+        OpenAi {
+            model: OpenAi::default_model(),
+            max_steps: OpenAi::default_max_steps(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Autogen {
+    /// OpenAI model every agent in the chat runs; AUTOGEN_MODEL overrides it
+    #[serde(default = "Autogen::default_model")]
+    pub model: String,
+    /// Messages one run may exchange before the chat stops
+    #[serde(default = "Autogen::default_max_rounds")]
+    pub max_rounds: u32,
+}
+
+impl Autogen {
+    fn default_model() -> String {
+        "gpt-4.1".to_string()
+    }
+    fn default_max_rounds() -> u32 {
+        12
+    }
+}
+
+impl Default for Autogen {
+    fn default() -> Self {
+        Autogen {
+            model: Autogen::default_model(),
+            max_rounds: Autogen::default_max_rounds(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SemanticKernel {
+    /// OpenAI chat model the kernel runs; SEMANTIC_KERNEL_MODEL overrides it
+    #[serde(default = "SemanticKernel::default_model")]
+    pub model: String,
+    /// Model turns one run may take before it gives up
+    #[serde(default = "SemanticKernel::default_max_steps")]
+    pub max_steps: u32,
+}
+
+impl SemanticKernel {
+    fn default_model() -> String {
+        "gpt-4.1".to_string()
+    }
+    fn default_max_steps() -> u32 {
+        20
+    }
+}
+
+impl Default for SemanticKernel {
+    fn default() -> Self {
+        SemanticKernel {
+            model: SemanticKernel::default_model(),
+            max_steps: SemanticKernel::default_max_steps(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SpringAi {
+    /// OpenAI chat model the agent runs; OPENAI_MODEL overrides it
+    #[serde(default = "SpringAi::default_model")]
+    pub model: String,
+    /// Model turns one run may take before it gives up
+    #[serde(default = "SpringAi::default_max_steps")]
+    pub max_steps: u32,
+}
+
+impl SpringAi {
+    fn default_model() -> String {
+        "gpt-4.1".to_string()
+    }
+    fn default_max_steps() -> u32 {
+        20
+    }
+}
+
+impl Default for SpringAi {
+    fn default() -> Self {
+        SpringAi {
+            model: SpringAi::default_model(),
+            max_steps: SpringAi::default_max_steps(),
+        }
+    }
+}
+
+/// LlamaIndex runs agent.model on Hugging Face inference, like smolagents.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LlamaIndex {
+    /// Reasoning steps one run may take before it gives up
+    #[serde(default = "LlamaIndex::default_max_iterations")]
+    pub max_iterations: u32,
+}
+
+impl LlamaIndex {
+    fn default_max_iterations() -> u32 {
+        20
+    }
+}
+
+impl Default for LlamaIndex {
+    fn default() -> Self {
+        LlamaIndex {
+            max_iterations: LlamaIndex::default_max_iterations(),
+        }
+    }
+}
+
+/// A way to talk to the agent, each with its own entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interface {
+    /// cli.py, shaped by the `cli` section
+    Cli,
+    /// server.py: `POST /run` with `{"task": ...}` answers with `{"answer": ...}`
+    Server,
+    /// gradio_app.py: smolagents' Gradio chat UI in the browser
+    Gradio,
+}
+
+impl Interface {
+    pub const ALL: &'static [Interface] = &[Interface::Cli, Interface::Server, Interface::Gradio];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interface::Cli => "cli",
+            Interface::Server => "server",
+            Interface::Gradio => "gradio",
+        }
+    }
+}
+
+/// A tool in smolagents' base toolbox. Its python_interpreter is only for
+/// tool-calling agents, and final_answer is always there, since a
+/// CodeAgent run ends by calling it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BaseTool {
+    WebSearch,
+    VisitWebpage,
+}
+
+impl BaseTool {
+    pub const ALL: &'static [BaseTool] = &[BaseTool::WebSearch, BaseTool::VisitWebpage];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BaseTool::WebSearch => "web-search",
+            BaseTool::VisitWebpage => "visit-webpage",
+        }
+    }
+
+    /// The tool's name in `agent.tools` at run time.
+    pub(crate) fn py_name(self) -> &'static str {
+        match self {
+            BaseTool::WebSearch => "web_search",
+            BaseTool::VisitWebpage => "visit_webpage",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// `/image <file>` in the chat attaches an image to the next message
+    Vision,
+    /// `/voice` in the chat records a message from the microphone
+    Voice,
+}
+
+impl Capability {
+    pub const ALL: &'static [Capability] = &[Capability::Vision, Capability::Voice];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Vision => "vision",
+            Capability::Voice => "voice",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Guardrails {
+    /// Strip instruction-like content from web tool output before the model sees it
+    #[serde(default)]
+    pub injection_filter: bool,
+}
+
+/// How server.py admits clients and bounds runs; each control is off when unset.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Server {
+    /// What a request must carry before the server answers it
+    #[serde(default)]
+    pub auth: ServerAuth,
+    /// Requests one client address may make per window; more get 429
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// Runs in progress or waiting for the agent at once; more get 503
+    #[serde(default)]
+    pub max_concurrent_runs: Option<u32>,
+    /// Seconds a run may take once it starts before it is interrupted and
+    /// answered with 504
+    #[serde(default)]
+    pub timeout: Option<u32>,
+    /// Add a `/ws/chat` WebSocket that streams each run's steps and tokens
+    /// as JSON events, and chat.html to try it from a browser
+    #[serde(default)]
+    pub streaming: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServerAuth {
+    /// Anyone who can reach the port may run the agent
+    #[default]
+    None,
+    /// `Authorization: Bearer <key>` or `X-API-Key: <key>`, checked against
+    /// SERVER_API_KEY; anything else gets 401
+    ApiKey,
+}
+
+impl ServerAuth {
+    pub const ALL: &'static [ServerAuth] = &[ServerAuth::None, ServerAuth::ApiKey];
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RateLimit {
+    pub requests: u32,
+    /// Window length in seconds
+    #[serde(default = "RateLimit::default_per")]
+    pub per: u32,
+}
+
+impl RateLimit {
+    fn default_per() -> u32 {
+        60
+    }
+}
+
+/// Human-in-the-loop approval: the operator confirms each call of these tools.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Approval {
+    /// Tools from `agent.tools` whose calls need approval
+    pub tools: Vec<ToolKind>,
+    /// Seconds a2a_server.py waits for a decision before denying the call
+    #[serde(default = "Approval::default_timeout")]
+    pub timeout: u32,
+}
+
+impl Approval {
+    fn default_timeout() -> u32 {
+        300
+    }
+}
+
+/// smolagents' planning step: the agent writes a plan before acting and
+/// revises it every `interval` steps.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Planning {
+    /// Plan before acting, guided by planning_prompt.md
+    #[serde(default)]
+    pub enabled: bool,
+    /// Steps between plan updates
+    #[serde(default = "Planning::default_interval")]
+    pub interval: u32,
+}
+
+impl Planning {
+    fn default_interval() -> u32 {
+        3
+    }
+}
+
+impl Default for Planning {
+    fn default() -> Self {
+        Planning {
+            enabled: false,
+            interval: Planning::default_interval(),
+        }
+    }
+}
+
+/// Response cache for model calls, keyed on the prompt and call parameters.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Cache {
+    #[serde(default)]
+    pub backend: CacheBackend,
+    /// Seconds a cached response stays valid
+    #[serde(default = "Cache::default_ttl")]
+    pub ttl: u32,
+    /// Directory of the disk cache, relative to the project
+    #[serde(default = "Cache::default_path")]
+    pub path: String,
+}
+
+impl Cache {
+    fn default_ttl() -> u32 {
+        86400
+    }
+    fn default_path() -> String {
+        ".cache/model".to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheBackend {
+    /// One JSON file per response under `path`
+    #[default]
+    Disk,
+    /// Redis at CACHE_REDIS_URL, expiring entries itself
+    Redis,
+}
+
+impl CacheBackend {
+    pub const ALL: &'static [CacheBackend] = &[CacheBackend::Disk, CacheBackend::Redis];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Packaging {
+    /// requirements.txt for pip
+    #[default]
+    Pip,
+    /// environment.yml as well, installing the same requirements through conda's pip
+    Conda,
+}
+
+impl Packaging {
+    pub const ALL: &'static [Packaging] = &[Packaging::Pip, Packaging::Conda];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeployTarget {
+    /// .devcontainer/ for VS Code and Codespaces
+    Devcontainer,
+}
+
+impl DeployTarget {
+    pub const ALL: &'static [DeployTarget] = &[DeployTarget::Devcontainer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DeployTarget::Devcontainer => "devcontainer",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskRunner {
+    /// justfile, for `just`
+    Just,
+    /// Makefile, for `make`
+    Make,
+}
+
+impl TaskRunner {
+    pub const ALL: &'static [TaskRunner] = &[TaskRunner::Just, TaskRunner::Make];
+}
+
+/// Formatter and linter settings for the generated Python.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Quality {
+    /// Formatter configured in pyproject.toml and run by pre-commit. tachi
+    /// itself formats through ruff, whose output matches black's.
+    #[serde(default)]
+    pub formatter: Formatter,
+    #[serde(default = "Quality::default_line_length")]
+    pub line_length: u16,
+    /// Write .pre-commit-config.yaml
+    #[serde(default = "Quality::default_pre_commit")]
+    pub pre_commit: bool,
+}
+
+impl Quality {
+    fn default_line_length() -> u16 {
+        100
+    }
+    fn default_pre_commit() -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Formatter {
+    #[default]
+    Ruff,
+    Black,
+}
+
+impl Formatter {
+    pub const ALL: &'static [Formatter] = &[Formatter::Ruff, Formatter::Black];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum License {
+    Mit,
+    #[serde(rename = "apache-2.0")]
+    Apache2,
+    /// All rights reserved
+    Proprietary,
+}
+
+impl License {
+    pub const ALL: &'static [License] = &[License::Mit, License::Apache2, License::Proprietary];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            License::Mit => "mit",
+            License::Apache2 => "apache-2.0",
+            License::Proprietary => "proprietary",
+        }
+    }
+
+    /// SPDX identifier for file headers.
+    pub fn spdx(self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::Proprietary => "LicenseRef-Proprietary",
+        }
+    }
+}
+
+/// `ci: github` is short for `ci: {provider: github}`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", from = "CiRepr")]
+pub struct Ci {
+    pub provider: CiProvider,
+    /// Build the dev container image after the checks, and maybe push it
+    pub docker: Option<CiDocker>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CiRepr {
+    Provider(CiProvider),
+    Full {
+        provider: CiProvider,
+        #[serde(default)]
+        docker: Option<CiDocker>,
+    },
+}
+
+impl From<CiRepr> for Ci {
+    fn from(repr: CiRepr) -> Self {
+        match repr {
+            CiRepr::Provider(provider) => Ci {
+                provider,
+                docker: None,
+            },
+            CiRepr::Full { provider, docker } => Ci { provider, docker },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CiProvider {
+    /// .github/workflows/ci.yml for GitHub Actions
+    Github,
+}
+
+impl CiProvider {
+    pub const ALL: &'static [CiProvider] = &[CiProvider::Github];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CiDocker {
+    /// Build on every push and pull request
+    Build,
+    /// Build, and push pushes to the default branch to ghcr.io
+    Push,
+}
+
+impl CiDocker {
+    pub const ALL: &'static [CiDocker] = &[CiDocker::Build, CiDocker::Push];
+}
+
+/// `hooks: true` is short for `hooks: {agent: true}`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", from = "HooksRepr")]
+pub struct Hooks {
+    /// Call the step, tool-call and final-answer hooks in hooks.py
+    pub agent: bool,
+    /// Body of `on_step(step)`; any hook body makes hooks.py generated
+    /// rather than user-owned
+    pub on_step: Option<String>,
+    /// Body of `on_tool_call(name, arguments, result)`
+    pub on_tool_call: Option<String>,
+    /// Body of `on_final_answer(answer)`
+    pub on_final_answer: Option<String>,
+    /// Shell commands run in the project directory after `tachi gen`
+    pub post_gen: Vec<String>,
+}
+
+impl Hooks {
+    /// Each hook the spec writes the body of: its spec key, function name
+    /// and body.
+    pub(crate) fn bodies(&self) -> Vec<(&'static str, &'static str, &str)> {
+        [
+            ("hooks.on-step", "on_step", &self.on_step),
+            ("hooks.on-tool-call", "on_tool_call", &self.on_tool_call),
+            (
+                "hooks.on-final-answer",
+                "on_final_answer",
+                &self.on_final_answer,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, name, body)| Some((key, name, body.as_deref()?)))
+        .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HooksRepr {
+    Agent(bool),
+    #[serde(rename_all = "kebab-case")]
+    Full {
+        #[serde(default)]
+        agent: bool,
+        #[serde(default)]
+        on_step: Option<String>,
+        #[serde(default)]
+        on_tool_call: Option<String>,
+        #[serde(default)]
+        on_final_answer: Option<String>,
+        #[serde(default)]
+        post_gen: Vec<String>,
+    },
+}
+
+impl From<HooksRepr> for Hooks {
+    fn from(repr: HooksRepr) -> Self {
+        match repr {
+            HooksRepr::Agent(agent) => Hooks {
+                agent,
+                ..Hooks::default()
+            },
+            HooksRepr::Full {
+                agent,
+                on_step,
+                on_tool_call,
+                on_final_answer,
+                post_gen,
+            } => Hooks {
+                // A hook body is only worth writing if agent.py calls it
+                agent: agent
+                    || on_step.is_some()
+                    || on_tool_call.is_some()
+                    || on_final_answer.is_some(),
+                on_step,
+                on_tool_call,
+                on_final_answer,
+                post_gen,
+            },
+        }
+    }
+}
+
+/// A single value or a list of them.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Budgets enforced around every model call; each one is off when unset.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Limits {
+    /// Input plus output tokens one run may use
+    #[serde(default)]
+    pub max_tokens_per_run: Option<u64>,
+    /// Wall-clock seconds one run may take
+    #[serde(default)]
+    pub max_seconds_per_run: Option<u32>,
+    /// Spend across all runs in a calendar day; needs `dollars-per-million-tokens`
+    #[serde(default)]
+    pub max_dollars_per_day: Option<f64>,
+    /// The model's price, used to turn tokens into dollars
+    #[serde(default)]
+    pub dollars_per_million_tokens: Option<f64>,
+}
+
+/// Policy for the CodeAgent's Python executor. smolagents' own import list
+/// and timeout apply to whatever is unset.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Security {
+    /// Modules executed code may import beyond smolagents' safe list;
+    /// `pkg.*` allows a package's submodules
+    #[serde(default)]
+    pub authorized_imports: Vec<String>,
+    /// Allow network modules such as `requests` or `socket` in
+    /// `authorized-imports`; the local executor has no other network sandbox
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Seconds one code step may run before it is stopped
+    #[serde(default)]
+    pub max_execution_seconds: Option<u32>,
+}
+
+/// What changes in one deployment environment. Its values go in
+/// `.env.<name>`, which config.py loads over `.env` when `APP_ENV` names it.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Environment {
+    /// Hugging Face inference provider serving the model, such as together
+    #[serde(default)]
+    pub model_provider: Option<String>,
+    /// URL of a dedicated endpoint serving the model, used instead of its id
+    #[serde(default)]
+    pub model_endpoint: Option<String>,
+    /// Values for other variables the project reads, such as CACHE_REDIS_URL
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Environment {
+    /// Every value the environment sets, by variable name.
+    pub(crate) fn values(&self) -> Vec<(&str, &str)> {
+        let mut values = Vec::new();
+        if let Some(provider) = &self.model_provider {
+            values.push(("MODEL_PROVIDER", provider.as_str()));
+        }
+        if let Some(endpoint) = &self.model_endpoint {
+            values.push(("MODEL_ENDPOINT", endpoint.as_str()));
+        }
+        values.extend(self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        values
+    }
+}
+
+/// Agent-to-Agent protocol server output.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct A2a {
+    /// Generate a2a_server.py and agent_card.json
+    #[serde(default)]
+    pub enabled: bool,
+    /// Default port for a2a_server.py
+    #[serde(default = "A2a::default_port")]
+    pub port: u16,
+    /// Public URL advertised in the agent card; `http://localhost:<port>/` when unset
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl A2a {
+    fn default_port() -> u16 {
+        8000
+    }
+}
+
+impl Default for A2a {
+    fn default() -> Self {
+        A2a {
+            enabled: false,
+            port: A2a::default_port(),
+            url: None,
+        }
+    }
+}
+
+/// Offline batch runner output.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Batch {
+    /// Generate batch.py
+    #[serde(default)]
+    pub enabled: bool,
+    /// Prompts to run: a CSV with a `prompt` column, or JSONL objects with a `prompt` key
+    #[serde(default = "Batch::default_input")]
+    pub input: String,
+    /// Results file, CSV or JSONL by extension
+    #[serde(default = "Batch::default_output")]
+    pub output: String,
+    /// Extra attempts for a prompt whose run raises
+    #[serde(default = "Batch::default_retries")]
+    pub retries: u32,
+    /// Also generate a cron entry or systemd timer that runs the batch
+    #[serde(default)]
+    pub timer: Option<BatchTimer>,
+}
+
+impl Batch {
+    fn default_input() -> String {
+        "prompts.jsonl".to_string()
+    }
+    fn default_output() -> String {
+        "results.jsonl".to_string()
+    }
+    fn default_retries() -> u32 {
+        2
+    }
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Batch {
+            enabled: false,
+            input: Batch::default_input(),
+            output: Batch::default_output(),
+            retries: Batch::default_retries(),
+            timer: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BatchTimer {
+    pub kind: TimerKind,
+    /// Five-field cron expression for cron, an `OnCalendar=` value for systemd
+    pub schedule: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimerKind {
+    /// batch.cron, a crontab line to install
+    Cron,
+    /// `<slug>-batch.service` and `<slug>-batch.timer` units
+    Systemd,
+}
+
+impl TimerKind {
+    pub const ALL: &'static [TimerKind] = &[TimerKind::Cron, TimerKind::Systemd];
+}
+
+/// Queue worker output: worker.py and a docker-compose.yml with the broker.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Worker {
+    /// Generate worker.py and docker-compose.yml
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: WorkerBackend,
+    /// Queue the worker consumes
+    #[serde(default = "Worker::default_queue")]
+    pub queue: String,
+    /// Seconds a task may run before it is killed
+    #[serde(default = "Worker::default_timeout")]
+    pub timeout: u32,
+}
+
+impl Worker {
+    fn default_queue() -> String {
+        "agent".to_string()
+    }
+    fn default_timeout() -> u32 {
+        600
+    }
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        Worker {
+            enabled: false,
+            backend: WorkerBackend::default(),
+            queue: Worker::default_queue(),
+            timeout: Worker::default_timeout(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkerBackend {
+    /// RQ on Redis
+    #[default]
+    Rq,
+    /// Celery with Redis as broker and result backend
+    Celery,
+}
+
+impl WorkerBackend {
+    pub const ALL: &'static [WorkerBackend] = &[WorkerBackend::Rq, WorkerBackend::Celery];
+}
+
+/// The single-file artifact `tachi bundle` builds into `dist/`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Bundle {
+    #[serde(default)]
+    pub format: BundleFormat,
+    /// Interface the artifact starts; the first in `interfaces` when unset
+    #[serde(default)]
+    pub entry: Option<Interface>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BundleFormat {
+    /// A zipapp (.pyz) with the dependencies inside; the target needs Python
+    #[default]
+    Shiv,
+    /// A native executable; the target needs nothing installed
+    Pyinstaller,
+}
+
+impl BundleFormat {
+    pub const ALL: &'static [BundleFormat] = &[BundleFormat::Shiv, BundleFormat::Pyinstaller];
+}
+
+/// Steps run in order by pipeline.py. Prompts and tool arguments may use
+/// `{input}` for the pipeline's input and `{<step>}` for an earlier step's output.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Pipeline {
+    pub steps: Vec<PipelineStep>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PipelineStep {
+    /// Python identifier later steps refer to the output by
+    pub name: String,
+    /// Prompt for a fresh agent run
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Tool to call directly instead, by its name in the agent (`web_search`)
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Arguments for `tool`
+    #[serde(default)]
+    pub args: BTreeMap<String, String>,
+}
+
+impl PipelineStep {
+    /// Every `{name}` placeholder in the step's prompt and arguments.
+    pub(crate) fn placeholders(&self) -> Vec<&str> {
+        self.agent
+            .iter()
+            .chain(self.args.values())
+            .flat_map(|text| {
+                text.split('{').skip(1).filter_map(|rest| {
+                    let (name, _) = rest.split_once('}')?;
+                    is_py_ident(name).then_some(name)
+                })
+            })
+            .collect()
+    }
+}
+
+/// A classifier that picks one route per request; each route runs the agent
+/// with its own tools and instructions.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Router {
+    /// Model that classifies requests; `agent.model` when unset
+    #[serde(default)]
+    pub model: Option<Model>,
+    pub routes: Vec<Route>,
+    /// Route for requests the classifier cannot place; the first route when unset
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Route {
+    pub name: String,
+    /// What belongs on this route, shown to the classifier
+    pub description: String,
+    /// Put before each request this route's agent runs
+    #[serde(default)]
+    pub instructions: Option<String>,
+    /// Tools from `agent.tools` this route's agent gets; all of them when empty
+    #[serde(default)]
+    pub tools: Vec<ToolKind>,
+}
+
+/// Settings for the `voice` capability.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Voice {
+    #[serde(default)]
+    pub whisper_model: WhisperModel,
+    /// Spoken language as an ISO 639-1 code; Whisper detects it when unset
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Microphone, by index or part of its name; the system default when unset
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Speakers for `tts`, by index or part of its name
+    #[serde(default)]
+    pub output_device: Option<String>,
+    /// Read answers aloud; `/mute` toggles it in the chat
+    #[serde(default)]
+    pub tts: bool,
+}
+
+/// faster-whisper model size: larger is more accurate and slower.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WhisperModel {
+    Tiny,
+    #[default]
+    Base,
+    Small,
+    Medium,
+    LargeV3,
+}
+
+impl WhisperModel {
+    pub const ALL: &'static [WhisperModel] = &[
+        WhisperModel::Tiny,
+        WhisperModel::Base,
+        WhisperModel::Small,
+        WhisperModel::Medium,
+        WhisperModel::LargeV3,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "tiny",
+            WhisperModel::Base => "base",
+            WhisperModel::Small => "small",
+            WhisperModel::Medium => "medium",
+            WhisperModel::LargeV3 => "large-v3",
+        }
+    }
+}
+
+/// Long-term memory: past exchanges and ingested notes are embedded, and
+/// the closest matches are appended to each prompt.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Memory {
+    pub kind: MemoryKind,
+    /// sentence-transformers model used to embed memories and queries
+    #[serde(default = "Memory::default_embedding_model")]
+    pub embedding_model: String,
+    /// JSONL file holding the memories, relative to the project
+    #[serde(default = "Memory::default_path")]
+    pub path: String,
+    /// Memories appended to each prompt
+    #[serde(default = "Memory::default_top_k")]
+    pub top_k: u32,
+    /// Days a memory is kept; unset keeps it forever
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Most memories kept; the oldest are dropped first
+    #[serde(default)]
+    pub max_entries: Option<u32>,
+}
+
+impl Memory {
+    fn default_embedding_model() -> String {
+        "sentence-transformers/all-MiniLM-L6-v2".to_string()
+    }
+    fn default_path() -> String {
+        "memory/vectors.jsonl".to_string()
+    }
+    fn default_top_k() -> u32 {
+        3
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemoryKind {
+    /// Embedding similarity search
+    Vector,
+}
+
+impl MemoryKind {
+    pub const ALL: &'static [MemoryKind] = &[MemoryKind::Vector];
+}
+
+/// Documents the agent answers from: ingest.py embeds them into a vector
+/// index, which the agent queries as its `knowledge` tool.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Knowledge {
+    /// Directory of documents to index, relative to the project
+    #[serde(default = "Knowledge::default_path")]
+    pub path: String,
+    /// Directory the index is persisted to, relative to the project
+    #[serde(default = "Knowledge::default_index")]
+    pub index: String,
+    /// Hugging Face embedding model for documents and queries
+    #[serde(default = "Knowledge::default_embedding_model")]
+    pub embedding_model: String,
+    /// Chunks retrieved for each query
+    #[serde(default = "Knowledge::default_top_k")]
+    pub top_k: u32,
+}
+
+impl Knowledge {
+    fn default_path() -> String {
+        "knowledge".to_string()
+    }
+    fn default_index() -> String {
+        "knowledge-index".to_string()
+    }
+    fn default_embedding_model() -> String {
+        "sentence-transformers/all-MiniLM-L6-v2".to_string()
+    }
+    fn default_top_k() -> u32 {
+        4
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Runtime {
+    /// Pin smolagents and target its API; latest when unset
+    #[serde(default)]
+    pub smolagents_version: Option<Version>,
+}
+
+/// A `major.minor[.patch]` release number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid version {s:?}, expected major.minor[.patch]");
+        let parts: Vec<u32> = s
+            .split('.')
+            .map(|p| p.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        match parts[..] {
+            [major, minor] => Ok(Version::new(major, minor, 0)),
+            [major, minor, patch] => Ok(Version::new(major, minor, patch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<Version> for String {
+    fn from(v: Version) -> String {
+        v.to_string()
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Docs {
+    /// Write architecture.md with a Mermaid diagram of the agent
+    #[serde(default)]
+    pub architecture: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LintOptions {
+    /// Lint rules to skip for this spec
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CliOptions {
+    #[serde(default)]
+    pub framework: CliFramework,
+    #[serde(default)]
+    pub ui: CliUi,
+    /// Record each chat session as JSONL and generate replay.py
+    #[serde(default)]
+    pub transcripts: bool,
+    #[serde(default)]
+    pub input: CliInput,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliFramework {
+    /// Bare `input()` chat loop
+    #[default]
+    Plain,
+    /// Typer app with chat/ask/tools/version subcommands
+    Typer,
+}
+
+impl CliFramework {
+    pub const ALL: &'static [CliFramework] = &[CliFramework::Plain, CliFramework::Typer];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliUi {
+    /// Print answers as plain text
+    #[default]
+    Plain,
+    /// Markdown answers, step spinners and highlighted code via `rich`
+    Rich,
+}
+
+impl CliUi {
+    pub const ALL: &'static [CliUi] = &[CliUi::Plain, CliUi::Rich];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliInput {
+    /// Python's built-in `input()`
+    #[default]
+    Plain,
+    /// Persistent history, Alt+Enter multi-line editing and Ctrl+R search
+    PromptToolkit,
+}
+
+impl CliInput {
+    pub const ALL: &'static [CliInput] = &[CliInput::Plain, CliInput::PromptToolkit];
+}
+
+impl Spec {
+    fn default_interfaces() -> Vec<Interface> {
+        vec![Interface::Cli]
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_names()?;
+        let mut seen = HashSet::new();
+        let mut names = HashSet::new();
+        for tool in &self.agent.tools {
+            if !seen.insert(tool.kind()) {
+                return Err(ValidationError::new(
+                    "agent.tools",
+                    format!("tool {} is listed more than once", tool.kind()),
+                ));
+            }
+            tool.validate()?;
+            tool.validate_overrides()?;
+            if let Some(name) = tool.name.as_ref().filter(|name| !names.insert(*name)) {
+                return Err(ValidationError::new(
+                    "agent.tools",
+                    format!("more than one tool is named {name}"),
+                ));
+            }
+        }
+        self.validate_base_tools()?;
+        self.validate_interfaces()?;
+        self.validate_framework()?;
+        self.validate_lang()?;
+        self.validate_environments()?;
+        if let Some(entry) = self.bundle.entry {
+            if !self.has_interface(entry) {
+                return Err(ValidationError::new(
+                    "bundle.entry",
+                    format!(
+                        "bundle.entry is {}, which is not in interfaces",
+                        entry.name()
+                    ),
+                ));
+            }
+        }
+        let mut seen = HashSet::new();
+        for capability in &self.agent.capabilities {
+            if !seen.insert(capability) {
+                return Err(ValidationError::new(
+                    "agent.capabilities",
+                    format!("capability {} is listed more than once", capability.name()),
+                ));
+            }
+        }
+        if self.has_capability(Capability::Vision) && !self.agent.model.supports_vision() {
+            let vision_models: Vec<String> = Model::ALL
+                .iter()
+                .filter(|m| m.supports_vision())
+                .filter_map(|m| serde_json::to_value(m).ok()?.as_str().map(String::from))
+                .collect();
+            return Err(ValidationError::new(
+                "agent.capabilities",
+                format!(
+                    "vision needs a model that accepts images ({}); {} is text-only",
+                    vision_models.join(", "),
+                    self.agent.model.model_id()
+                ),
+            ));
+        }
+        if let Some(language) = &self.voice.language {
+            if language.len() != 2 || !language.chars().all(|c| c.is_ascii_lowercase()) {
+                return Err(ValidationError::new(
+                    "voice.language",
+                    format!("voice.language {language:?} must be a two-letter code such as en"),
+                ));
+            }
+        }
+        if let Some(memory) = &self.memory {
+            let invalid = |key: &'static str, msg: String| Err(ValidationError::new(key, msg));
+            if !is_repo_id(&memory.embedding_model) {
+                return invalid(
+                    "memory.embedding-model",
+                    format!(
+                        "memory.embedding-model {:?} must be a Hugging Face model id like owner/name",
+                        memory.embedding_model
+                    ),
+                );
+            }
+            let path = memory.path.trim_end_matches('/');
+            if path.is_empty() || (!path.starts_with('/') && path.split('/').any(|p| p == "..")) {
+                return invalid(
+                    "memory.path",
+                    format!(
+                        "memory.path {:?} must be a file inside the project or an absolute path",
+                        memory.path
+                    ),
+                );
+            }
+            if memory.top_k == 0 {
+                return invalid(
+                    "memory.top-k",
+                    "memory.top-k must be at least 1".to_string(),
+                );
+            }
+            if memory.retention_days == Some(0) {
+                return invalid(
+                    "memory.retention-days",
+                    "memory.retention-days must be at least 1".to_string(),
+                );
+            }
+            if memory.max_entries == Some(0) {
+                return invalid(
+                    "memory.max-entries",
+                    "memory.max-entries must be at least 1".to_string(),
+                );
+            }
+        }
+        if let Some(knowledge) = &self.knowledge {
+            let invalid = |key: &'static str, msg: String| Err(ValidationError::new(key, msg));
+            if !is_repo_id(&knowledge.embedding_model) {
+                return invalid(
+                    "knowledge.embedding-model",
+                    format!(
+                        "knowledge.embedding-model {:?} must be a Hugging Face model id like owner/name",
+                        knowledge.embedding_model
+                    ),
+                );
+            }
+            for (key, dir) in [
+                ("knowledge.path", &knowledge.path),
+                ("knowledge.index", &knowledge.index),
+            ] {
+                let path = dir.trim_end_matches('/');
+                if path.is_empty() || (!path.starts_with('/') && path.split('/').any(|p| p == ".."))
+                {
+                    return invalid(
+                        key,
+                        format!("{key} {dir:?} must be a directory inside the project or an absolute path"),
+                    );
+                }
+            }
+            if knowledge.path.trim_end_matches('/') == knowledge.index.trim_end_matches('/') {
+                return invalid(
+                    "knowledge.index",
+                    "knowledge.index must not be the documents directory".to_string(),
+                );
+            }
+            if knowledge.top_k == 0 {
+                return invalid(
+                    "knowledge.top-k",
+                    "knowledge.top-k must be at least 1".to_string(),
+                );
+            }
+        }
+        if let Some(ResponseFormat::JsonSchema { json_schema }) = &self.agent.response_format {
+            if !json_schema.is_mapping() || serde_json::to_value(json_schema).is_err() {
+                return Err(ValidationError::new(
+                    "agent.response-format",
+                    "agent.response-format json-schema must be a JSON Schema object",
+                ));
+            }
+        }
+        if self.agent.max_steps == Some(0) {
+            return Err(ValidationError::new(
+                "agent.max-steps",
+                "agent.max-steps must be at least 1",
+            ));
+        }
+        if let Some(temperature) = self.agent.temperature {
+            // The range OpenAI-compatible providers accept
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(ValidationError::new(
+                    "agent.temperature",
+                    format!("agent.temperature must be between 0 and 2, not {temperature}"),
+                ));
+            }
+        }
+        if let Some(approval) = &self.approval {
+            self.validate_approval(approval)?;
+        }
+        if let Some(cache) = &self.cache {
+            if cache.ttl == 0 {
+                return Err(ValidationError::new(
+                    "cache.ttl",
+                    "cache.ttl must be at least 1 second",
+                ));
+            }
+            let path = cache.path.trim_end_matches('/');
+            if path.is_empty() || path.starts_with('/') || path.split('/').any(|p| p == "..") {
+                return Err(ValidationError::new(
+                    "cache.path",
+                    format!(
+                        "cache.path {:?} must be a directory inside the project",
+                        cache.path
+                    ),
+                ));
+            }
+        }
+        if let Some(limits) = &self.limits {
+            self.validate_limits(limits)?;
+        }
+        if let Some(security) = &self.security {
+            self.validate_security(security)?;
+        }
+        let mut seen = HashSet::new();
+        for target in &self.deploy {
+            if !seen.insert(target) {
+                return Err(ValidationError::new(
+                    "deploy",
+                    format!("deploy target {} is listed more than once", target.name()),
+                ));
+            }
+        }
+        if self.hooks.post_gen.iter().any(|c| c.trim().is_empty()) {
+            return Err(ValidationError::new(
+                "hooks.post-gen",
+                "hooks.post-gen has an empty command",
+            ));
+        }
+        if let Some(copyright) = &self.copyright {
+            if copyright.trim().is_empty() || copyright.contains('\n') {
+                return Err(ValidationError::new(
+                    "copyright",
+                    "copyright must be one line, such as `2026 Acme Inc.`",
+                ));
+            }
+        } else if let Some(license) = self.license {
+            return Err(ValidationError::new(
+                "copyright",
+                format!(
+                    "license {} needs copyright: the year and holder named in LICENSE",
+                    license.name()
+                ),
+            ));
+        }
+        if self.ci.as_ref().is_some_and(|ci| ci.docker.is_some())
+            && !self.deploy.contains(&DeployTarget::Devcontainer)
+        {
+            return Err(ValidationError::new(
+                "ci.docker",
+                "ci.docker builds .devcontainer/Dockerfile; add `deploy: devcontainer`",
+            ));
+        }
+        if let Some(quality) = &self.quality {
+            // ruff's own bounds
+            if !(1..=320).contains(&quality.line_length) {
+                return Err(ValidationError::new(
+                    "quality.line-length",
+                    format!(
+                        "quality.line-length must be between 1 and 320, not {}",
+                        quality.line_length
+                    ),
+                ));
+            }
+        }
+        if self.planning.enabled && self.planning.interval == 0 {
+            return Err(ValidationError::new(
+                "planning.interval",
+                "planning.interval must be at least 1 step",
+            ));
+        }
+        if self.batch.enabled {
+            for (key, value) in [
+                ("batch.input", &self.batch.input),
+                ("batch.output", &self.batch.output),
+            ] {
+                let tabular = [".csv", ".jsonl"].iter().any(|ext| value.ends_with(ext));
+                if !tabular {
+                    return Err(ValidationError::new(
+                        key,
+                        format!("{key} {value:?} must be a .csv or .jsonl file"),
+                    ));
+                }
+            }
+            if let Some(timer) = &self.batch.timer {
+                let schedule = timer.schedule.trim();
+                let valid = match timer.kind {
+                    TimerKind::Cron => schedule.split_whitespace().count() == 5,
+                    TimerKind::Systemd => !schedule.is_empty() && !schedule.contains('\n'),
+                };
+                if !valid {
+                    return Err(ValidationError::new(
+                        "batch.timer.schedule",
+                        match timer.kind {
+                            TimerKind::Cron => format!(
+                                "batch.timer.schedule {schedule:?} must have five cron fields, like \"0 2 * * *\""
+                            ),
+                            TimerKind::Systemd => format!(
+                                "batch.timer.schedule {schedule:?} must be one OnCalendar= value, like daily"
+                            ),
+                        },
+                    ));
+                }
+            }
+        }
+        if self.worker.enabled {
+            let queue = &self.worker.queue;
+            let valid = !queue.is_empty()
+                && queue
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+            if !valid {
+                return Err(ValidationError::new(
+                    "worker.queue",
+                    format!("worker.queue {queue:?} may only use letters, digits, _ . and -"),
+                ));
+            }
+            if self.worker.timeout == 0 {
+                return Err(ValidationError::new(
+                    "worker.timeout",
+                    "worker.timeout must be at least 1 second",
+                ));
+            }
+        }
+        if let Some(pipeline) = &self.pipeline {
+            self.validate_pipeline(pipeline)?;
+        }
+        if let Some(router) = &self.router {
+            self.validate_router(router)?;
+        }
+        if self.a2a.port == 0 {
+            return Err(ValidationError::new("a2a.port", "a2a.port must not be 0"));
+        }
+        if let Some(version) = self.runtime.smolagents_version {
+            if version < MIN_SMOLAGENTS {
+                return Err(ValidationError::new(
+                    "runtime.smolagents-version",
+                    format!(
+                        "smolagents {version} is older than the oldest supported {MIN_SMOLAGENTS}"
+                    ),
+                ));
+            }
+            for tool in &self.agent.tools {
+                let since = tool.kind().since();
+                if since > version {
+                    return Err(ValidationError::new(
+                        "agent.tools",
+                        format!(
+                            "tool {} needs smolagents {since} but runtime.smolagents-version is {version}",
+                            tool.kind()
+                        ),
+                    ));
+                }
+            }
+            if (self.cache.is_some() || self.limits.is_some()) && version < MODEL_GENERATE_SINCE {
+                let key = if self.cache.is_some() {
+                    "cache"
+                } else {
+                    "limits"
+                };
+                return Err(ValidationError::new(
+                    key,
+                    format!(
+                        "{key} needs smolagents {MODEL_GENERATE_SINCE} but runtime.smolagents-version is {version}"
+                    ),
+                ));
+            }
+            let timeout = self.security.as_ref().and_then(|s| s.max_execution_seconds);
+            if timeout.is_some() && version < EXECUTION_TIMEOUT_SINCE {
+                return Err(ValidationError::new(
+                    "security.max-execution-seconds",
+                    format!(
+                        "security.max-execution-seconds needs smolagents {EXECUTION_TIMEOUT_SINCE} but runtime.smolagents-version is {version}"
+                    ),
+                ));
+            }
+            let provider = self
+                .environments
+                .values()
+                .any(|e| e.model_provider.is_some());
+            if provider && version < MODEL_PROVIDER_SINCE {
+                return Err(ValidationError::new(
+                    "environments",
+                    format!(
+                        "model-provider needs smolagents {MODEL_PROVIDER_SINCE} but runtime.smolagents-version is {version}"
+                    ),
+                ));
+            }
+            if self.planning.enabled && version < PLANNING_PROMPTS_SINCE {
+                return Err(ValidationError::new(
+                    "planning.enabled",
+                    format!(
+                        "planning needs smolagents {PLANNING_PROMPTS_SINCE} but runtime.smolagents-version is {version}"
+                    ),
+                ));
+            }
+        }
+        for (key, allow) in [("allow", &self.allow), ("lint.allow", &self.lint.allow)] {
+            for rule in allow {
+                if !lint::rule_names().any(|name| name == rule) {
+                    return Err(ValidationError::new(
+                        key,
+                        format!("{key} names unknown rule {rule:?}"),
+                    ));
+                }
+            }
+        }
+        self.validate_server()
+    }
+
+    fn validate_server(&self) -> Result<(), ValidationError> {
+        let server = &self.server;
+        if *server != Server::default() && !self.has_interface(Interface::Server) {
+            return Err(ValidationError::new(
+                "server",
+                "server configures server.py, but interfaces has no server",
+            ));
+        }
+        let rate_limit = server.rate_limit.as_ref();
+        for (key, value) in [
+            ("server.rate-limit.requests", rate_limit.map(|r| r.requests)),
+            ("server.rate-limit.per", rate_limit.map(|r| r.per)),
+            ("server.max-concurrent-runs", server.max_concurrent_runs),
+            ("server.timeout", server.timeout),
+        ] {
+            if value == Some(0) {
+                return Err(ValidationError::new(
+                    key,
+                    format!("{key} must be at least 1"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_limits(&self, limits: &Limits) -> Result<(), ValidationError> {
+        if limits.max_tokens_per_run.is_none()
+            && limits.max_seconds_per_run.is_none()
+            && limits.max_dollars_per_day.is_none()
+        {
+            return Err(ValidationError::new(
+                "limits",
+                "limits needs at least one of max-tokens-per-run, max-seconds-per-run or max-dollars-per-day",
+            ));
+        }
+        for (key, value) in [
+            (
+                "limits.max-tokens-per-run",
+                limits.max_tokens_per_run.map(|n| n as f64),
+            ),
+            (
+                "limits.max-seconds-per-run",
+                limits.max_seconds_per_run.map(f64::from),
+            ),
+            ("limits.max-dollars-per-day", limits.max_dollars_per_day),
+            (
+                "limits.dollars-per-million-tokens",
+                limits.dollars_per_million_tokens,
+            ),
+        ] {
+            if value.is_some_and(|v| !(v > 0.0 && v.is_finite())) {
+                return Err(ValidationError::new(
+                    key,
+                    format!("{key} must be more than 0"),
+                ));
+            }
+        }
+        if limits.max_dollars_per_day.is_some() && limits.dollars_per_million_tokens.is_none() {
+            return Err(ValidationError::new(
+                "limits.dollars-per-million-tokens",
+                "limits.max-dollars-per-day needs dollars-per-million-tokens to price tokens",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Each framework's settings section applies to it alone, and its model
+    /// and run limit must be usable.
+    fn validate_framework(&self) -> Result<(), ValidationError> {
+        let framework = self.framework.name();
+        if self.framework != Framework::OpenaiAssistants && self.openai != OpenAi::default() {
+            return Err(ValidationError::new(
+                "openai",
+                "openai only applies to framework openai-assistants",
+            ));
+        }
+        if self.framework != Framework::Autogen && self.autogen != Autogen::default() {
+            return Err(ValidationError::new(
+                "autogen",
+                "autogen only applies to framework autogen",
+            ));
+        }
+        if self.framework != Framework::SemanticKernel
+            && self.semantic_kernel != SemanticKernel::default()
+        {
+            return Err(ValidationError::new(
+                "semantic-kernel",
+                "semantic-kernel only applies to framework semantic-kernel",
+            ));
+        }
+        if self.framework != Framework::SpringAi && self.spring_ai != SpringAi::default() {
+            return Err(ValidationError::new(
+                "spring-ai",
+                "spring-ai only applies to framework spring-ai",
+            ));
+        }
+        if self.framework != Framework::Llamaindex && self.llamaindex != LlamaIndex::default() {
+            return Err(ValidationError::new(
+                "llamaindex",
+                "llamaindex only applies to framework llamaindex",
+            ));
+        }
+        self.validate_features()?;
+        // The framework's own model setting, if it has one, and its cap on one run
+        let (model, (limit_key, limit)) = match self.framework {
+            Framework::Smolagents => return Ok(()),
+            Framework::OpenaiAssistants => (
+                Some(("openai.model", &self.openai.model)),
+                ("openai.max-steps", self.openai.max_steps),
+            ),
+            Framework::Autogen => (
+                Some(("autogen.model", &self.autogen.model)),
+                ("autogen.max-rounds", self.autogen.max_rounds),
+            ),
+            Framework::SemanticKernel => (
+                Some(("semantic-kernel.model", &self.semantic_kernel.model)),
+                ("semantic-kernel.max-steps", self.semantic_kernel.max_steps),
+            ),
+            Framework::SpringAi => (
+                Some(("spring-ai.model", &self.spring_ai.model)),
+                ("spring-ai.max-steps", self.spring_ai.max_steps),
+            ),
+            Framework::Llamaindex => (
+                None,
+                ("llamaindex.max-iterations", self.llamaindex.max_iterations),
+            ),
+        };
+        if let Some((model_key, model)) = model {
+            if model.trim().is_empty() {
+                return Err(ValidationError::new(
+                    model_key,
+                    format!("{model_key} must name a model, such as gpt-4.1"),
+                ));
+            }
+            if self.agent.model != Model::default() {
+                return Err(ValidationError::new(
+                    "agent.model",
+                    format!("agent.model picks a Hugging Face model, which framework {framework} does not use; set {model_key}"),
+                ));
+            }
+        }
+        if limit == 0 {
+            return Err(ValidationError::new(
+                limit_key,
+                format!("{limit_key} must be at least 1"),
+            ));
+        }
+        if let Some(router) = self
+            .router
+            .as_ref()
+            .filter(|_| self.framework == Framework::Autogen)
+        {
+            // The group chat manager picks each speaker with autogen.model
+            if router.model.is_some() {
+                return Err(ValidationError::new(
+                    "router.model",
+                    "router.model picks the classifier, which framework autogen does not use; its group chat manager runs autogen.model",
+                ));
+            }
+            if router.fallback.is_some() {
+                return Err(ValidationError::new(
+                    "router.fallback",
+                    "router.fallback needs framework smolagents; in framework autogen the group chat manager picks every speaker",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Each section in use must be one [`FRAMEWORK_FEATURES`] lists for the
+    /// spec's framework; the error names the frameworks that would take it.
+    fn validate_features(&self) -> Result<(), ValidationError> {
+        let environments = || self.environments.values();
+        let used = [
+            ("agent.add-base-tools", self.agent.add_base_tools),
+            ("agent.capabilities", !self.agent.capabilities.is_empty()),
+            (
+                "agent.response-format",
+                self.agent.response_format.is_some(),
+            ),
+            ("agent.max-steps", self.agent.max_steps.is_some()),
+            ("agent.temperature", self.agent.temperature.is_some()),
+            ("interfaces.gradio", self.has_interface(Interface::Gradio)),
+            ("guardrails", self.guardrails.injection_filter),
+            ("approval", self.approval.is_some()),
+            ("hooks.agent", self.hooks.agent),
+            ("planning", self.planning.enabled),
+            ("cache", self.cache.is_some()),
+            ("limits", self.limits.is_some()),
+            ("server.timeout", self.server.timeout.is_some()),
+            ("server.streaming", self.server.streaming),
+            ("security", self.security.is_some()),
+            ("memory", self.memory.is_some()),
+            ("a2a", self.a2a.enabled),
+            ("pipeline", self.pipeline.is_some()),
+            ("router", self.router.is_some()),
+            (
+                "environments.model",
+                environments().any(|e| e.model_provider.is_some() || e.model_endpoint.is_some()),
+            ),
+            ("knowledge", self.knowledge.is_some()),
+        ];
+        let unsupported = used
+            .iter()
+            .find(|(feature, used)| *used && !self.framework.supports(feature));
+        if let Some((feature, _)) = unsupported {
+            let (key, what) = match *feature {
+                "interfaces.gradio" => ("interfaces", "the gradio interface"),
+                "environments.model" => (
+                    "environments",
+                    "model-provider and model-endpoint in environments",
+                ),
+                feature => (feature, feature),
+            };
+            return Err(ValidationError::new(
+                key,
+                format!(
+                    "{what} needs framework {}; {} does not support it",
+                    Framework::supporting(feature),
+                    self.framework.name()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_lang(&self) -> Result<(), ValidationError> {
+        match (self.lang, self.framework) {
+            (Lang::Python, Framework::SemanticKernel) => Err(ValidationError::new(
+                "framework",
+                "framework semantic-kernel generates C#; set lang csharp",
+            )),
+            (Lang::Csharp, Framework::SemanticKernel) => self.validate_csharp(),
+            (Lang::Csharp, framework) => Err(ValidationError::new(
+                "lang",
+                format!(
+                    "lang csharp needs framework semantic-kernel; {} does not generate C#",
+                    framework.name()
+                ),
+            )),
+            (Lang::Python, Framework::SpringAi) => Err(ValidationError::new(
+                "framework",
+                "framework spring-ai generates Java; set lang java",
+            )),
+            (Lang::Java, Framework::SpringAi) => self.validate_console_app(),
+            (Lang::Java, framework) => Err(ValidationError::new(
+                "lang",
+                format!(
+                    "lang java needs framework spring-ai; {} does not generate Java",
+                    framework.name()
+                ),
+            )),
+            (Lang::Go, Framework::OpenaiAssistants) => self.validate_console_app(),
+            (Lang::Go, framework) => Err(ValidationError::new(
+                "lang",
+                format!(
+                    "lang go needs framework openai-assistants, whose tool loop it ports; {} does not generate Go",
+                    framework.name()
+                ),
+            )),
+            (Lang::Python, _) => Ok(()),
+        }
+    }
+
+    /// Every tool needs a C# plugin, which keeps its own function names.
+    fn validate_csharp(&self) -> Result<(), ValidationError> {
+        for tool in &self.agent.tools {
+            let kind = tool.kind();
+            if tool.tool.cs_plugin().is_none() {
+                let ported: Vec<&str> = ToolKind::ALL
+                    .iter()
+                    .filter(|k| Tool::from(**k).cs_plugin().is_some())
+                    .map(|k| k.name())
+                    .collect();
+                let what = if kind == ToolKind::Webpage {
+                    "webpage with render-js".to_string()
+                } else {
+                    format!("tool {kind}")
+                };
+                return Err(ValidationError::new(
+                    "agent.tools",
+                    format!(
+                        "{what} has no C# plugin; lang csharp supports {}",
+                        ported.join(", ")
+                    ),
+                ));
+            }
+            if tool.name.is_some() || tool.description.is_some() {
+                return Err(ValidationError::new(
+                    "agent.tools",
+                    format!("lang csharp keeps each plugin's own names; tool {kind} cannot set name or description"),
+                ));
+            }
+        }
+        self.validate_console_app()
+    }
+
+    /// The C#, Go and Java projects are one console app; the rest of the spec
+    /// describes Python files they have no counterpart for.
+    fn validate_console_app(&self) -> Result<(), ValidationError> {
+        let lang = self.lang.name();
+        if self.interfaces != [Interface::Cli] {
+            return Err(ValidationError::new(
+                "interfaces",
+                format!("lang {lang} generates a console app; interfaces must be [cli]"),
+            ));
+        }
+        let cli = &self.cli;
+        let python_only = [
+            ("packaging", self.packaging != Packaging::default()),
+            ("deploy", !self.deploy.is_empty()),
+            ("tasks", self.tasks.is_some()),
+            ("quality", self.quality.is_some()),
+            ("ci", self.ci.is_some()),
+            ("environments", !self.environments.is_empty()),
+            ("bundle", self.bundle != Bundle::default()),
+            (
+                "cli",
+                cli.framework != CliFramework::default()
+                    || cli.ui != CliUi::default()
+                    || cli.transcripts
+                    || cli.input != CliInput::default(),
+            ),
+            ("runtime", self.runtime.smolagents_version.is_some()),
+            ("batch", self.batch.enabled),
+            ("worker", self.worker.enabled),
+        ];
+        if let Some((key, _)) = python_only.iter().find(|(_, used)| *used) {
+            return Err(ValidationError::new(
+                key,
+                format!("{key} needs lang python; lang {lang} does not support it"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_interfaces(&self) -> Result<(), ValidationError> {
+        if self.interfaces.is_empty() {
+            return Err(ValidationError::new(
+                "interfaces",
+                "interfaces needs at least one of cli, server or gradio",
+            ));
+        }
+        let mut seen = HashSet::new();
+        for interface in &self.interfaces {
+            if !seen.insert(interface) {
+                return Err(ValidationError::new(
+                    "interfaces",
+                    format!("interface {} is listed more than once", interface.name()),
+                ));
+            }
+        }
+        if self.has_interface(Interface::Cli) {
+            return Ok(());
+        }
+        // These only change cli.py
+        if let Some(capability) = self.agent.capabilities.first() {
+            return Err(ValidationError::new(
+                "agent.capabilities",
+                format!(
+                    "capability {} is part of cli.py, but interfaces has no cli",
+                    capability.name()
+                ),
+            ));
+        }
+        if self.cli.transcripts {
+            return Err(ValidationError::new(
+                "cli.transcripts",
+                "cli.transcripts is part of cli.py, but interfaces has no cli",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_environments(&self) -> Result<(), ValidationError> {
+        let invalid = |msg: String| Err(ValidationError::new("environments", msg));
+        for (name, environment) in &self.environments {
+            let valid_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !valid_name {
+                return invalid(format!(
+                    "environment {name:?} must be lowercase letters, digits and hyphens, as it names .env.{name}"
+                ));
+            }
+            if environment.model_provider.is_some() && environment.model_endpoint.is_some() {
+                return invalid(format!(
+                    "environment {name} sets model-provider and model-endpoint; a dedicated endpoint has no provider"
+                ));
+            }
+            if environment.model_provider.as_deref() == Some("") {
+                return invalid(format!("environment {name} has an empty model-provider"));
+            }
+            if let Some(endpoint) = &environment.model_endpoint {
+                if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+                    return invalid(format!(
+                        "environment {name} has model-endpoint {endpoint:?}, which is not an http(s) URL"
+                    ));
+                }
+            }
+            for var in environment.env.keys() {
+                let valid_var = var.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+                    && var
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+                if !valid_var {
+                    return invalid(format!(
+                        "environment {name} sets {var:?}, which is not an environment variable name like CACHE_REDIS_URL"
+                    ));
+                }
+                match var.as_str() {
+                    "APP_ENV" => {
+                        return invalid(format!(
+                            "environment {name} sets APP_ENV, which chooses the environment and so cannot come from one"
+                        ))
+                    }
+                    "MODEL_PROVIDER" | "MODEL_ENDPOINT" => {
+                        let key = var.to_lowercase().replace('_', "-");
+                        return invalid(format!(
+                            "environment {name} sets {var} in env; use its {key} instead"
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_base_tools(&self) -> Result<(), ValidationError> {
+        let key = "agent.exclude-base-tools";
+        if !self.agent.exclude_base_tools.is_empty() && !self.agent.add_base_tools {
+            return Err(ValidationError::new(
+                key,
+                "agent.exclude-base-tools needs agent.add-base-tools",
+            ));
+        }
+        let mut seen = HashSet::new();
+        for tool in &self.agent.exclude_base_tools {
+            if !seen.insert(tool) {
+                return Err(ValidationError::new(
+                    key,
+                    format!("base tool {} is excluded more than once", tool.name()),
+                ));
+            }
+        }
+        if !self.agent.add_base_tools {
+            return Ok(());
+        }
+        // smolagents puts the base tools over any listed tool of the same name
+        for tool in &self.agent.tools {
+            let name = match (&tool.name, tool.kind()) {
+                (Some(name), _) => name.as_str(),
+                (None, ToolKind::Search) => "web_search",
+                (None, ToolKind::Webpage) => "visit_webpage",
+                _ => continue,
+            };
+            if let Some(base) = BaseTool::ALL.iter().find(|b| b.py_name() == name) {
+                return Err(ValidationError::new(
+                    "agent.add-base-tools",
+                    format!(
+                        "add-base-tools replaces tool {} with its own {name}; give the tool another name, or drop it and keep base tool {}",
+                        tool.kind(),
+                        base.name()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Base tools the agent ends up with.
+    pub(crate) fn base_tools(&self) -> Vec<BaseTool> {
+        if !self.agent.add_base_tools {
+            return Vec::new();
+        }
+        BaseTool::ALL
+            .iter()
+            .copied()
+            .filter(|b| !self.agent.exclude_base_tools.contains(b))
+            .collect()
+    }
+
+    fn validate_security(&self, security: &Security) -> Result<(), ValidationError> {
+        let key = "security.authorized-imports";
+        for module in &security.authorized_imports {
+            if module == "*" {
+                return Err(ValidationError::new(
+                    key,
+                    "security.authorized-imports cannot be `*`; list the modules the agent needs",
+                ));
+            }
+            let name = module.strip_suffix(".*").unwrap_or(module);
+            if !name.split('.').all(is_py_ident) {
+                return Err(ValidationError::new(
+                    key,
+                    format!("{key} has {module:?}, which is not a module name"),
+                ));
+            }
+            let top = name.split('.').next().unwrap_or(name);
+            if !security.allow_network && NETWORK_MODULES.contains(&top) {
+                return Err(ValidationError::new(
+                    key,
+                    format!(
+                        "{module} lets executed code open network connections; set security.allow-network to authorize it"
+                    ),
+                ));
+            }
+        }
+        if security.max_execution_seconds == Some(0) {
+            return Err(ValidationError::new(
+                "security.max-execution-seconds",
+                "security.max-execution-seconds must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_approval(&self, approval: &Approval) -> Result<(), ValidationError> {
+        if approval.tools.is_empty() {
+            return Err(ValidationError::new(
+                "approval.tools",
+                "approval needs at least one tool",
+            ));
+        }
+        for kind in &approval.tools {
+            let Some(tool) = self.agent.tools.iter().find(|t| t.kind() == *kind) else {
+                return Err(ValidationError::new(
+                    "approval.tools",
+                    format!("approval lists tool {kind}, which is not in agent.tools"),
+                ));
+            };
+            if tool.approval_classes().is_empty() {
+                return Err(ValidationError::new(
+                    "approval.tools",
+                    format!("approval lists tool {kind}, but its tools here only read"),
+                ));
+            }
+        }
+        if approval.timeout == 0 {
+            return Err(ValidationError::new(
+                "approval.timeout",
+                "approval.timeout must be at least 1 second",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_pipeline(&self, pipeline: &Pipeline) -> Result<(), ValidationError> {
+        let invalid = |msg: String| Err(ValidationError::new("pipeline.steps", msg));
+        if pipeline.steps.is_empty() {
+            return invalid("pipeline needs at least one step".to_string());
+        }
+        let mut earlier = vec!["input"];
+        for step in &pipeline.steps {
+            let name = &step.name;
+            if !is_py_ident(name) {
+                return invalid(format!(
+                    "pipeline step name {name:?} must be a Python identifier"
+                ));
+            }
+            if earlier.contains(&name.as_str()) {
+                return invalid(format!(
+                    "pipeline step name {name:?} is already used by an earlier step or is `input`"
+                ));
+            }
+            match (&step.agent, &step.tool) {
+                (Some(prompt), None) => {
+                    if prompt.trim().is_empty() {
+                        return invalid(format!("pipeline step {name} has an empty prompt"));
+                    }
+                    if !step.args.is_empty() {
+                        return invalid(format!(
+                            "pipeline step {name} has args, which only tool steps take"
+                        ));
+                    }
+                }
+                (None, Some(tool)) => {
+                    if !is_py_ident(tool) {
+                        return invalid(format!(
+                            "pipeline step {name} tool {tool:?} must be a tool name such as web_search"
+                        ));
+                    }
+                }
+                _ => {
+                    return invalid(format!(
+                        "pipeline step {name} needs exactly one of agent (a prompt) or tool"
+                    ))
+                }
+            }
+            for placeholder in step.placeholders() {
+                if !earlier.contains(&placeholder) {
+                    return invalid(format!(
+                        "pipeline step {name} uses {{{placeholder}}}, which is neither input nor an earlier step"
+                    ));
+                }
+            }
+            earlier.push(name);
+        }
+        Ok(())
+    }
+
+    fn validate_router(&self, router: &Router) -> Result<(), ValidationError> {
+        let invalid = |msg: String| Err(ValidationError::new("router.routes", msg));
+        if router.routes.len() < 2 {
+            return invalid("router needs at least two routes to choose between".to_string());
+        }
+        let mut names = HashSet::new();
+        for route in &router.routes {
+            let name = &route.name;
+            let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-".contains(c));
+            if !valid {
+                return invalid(format!(
+                    "router route name {name:?} must be lowercase letters, digits, _ and -"
+                ));
+            }
+            if !names.insert(name.as_str()) {
+                return invalid(format!("router route {name} is listed more than once"));
+            }
+            if route.description.trim().is_empty() {
+                return invalid(format!(
+                    "router route {name} needs a description for the classifier"
+                ));
+            }
+            for kind in &route.tools {
+                if !self.has_tool(*kind) {
+                    return invalid(format!(
+                        "router route {name} uses tool {kind}, which is not in agent.tools"
+                    ));
+                }
+            }
+        }
+        if let Some(fallback) = &router.fallback {
+            if !names.contains(fallback.as_str()) {
+                return Err(ValidationError::new(
+                    "router.fallback",
+                    format!("router.fallback {fallback:?} is not one of the routes"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Id of the model the agent runs on, as agent.py names it.
+    pub(crate) fn model_id(&self) -> &str {
+        match self.framework {
+            Framework::Smolagents => self.agent.model.model_id(),
+            Framework::OpenaiAssistants => &self.openai.model,
+            Framework::Autogen => &self.autogen.model,
+            Framework::Llamaindex => self.agent.model.model_id(),
+            Framework::SemanticKernel => &self.semantic_kernel.model,
+            Framework::SpringAi => &self.spring_ai.model,
+        }
+    }
+
+    /// `HfApiModel` was renamed `InferenceClientModel` in smolagents 1.14.
+    pub(crate) fn model_class(&self) -> &'static str {
+        match self.runtime.smolagents_version {
+            Some(v) if v < Version::new(1, 14, 0) => "HfApiModel",
+            _ => "InferenceClientModel",
+        }
+    }
+
+    /// Whether CodeAgent should ask the model for JSON steps (provider JSON
+    /// mode), which smolagents supports from 1.17 on.
+    pub(crate) fn structured_outputs(&self) -> bool {
+        self.agent.response_format.is_some()
+            && self
+                .runtime
+                .smolagents_version
+                .is_none_or(|v| v >= Version::new(1, 17, 0))
+    }
+
+    /// `APP_ENV` when it is unset: development if the spec has one, else the
+    /// first environment by name.
+    pub(crate) fn default_environment(&self) -> &str {
+        if self.environments.contains_key("development") {
+            return "development";
+        }
+        self.environments
+            .keys()
+            .next()
+            .map_or("development", String::as_str)
+    }
+
+    /// smolagents 1.20 moved DuckDuckGoSearchTool from `duckduckgo-search` to `ddgs`.
+    /// None when the search tool uses another provider and no base web_search
+    /// needs it either.
+    pub(crate) fn search_package(&self) -> Option<&'static str> {
+        let other_provider = self.agent.tools.iter().any(
+            |t| matches!(&t.tool, Tool::Search(search) if search.provider != SearchProvider::Duckduckgo),
+        ) && !self.base_tools().contains(&BaseTool::WebSearch);
+        match self.runtime.smolagents_version {
+            _ if other_provider => None,
+            Some(v) if v < Version::new(1, 20, 0) => Some("duckduckgo-search"),
+            _ => Some("ddgs"),
+        }
+    }
+
+    /// Python classes of every tool, in the order agent.py lists them.
+    pub(crate) fn tool_classes(&self) -> Vec<&'static str> {
+        self.agent
+            .tools
+            .iter()
+            .flat_map(|t| t.py_classes())
+            .collect()
+    }
+
+    pub(crate) fn has_interface(&self, interface: Interface) -> bool {
+        self.interfaces.contains(&interface)
+    }
+
+    /// The interface `tachi bundle` starts.
+    pub(crate) fn bundle_entry(&self) -> Interface {
+        self.bundle
+            .entry
+            .or(self.interfaces.first().copied())
+            .unwrap_or(Interface::Cli)
+    }
+
+    pub(crate) fn has_capability(&self, capability: Capability) -> bool {
+        self.agent.capabilities.contains(&capability)
+    }
+
+    pub(crate) fn has_tool(&self, kind: ToolKind) -> bool {
+        self.agent.tools.iter().any(|t| t.kind() == kind)
+    }
+
+    pub(crate) fn wants_injection_filter(&self) -> bool {
+        self.guardrails.injection_filter
+            && self
+                .agent
+                .tools
+                .iter()
+                .any(|t| t.kind().fetches_web_content())
+    }
+
+    /// Python classes whose calls wait for the operator's approval.
+    pub(crate) fn approval_classes(&self) -> Vec<&'static str> {
+        let Some(approval) = &self.approval else {
+            return Vec::new();
+        };
+        self.agent
+            .tools
+            .iter()
+            .filter(|t| approval.tools.contains(&t.kind()))
+            .flat_map(|t| t.approval_classes())
+            .collect()
+    }
+
+    /// Whether any tool comes from the generated tools.py.
+    pub(crate) fn has_local_tools(&self) -> bool {
+        self.agent.tools.iter().any(|t| !t.ships_with_smolagents())
+    }
+}
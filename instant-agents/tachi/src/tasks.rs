@@ -0,0 +1,197 @@
+use crate::spec::{CliFramework, DeployTarget, Framework, Interface, Packaging, Spec, TaskRunner};
+use std::fmt::Write;
+
+/// One task of the generated task runner.
+struct Recipe {
+    name: &'static str,
+    doc: &'static str,
+    commands: Vec<String>,
+    /// Extra arguments are passed to the last command
+    takes_args: bool,
+}
+
+impl Recipe {
+    fn new(name: &'static str, doc: &'static str, commands: Vec<String>) -> Self {
+        Recipe {
+            name,
+            doc,
+            commands,
+            takes_args: false,
+        }
+    }
+}
+
+/// The same recipes for every project, plus one per generated entry point.
+fn recipes(spec: &Spec) -> Vec<Recipe> {
+    let slug = spec.names().slug;
+    let (python, install) = match spec.packaging {
+        Packaging::Pip => (
+            ".venv/bin/python".to_string(),
+            vec![
+                "python3 -m venv .venv".to_string(),
+                ".venv/bin/pip install -r requirements.txt".to_string(),
+            ],
+        ),
+        Packaging::Conda => (
+            format!("conda run --no-capture-output -n {slug} python"),
+            vec!["conda env update --file environment.yml --prune".to_string()],
+        ),
+    };
+    let mut recipes = vec![Recipe::new("install", "Install the dependencies", install)];
+    if spec.has_interface(Interface::Cli) {
+        recipes.push(Recipe {
+            takes_args: true,
+            ..Recipe::new(
+                "run",
+                match spec.cli.framework {
+                    CliFramework::Plain => "Chat with the agent, or pass a question",
+                    CliFramework::Typer => "Run the CLI; pass a command and its arguments",
+                },
+                vec![format!("{python} cli.py")],
+            )
+        });
+    }
+    if spec.has_interface(Interface::Server) {
+        recipes.push(Recipe::new(
+            "server",
+            "Serve the agent over HTTP",
+            vec![format!("{python} server.py")],
+        ));
+    }
+    if spec.has_interface(Interface::Gradio) {
+        recipes.push(Recipe::new(
+            "gradio",
+            "Chat with the agent in the browser",
+            vec![format!("{python} gradio_app.py")],
+        ));
+    }
+    recipes.extend([
+        Recipe::new(
+            "test",
+            "Check that every module compiles",
+            vec![format!("{python} -m compileall -q -x '/\\.venv/' .")],
+        ),
+        Recipe::new(
+            "lint",
+            "Lint the Python code (needs ruff on PATH)",
+            vec!["ruff check .".to_string()],
+        ),
+    ]);
+    if spec.deploy.contains(&DeployTarget::Devcontainer) {
+        recipes.push(Recipe::new(
+            "docker-build",
+            "Build the dev container image",
+            vec![format!(
+                "docker build -f .devcontainer/Dockerfile -t {} .",
+                spec.names().image
+            )],
+        ));
+    }
+    if spec.a2a.enabled {
+        recipes.push(Recipe::new(
+            "serve",
+            "Serve the agent over A2A",
+            vec![format!("{python} a2a_server.py")],
+        ));
+    }
+    if spec.batch.enabled {
+        recipes.push(Recipe::new(
+            "batch",
+            "Run the agent over the batch input",
+            vec![format!("{python} batch.py")],
+        ));
+    }
+    if spec.worker.enabled {
+        recipes.push(Recipe::new(
+            "worker",
+            "Start Redis and the queue worker",
+            vec!["docker compose up".to_string()],
+        ));
+    }
+    if spec.pipeline.is_some() {
+        recipes.push(Recipe {
+            takes_args: true,
+            ..Recipe::new(
+                "pipeline",
+                "Run the pipeline on an input",
+                vec![format!("{python} pipeline.py")],
+            )
+        });
+    }
+    if spec.knowledge.is_some() {
+        recipes.push(Recipe::new(
+            "ingest",
+            "Rebuild the knowledge index from its documents",
+            vec![format!("{python} ingest.py")],
+        ));
+    }
+    if spec.router.is_some() && spec.framework == Framework::Smolagents {
+        recipes.push(Recipe {
+            takes_args: true,
+            ..Recipe::new(
+                "router",
+                "Route a request, or chat through the router",
+                vec![format!("{python} router.py")],
+            )
+        });
+    }
+    recipes
+}
+
+/// `justfile` or `Makefile` with the project's recipes, by `tasks`.
+pub(crate) fn render(spec: &Spec, runner: TaskRunner) -> (&'static str, String) {
+    let recipes = recipes(spec);
+    let mut out = String::new();
+    match runner {
+        TaskRunner::Just => {
+            // Positional arguments keep a quoted question in one piece
+            out.push_str(
+                "# Run `just` to list the recipes.\n\n\
+                 set positional-arguments\n\n\
+                 _default:\n    @just --list\n",
+            );
+            for recipe in &recipes {
+                let params = if recipe.takes_args { " *args" } else { "" };
+                let _ = write!(out, "\n# {}\n{}{params}:\n", recipe.doc, recipe.name);
+                for (i, command) in recipe.commands.iter().enumerate() {
+                    let last = i + 1 == recipe.commands.len();
+                    let args = if recipe.takes_args && last {
+                        " \"$@\""
+                    } else {
+                        ""
+                    };
+                    let _ = writeln!(out, "    {command}{args}");
+                }
+            }
+            ("justfile", out)
+        }
+        TaskRunner::Make => {
+            let names: Vec<&str> = recipes.iter().map(|r| r.name).collect();
+            let _ = write!(
+                out,
+                "# Run `make help` to list the targets. Pass arguments to run and\n\
+                 # friends with ARGS, e.g. make run ARGS='\"What is 2+2?\"'.\n\n\
+                 .PHONY: help {}\n\n\
+                 help:\n\t@grep -B1 -E '^[a-z-]+:' Makefile | grep -E '^#' | sed 's/^# //'\n",
+                names.join(" ")
+            );
+            for recipe in &recipes {
+                let _ = write!(
+                    out,
+                    "\n# {}: {}\n{}:\n",
+                    recipe.name, recipe.doc, recipe.name
+                );
+                for (i, command) in recipe.commands.iter().enumerate() {
+                    let last = i + 1 == recipe.commands.len();
+                    let args = if recipe.takes_args && last {
+                        " $(ARGS)"
+                    } else {
+                        ""
+                    };
+                    let _ = writeln!(out, "\t{command}{args}");
+                }
+            }
+            ("Makefile", out)
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::generate;
+use crate::parse_spec;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Specs every template pack is rendered against; between them they use
+/// each built-in template.
+const EXAMPLES: &[(&str, &str)] = &[
+    ("minimal", include_str!("examples/minimal.yml")),
+    ("services", include_str!("examples/services.yml")),
+    ("workflow", include_str!("examples/workflow.yml")),
+    ("router", include_str!("examples/router.yml")),
+    ("openai", include_str!("examples/openai.yml")),
+    ("autogen", include_str!("examples/autogen.yml")),
+    ("llamaindex", include_str!("examples/llamaindex.yml")),
+    (
+        "semantic-kernel",
+        include_str!("examples/semantic_kernel.yml"),
+    ),
+    ("go", include_str!("examples/go.yml")),
+    ("spring-ai", include_str!("examples/spring_ai.yml")),
+];
+
+/// Golden files, as `tests/<example>/<path>`, relative to the pack.
+const TESTS_DIR: &str = "tests";
+/// Strings a render must or must not contain, by example and path.
+const EXPECTATIONS: &str = "expect.yml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Expectation {
+    #[serde(default)]
+    contains: Vec<String>,
+    #[serde(default)]
+    excludes: Vec<String>,
+}
+
+/// One way a template pack's output differs from what the pack expects.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum TemplateFailure {
+    RenderFailed {
+        example: String,
+        error: String,
+    },
+    /// The golden file differs from the render
+    GoldenMismatch {
+        example: String,
+        path: String,
+    },
+    /// A golden file or expectation names a file the example does not render
+    NotRendered {
+        example: String,
+        path: String,
+    },
+    MissingText {
+        example: String,
+        path: String,
+        text: String,
+    },
+    UnexpectedText {
+        example: String,
+        path: String,
+        text: String,
+    },
+    UnknownExample {
+        example: String,
+    },
+}
+
+impl fmt::Display for TemplateFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateFailure::RenderFailed { example, error } => {
+                write!(f, "{example}: rendering failed: {error}")
+            }
+            TemplateFailure::GoldenMismatch { example, path } => {
+                write!(f, "{example}: {path} differs from the golden file")
+            }
+            TemplateFailure::NotRendered { example, path } => {
+                write!(f, "{example}: {path} is not rendered")
+            }
+            TemplateFailure::MissingText {
+                example,
+                path,
+                text,
+            } => write!(f, "{example}: {path} does not contain {text:?}"),
+            TemplateFailure::UnexpectedText {
+                example,
+                path,
+                text,
+            } => write!(f, "{example}: {path} contains {text:?}"),
+            TemplateFailure::UnknownExample { example } => write!(
+                f,
+                "{example} is not an example spec; tachi has {}",
+                example_names().join(", ")
+            ),
+        }
+    }
+}
+
+/// What `test_templates` checked and found.
+#[derive(Debug, Default, Serialize)]
+pub struct TemplateTest {
+    /// Golden files compared and expectations tested
+    pub checks: usize,
+    pub failures: Vec<TemplateFailure>,
+    /// Golden files written by `update`, relative to the pack
+    pub updated: Vec<String>,
+}
+
+/// Names of the example specs packs are tested against.
+pub fn example_names() -> Vec<&'static str> {
+    EXAMPLES.iter().map(|(name, _)| *name).collect()
+}
+
+/// Render each example spec with the templates in `pack` and compare the
+/// output with the pack's `tests/<example>/` golden files and the strings
+/// `tests/expect.yml` lists. With `update`, golden files are rewritten from
+/// the render instead, and an example without a directory gets one holding
+/// every file it renders.
+pub fn test_templates(pack: &Path, update: bool) -> Result<TemplateTest> {
+    if !pack.is_dir() {
+        bail!("{} is not a directory", pack.display());
+    }
+    let tests = pack.join(TESTS_DIR);
+    let expect_path = tests.join(EXPECTATIONS);
+    let expectations: BTreeMap<String, BTreeMap<String, Expectation>> = if expect_path.exists() {
+        let yaml = fs::read_to_string(&expect_path)
+            .with_context(|| format!("reading {}", expect_path.display()))?;
+        serde_yaml_ng::from_str(&yaml)
+            .with_context(|| format!("parsing {}", expect_path.display()))?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut result = TemplateTest::default();
+    let mut named: BTreeSet<String> = golden_examples(&tests)?.into_iter().collect();
+    named.extend(expectations.keys().cloned());
+    for example in named {
+        if !EXAMPLES.iter().any(|(name, _)| *name == example) {
+            result
+                .failures
+                .push(TemplateFailure::UnknownExample { example });
+        }
+    }
+
+    for (example, yaml) in EXAMPLES {
+        let spec = parse_spec(yaml).with_context(|| format!("example spec {example}"))?;
+        let rendered: BTreeMap<String, Vec<u8>> = match generate::render(&spec, Some(pack)) {
+            Ok(files) => files.into_iter().map(|f| (f.path, f.contents)).collect(),
+            Err(err) => {
+                result.failures.push(TemplateFailure::RenderFailed {
+                    example: example.to_string(),
+                    error: format!("{err:#}"),
+                });
+                continue;
+            }
+        };
+        let example = example.to_string();
+
+        let golden_dir = tests.join(&example);
+        let mut goldens = Vec::new();
+        if golden_dir.is_dir() {
+            golden_files(&golden_dir, "", &mut goldens)?;
+        } else if update {
+            goldens = rendered.keys().cloned().collect();
+        }
+        for path in goldens {
+            let golden = golden_dir.join(&path);
+            let Some(contents) = rendered.get(&path) else {
+                result.failures.push(TemplateFailure::NotRendered {
+                    example: example.clone(),
+                    path,
+                });
+                continue;
+            };
+            if update {
+                if let Some(parent) = golden.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("creating {}", parent.display()))?;
+                }
+                fs::write(&golden, contents)
+                    .with_context(|| format!("writing {}", golden.display()))?;
+                result.updated.push(format!("{TESTS_DIR}/{example}/{path}"));
+                continue;
+            }
+            result.checks += 1;
+            let expected =
+                fs::read(&golden).with_context(|| format!("reading {}", golden.display()))?;
+            if &expected != contents {
+                result.failures.push(TemplateFailure::GoldenMismatch {
+                    example: example.clone(),
+                    path,
+                });
+            }
+        }
+
+        for (path, expectation) in expectations.get(&example).into_iter().flatten() {
+            let Some(contents) = rendered.get(path) else {
+                result.failures.push(TemplateFailure::NotRendered {
+                    example: example.clone(),
+                    path: path.clone(),
+                });
+                continue;
+            };
+            let text = String::from_utf8_lossy(contents);
+            for wanted in &expectation.contains {
+                result.checks += 1;
+                if !text.contains(wanted.as_str()) {
+                    result.failures.push(TemplateFailure::MissingText {
+                        example: example.clone(),
+                        path: path.clone(),
+                        text: wanted.clone(),
+                    });
+                }
+            }
+            for unwanted in &expectation.excludes {
+                result.checks += 1;
+                if text.contains(unwanted.as_str()) {
+                    result.failures.push(TemplateFailure::UnexpectedText {
+                        example: example.clone(),
+                        path: path.clone(),
+                        text: unwanted.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Names of the directories under `tests`, each an example's golden files.
+fn golden_examples(tests: &Path) -> Result<Vec<String>> {
+    if !tests.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(tests).with_context(|| format!("reading {}", tests.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            names.push(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Every file under `dir`, as `/`-separated paths after `prefix`.
+fn golden_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .collect::<std::io::Result<_>>()
+        .with_context(|| format!("reading {}", dir.display()))?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            golden_files(&entry.path(), &format!("{name}/"), files)?;
+        } else {
+            files.push(name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,580 @@
+use crate::naming::{py_ident, snake_case};
+use crate::pack;
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tera::{Tera, Value};
+
+/// Built-in templates, registered under the name a user override replaces:
+/// `<dir>/agent.py.tera` takes the place of `agent.py`.
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("agent.py", include_str!("templates/agent.py.tera")),
+    (
+        "agent_openai.py",
+        include_str!("templates/agent_openai.py.tera"),
+    ),
+    (
+        "agent_autogen.py",
+        include_str!("templates/agent_autogen.py.tera"),
+    ),
+    (
+        "agent_llamaindex.py",
+        include_str!("templates/agent_llamaindex.py.tera"),
+    ),
+    ("cli.py", include_str!("templates/cli.py.tera")),
+    ("cli_typer.py", include_str!("templates/cli_typer.py.tera")),
+    (
+        "chat_session.py",
+        include_str!("templates/chat_session.py.tera"),
+    ),
+    ("replay.py", include_str!("templates/replay.py.tera")),
+    (
+        "guardrails.py",
+        include_str!("templates/guardrails.py.tera"),
+    ),
+    ("server.py", include_str!("templates/server.py.tera")),
+    ("chat.html", include_str!("templates/chat.html.tera")),
+    ("health.py", include_str!("templates/health.py.tera")),
+    ("openapi.yaml", include_str!("templates/openapi.yaml.tera")),
+    (
+        "a2a_server.py",
+        include_str!("templates/a2a_server.py.tera"),
+    ),
+    ("tools.py", include_str!("templates/tools.py.tera")),
+    ("voice.py", include_str!("templates/voice.py.tera")),
+    ("memory.py", include_str!("templates/memory.py.tera")),
+    ("ingest.py", include_str!("templates/ingest.py.tera")),
+    (
+        "agent_router.py",
+        include_str!("templates/agent_router.py.tera"),
+    ),
+    (
+        "agent_router_django.py",
+        include_str!("templates/agent_router_django.py.tera"),
+    ),
+    (
+        "gradio_app.py",
+        include_str!("templates/gradio_app.py.tera"),
+    ),
+    ("README.md", include_str!("templates/README.md.tera")),
+    ("config.py", include_str!("templates/config.py.tera")),
+    ("batch.py", include_str!("templates/batch.py.tera")),
+    ("worker.py", include_str!("templates/worker.py.tera")),
+    ("pipeline.py", include_str!("templates/pipeline.py.tera")),
+    ("router.py", include_str!("templates/router.py.tera")),
+    ("approval.py", include_str!("templates/approval.py.tera")),
+    ("hooks.py", include_str!("templates/hooks.py.tera")),
+    ("cache.py", include_str!("templates/cache.py.tera")),
+    ("limits.py", include_str!("templates/limits.py.tera")),
+    (
+        "response_format.py",
+        include_str!("templates/response_format.py.tera"),
+    ),
+    (
+        "planning_prompt.md",
+        include_str!("templates/planning_prompt.md.tera"),
+    ),
+    (
+        "test_agent.py",
+        include_str!("templates/test_agent.py.tera"),
+    ),
+    ("LICENSE", include_str!("templates/LICENSE.tera")),
+    ("agent.csproj", include_str!("templates/agent.csproj.tera")),
+    ("Program.cs", include_str!("templates/Program.cs.tera")),
+    ("Agent.cs", include_str!("templates/Agent.cs.tera")),
+    (
+        "MathPlugin.cs",
+        include_str!("templates/MathPlugin.cs.tera"),
+    ),
+    (
+        "HttpPlugin.cs",
+        include_str!("templates/HttpPlugin.cs.tera"),
+    ),
+    (
+        "FilesPlugin.cs",
+        include_str!("templates/FilesPlugin.cs.tera"),
+    ),
+    (
+        "WebpagePlugin.cs",
+        include_str!("templates/WebpagePlugin.cs.tera"),
+    ),
+    ("Web.cs", include_str!("templates/Web.cs.tera")),
+    (
+        "README_csharp.md",
+        include_str!("templates/README_csharp.md.tera"),
+    ),
+    ("go.mod", include_str!("templates/go.mod.tera")),
+    ("main.go", include_str!("templates/main.go.tera")),
+    ("agent.go", include_str!("templates/agent.go.tera")),
+    ("client.go", include_str!("templates/client.go.tera")),
+    ("config.go", include_str!("templates/config.go.tera")),
+    ("tools.go", include_str!("templates/tools.go.tera")),
+    ("tool.go", include_str!("templates/tool.go.tera")),
+    ("README_go.md", include_str!("templates/README_go.md.tera")),
+    ("pom.xml", include_str!("templates/pom.xml.tera")),
+    (
+        "application.properties",
+        include_str!("templates/application.properties.tera"),
+    ),
+    (
+        "Application.java",
+        include_str!("templates/Application.java.tera"),
+    ),
+    ("Agent.java", include_str!("templates/Agent.java.tera")),
+    (
+        "AgentTools.java",
+        include_str!("templates/AgentTools.java.tera"),
+    ),
+    ("Tool.java", include_str!("templates/Tool.java.tera")),
+    (
+        "README_java.md",
+        include_str!("templates/README_java.md.tera"),
+    ),
+    (
+        "docker-compose.yml",
+        include_str!("templates/docker-compose.yml.tera"),
+    ),
+];
+
+pub(crate) const OVERRIDE_EXTENSION: &str = ".tera";
+
+/// Where `tachi vendor` copies the templates, relative to the project.
+const VENDOR_DIR: &str = ".tachi/templates";
+/// sha256sum-style hashes of the vendored templates, relative to the project.
+const VENDOR_LOCK: &str = ".tachi/templates.lock";
+
+/// Build the template set once per run: built-ins, then any overrides from
+/// `overrides`, with tachi's filters registered.
+pub fn environment(overrides: Option<&Path>) -> Result<Tera> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(sources(overrides)?)
+        .context("loading templates")?;
+    register_filters(&mut tera);
+    Ok(tera)
+}
+
+pub(crate) fn register_filters(tera: &mut Tera) {
+    tera.register_filter("snake_case", snake_case_filter);
+    tera.register_filter("py_ident", py_ident_filter);
+    tera.register_filter("toml_escape", toml_escape_filter);
+}
+
+pub(crate) fn is_builtin(name: &str) -> bool {
+    BUILTIN_TEMPLATES.iter().any(|(n, _)| *n == name)
+}
+
+/// The file a pack keeps template `name` in.
+pub(crate) fn file_name(name: &str) -> String {
+    format!("{name}{OVERRIDE_EXTENSION}")
+}
+
+/// Built-in templates with `overrides` applied, by name.
+fn sources(overrides: Option<&Path>) -> Result<Vec<(String, String)>> {
+    let mut sources: Vec<(String, String)> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, body)| (name.to_string(), body.to_string()))
+        .collect();
+    if let Some(dir) = overrides {
+        for (name, body) in read_overrides(dir)? {
+            match sources.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 = body,
+                // Extra templates are allowed so overrides can include partials
+                None => sources.push((name, body)),
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// sha256 of every template, overrides included, by name, and of the
+/// pack's manifest when it has one.
+pub(crate) fn hashes(overrides: Option<&Path>) -> Result<Vec<(String, String)>> {
+    let mut hashes: Vec<(String, String)> = sources(overrides)?
+        .into_iter()
+        .map(|(name, body)| (name, format!("sha256:{:x}", Sha256::digest(&body))))
+        .collect();
+    if let Some(manifest) = read_manifest(overrides)? {
+        hashes.push((
+            pack::MANIFEST.to_string(),
+            format!("sha256:{:x}", Sha256::digest(&manifest)),
+        ));
+    }
+    Ok(hashes)
+}
+
+/// The pack's `pack.yml` as written, if it has one.
+fn read_manifest(overrides: Option<&Path>) -> Result<Option<String>> {
+    let Some(path) = overrides.map(|dir| dir.join(pack::MANIFEST)) else {
+        return Ok(None);
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+/// Copy every template, overrides included, and the pack's manifest and
+/// assets into the project's `.tachi/templates/` and record their hashes,
+/// replacing an earlier copy.
+/// Returns the template names.
+pub fn vendor(project_dir: &Path, overrides: Option<&Path>) -> Result<Vec<String>> {
+    let sources = sources(overrides)?;
+    let dir = project_dir.join(VENDOR_DIR);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("removing {}", dir.display()))?;
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let mut lock = String::new();
+    let mut names = Vec::new();
+    for (name, body) in sources {
+        let file = file_name(&name);
+        let path = dir.join(&file);
+        fs::write(&path, &body).with_context(|| format!("writing {}", path.display()))?;
+        lock.push_str(&format!("{:x}  {file}\n", Sha256::digest(&body)));
+        names.push(name);
+    }
+    // The pack's file layout pins the output as much as its templates do
+    if let Some(manifest) = read_manifest(overrides)? {
+        let path = dir.join(pack::MANIFEST);
+        fs::write(&path, &manifest).with_context(|| format!("writing {}", path.display()))?;
+        lock.push_str(&format!(
+            "{:x}  {}\n",
+            Sha256::digest(&manifest),
+            pack::MANIFEST
+        ));
+    }
+    if let Some(pack_dir) = overrides {
+        for (name, contents) in pack::assets(pack_dir)? {
+            let path = dir.join(&name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("creating {}", parent.display()))?;
+            }
+            fs::write(&path, &contents).with_context(|| format!("writing {}", path.display()))?;
+            lock.push_str(&format!("{:x}  {name}\n", Sha256::digest(&contents)));
+        }
+    }
+    let path = project_dir.join(VENDOR_LOCK);
+    fs::write(&path, lock).with_context(|| format!("writing {}", path.display()))?;
+    Ok(names)
+}
+
+/// How to work on a template pack, written into new ones.
+const PACK_README: &str = "# Template pack
+
+Templates here replace tachi's built-ins of the same name: `cli.py.tera`
+renders cli.py. Templates with other names can be included from them.
+`tachi explain <spec> --template <name>` prints the context a template
+is rendered with.
+
+    tachi gen agent.yml --templates <this directory>
+
+## Layout
+
+`pack.yml` says where files go. Each path is a Tera template rendered
+with the resolved spec, as `tachi explain` prints it:
+
+    files:
+      agent.py:
+        path: src/{{ agent.package }}/agent.py
+      conftest.py:
+        path: tests/conftest.py
+
+A key is a path tachi generates, which moves that file, or a template
+only this pack has, such as `conftest.py.tera`, which renders it with the
+spec. Files the spec does not generate are skipped.
+
+`assets` are copied into the project byte for byte, never rendered: logos,
+example datasets, wheels. A key is the file's path in this pack, and
+`path` and `when` work as for files. tachi.lock records each one's hash.
+
+    assets:
+      static/logo.png:
+        path: src/{{ agent.package }}/static/logo.png
+      data/examples.jsonl: {}
+
+`when` is a Tera expression over the same spec; a file is generated only
+while it holds, so a pack can leave out what tachi would write or add its
+own files for some specs:
+
+    files:
+      Dockerfile:
+        when: '\"server\" in interfaces'
+      tests/test_tools.py:
+        when: agent.tools | length > 0
+
+## Tests
+
+`tachi template test <this directory>` renders tachi's example specs
+(minimal, services, workflow, router, openai, autogen, llamaindex,
+semantic-kernel, go, spring-ai) with the pack and checks
+
+- `tests/<example>/<path>`: golden files the render must match exactly;
+  `--update` rewrites them from the current render
+- `tests/expect.yml`: text each rendered file must or must not contain
+";
+
+/// Starting expectations for a new pack, true of the built-in templates.
+const PACK_EXPECT: &str = r##"# By example spec, then rendered file
+minimal:
+  README.md:
+    contains: ["# minimal"]
+    excludes: []
+"##;
+
+/// How to work on a tool pack, written into new ones.
+const TOOL_PACK_README: &str = "# Tool pack
+
+A template pack whose tools are handed to every Python agent generated
+with it, after the tools its spec lists:
+
+    tachi gen agent.yml --templates <this directory>
+
+`pack.yml` lists each tool class under `tools`. agent.py imports it from
+its module and builds it with no arguments, so a tool reads its settings
+from the environment:
+
+    tools:
+      - module: pack_tools
+        class: WordCountTool
+
+## Layout
+
+- `pack_tools.py.tera`: the tools, smolagents `Tool` subclasses. It is a
+  template rendered with the resolved spec, as `tachi explain` prints it.
+- `test_pack_tools.py.tera`: pytest tests of the tools, generated as
+  tests/test_pack_tools.py so the project's CI workflow runs them.
+
+Both are listed under `files` with `when: lang == \"python\"`, so Go, C#
+and Java projects leave them out. `tachi new template-pack` writes a README
+on the rest of pack.yml.
+
+## Tests
+
+`tachi template test <this directory>` renders tachi's example specs with
+the pack and checks `tests/expect.yml`: text each rendered file must or
+must not contain, such as the tool's import in agent.py.
+";
+
+/// A new tool pack's manifest.
+const TOOL_PACK_MANIFEST: &str = r#"# Python files this pack adds to the project
+files:
+  pack_tools.py:
+    when: lang == "python"
+  test_pack_tools.py:
+    path: tests/test_pack_tools.py
+    when: lang == "python"
+# Classes agent.py imports and hands to the agent after the spec's tools
+tools:
+  - module: pack_tools
+    class: WordCountTool
+"#;
+
+/// A new tool pack's example tool.
+const TOOL_PACK_TOOLS: &str = r#""""
+Tools this pack hands to {{ agent.name }}, listed in the pack's pack.yml.
+"""
+
+from smolagents import Tool
+
+
+class WordCountTool(Tool):
+    name = "word_count"
+    description = "Counts the words in a text."
+    inputs = {"text": {"type": "string", "description": "The text to count the words of"}}
+    output_type = "integer"
+
+    def forward(self, text: str) -> int:
+        return len(text.split())
+"#;
+
+/// Tests of a new tool pack's example tool, rendered into the project.
+const TOOL_PACK_TEST: &str = r#""""Tests of the tools in pack_tools.py."""
+
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+
+from pack_tools import WordCountTool  # noqa: E402
+
+
+def test_word_count():
+    assert WordCountTool().forward("three short words") == 3
+"#;
+
+/// Expectations for a new tool pack, true of its example tool.
+const TOOL_PACK_EXPECT: &str = r##"# By example spec, then rendered file
+minimal:
+  agent.py:
+    contains: ["from pack_tools import WordCountTool", "WordCountTool()"]
+  pack_tools.py:
+    contains: ["class WordCountTool(Tool):"]
+  tests/test_pack_tools.py:
+    contains: ["def test_word_count():"]
+"##;
+
+/// Create a template pack in the empty or missing `dir`: a README, copies
+/// of the built-ins named in `copy` to start from, and a `tests/expect.yml`
+/// that `tachi template test` passes. Returns the files written.
+pub fn new_template_pack(dir: &Path, copy: &[String]) -> Result<Vec<String>> {
+    let mut files = vec![("README.md".to_string(), PACK_README.to_string())];
+    for name in copy {
+        let Some((_, body)) = BUILTIN_TEMPLATES.iter().find(|(n, _)| n == name) else {
+            let names: Vec<_> = BUILTIN_TEMPLATES.iter().map(|(n, _)| *n).collect();
+            bail!(
+                "{name} is not a built-in template; they are {}",
+                names.join(", ")
+            );
+        };
+        files.push((file_name(name), body.to_string()));
+    }
+    files.push(("tests/expect.yml".to_string(), PACK_EXPECT.to_string()));
+    write_pack(dir, files)
+}
+
+/// Create a tool pack in the empty or missing `dir`: a pack.yml handing
+/// an example tool to the agent, the tool and its pytest tests as
+/// templates, and a `tests/expect.yml` that `tachi template test` passes.
+/// Returns the files written.
+pub fn new_tool_pack(dir: &Path) -> Result<Vec<String>> {
+    let files = [
+        ("README.md", TOOL_PACK_README),
+        (pack::MANIFEST, TOOL_PACK_MANIFEST),
+        ("pack_tools.py.tera", TOOL_PACK_TOOLS),
+        ("test_pack_tools.py.tera", TOOL_PACK_TEST),
+        ("tests/expect.yml", TOOL_PACK_EXPECT),
+    ];
+    write_pack(
+        dir,
+        files
+            .into_iter()
+            .map(|(file, body)| (file.to_string(), body.to_string()))
+            .collect(),
+    )
+}
+
+/// Write `files` into the empty or missing `dir`. Returns their paths.
+fn write_pack(dir: &Path, files: Vec<(String, String)>) -> Result<Vec<String>> {
+    let non_empty = dir
+        .read_dir()
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if non_empty {
+        bail!("{} is not empty", dir.display());
+    }
+    for (file, body) in &files {
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        fs::write(&path, body).with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(files.into_iter().map(|(file, _)| file).collect())
+}
+
+/// The templates to render `project_dir` with: `overrides` when given,
+/// else the project's vendored templates once their hashes check out.
+pub(crate) fn pinned(project_dir: &Path, overrides: Option<&Path>) -> Result<Option<PathBuf>> {
+    if let Some(dir) = overrides {
+        return Ok(Some(dir.to_path_buf()));
+    }
+    let dir = project_dir.join(VENDOR_DIR);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let lock_path = project_dir.join(VENDOR_LOCK);
+    let lock = fs::read_to_string(&lock_path)
+        .with_context(|| format!("reading {}", lock_path.display()))?;
+    let mut locked = 0;
+    for line in lock.lines().filter(|l| !l.is_empty()) {
+        let Some((hash, file)) = line.split_once("  ") else {
+            bail!("{} has a malformed line: {line}", lock_path.display());
+        };
+        let path = dir.join(file);
+        let body = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        if format!("{:x}", Sha256::digest(&body)) != hash {
+            bail!(
+                "{} changed since it was vendored; put edits in a --templates directory \
+                 and run `tachi vendor` again",
+                path.display()
+            );
+        }
+        locked += 1;
+    }
+    let manifest = usize::from(dir.join(pack::MANIFEST).is_file());
+    let assets = pack::read(&dir)?.assets.len();
+    if read_overrides(&dir)?.len() + manifest + assets != locked {
+        bail!(
+            "{} has templates missing from {}; run `tachi vendor` again",
+            dir.display(),
+            lock_path.display()
+        );
+    }
+    Ok(Some(dir))
+}
+
+fn read_overrides(dir: &Path) -> Result<Vec<(String, String)>> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("reading template directory {}", dir.display()))?;
+    let mut templates = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("reading template directory {}", dir.display()))?
+            .path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(OVERRIDE_EXTENSION))
+        else {
+            continue;
+        };
+        let body =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        templates.push((name.to_string(), body));
+    }
+    Ok(templates)
+}
+
+// ----------------------
+// Filters
+// ----------------------
+
+/// Escape `s` for use inside a TOML basic (double-quoted) string.
+pub(crate) fn toml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn string_filter(name: &str, value: &Value, f: fn(&str) -> String) -> tera::Result<Value> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg(format!("filter `{name}` expects a string")))?;
+    Ok(Value::String(f(s)))
+}
+
+fn snake_case_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    string_filter("snake_case", value, snake_case)
+}
+
+fn py_ident_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    string_filter("py_ident", value, py_ident)
+}
+
+fn toml_escape_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    string_filter("toml_escape", value, toml_escape)
+}
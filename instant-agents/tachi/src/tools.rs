@@ -0,0 +1,2106 @@
+use crate::error::ValidationError;
+use crate::naming::is_py_ident;
+use crate::spec::Version;
+use serde::de::{self, Deserializer};
+use serde::ser::{self, SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value};
+use std::collections::BTreeMap;
+
+/// A tool handed to the agent. Spec files list a tool by name (`search`) or,
+/// to change its options, as a one-key map (`{sql: {readonly: false}}`).
+/// Tools whose options are all defaults serialize back to the bare name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tool {
+    Search(SearchTool),
+    Webpage(WebpageTool),
+    Math,
+    Sql(SqlTool),
+    Http(HttpTool),
+    Files(FilesTool),
+    Shell(ShellTool),
+    Github(GithubTool),
+    Slack(SlackTool),
+    Email(EmailTool),
+    Documents(DocumentsTool),
+    TextToImage(TextToImageTool),
+    YoutubeTranscript(YoutubeTranscriptTool),
+    Memory(MemoryTool),
+}
+
+/// The name a tool goes by in spec files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolKind {
+    #[serde(alias = "search")]
+    Search,
+    #[serde(alias = "webpage")]
+    Webpage,
+    Math,
+    Sql,
+    Http,
+    Files,
+    Shell,
+    Github,
+    Slack,
+    Email,
+    Documents,
+    TextToImage,
+    YoutubeTranscript,
+    Memory,
+}
+
+impl ToolKind {
+    pub const ALL: &'static [ToolKind] = &[
+        ToolKind::Search,
+        ToolKind::Webpage,
+        ToolKind::Math,
+        ToolKind::Sql,
+        ToolKind::Http,
+        ToolKind::Files,
+        ToolKind::Shell,
+        ToolKind::Github,
+        ToolKind::Slack,
+        ToolKind::Email,
+        ToolKind::Documents,
+        ToolKind::TextToImage,
+        ToolKind::YoutubeTranscript,
+        ToolKind::Memory,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToolKind::Search => "search",
+            ToolKind::Webpage => "webpage",
+            ToolKind::Math => "math",
+            ToolKind::Sql => "sql",
+            ToolKind::Http => "http",
+            ToolKind::Files => "files",
+            ToolKind::Shell => "shell",
+            ToolKind::Github => "github",
+            ToolKind::Slack => "slack",
+            ToolKind::Email => "email",
+            ToolKind::Documents => "documents",
+            ToolKind::TextToImage => "text-to-image",
+            ToolKind::YoutubeTranscript => "youtube-transcript",
+            ToolKind::Memory => "memory",
+        }
+    }
+
+    /// Whether the tool feeds third-party web content back to the model.
+    /// The http tool only reaches allowlisted hosts, so it doesn't count.
+    pub(crate) fn fetches_web_content(self) -> bool {
+        matches!(
+            self,
+            ToolKind::Search
+                | ToolKind::Webpage
+                | ToolKind::Documents
+                | ToolKind::YoutubeTranscript
+        )
+    }
+
+    /// First smolagents release the tool works with.
+    pub(crate) fn since(self) -> Version {
+        match self {
+            ToolKind::Search
+            | ToolKind::Webpage
+            | ToolKind::Sql
+            | ToolKind::Http
+            | ToolKind::Files
+            | ToolKind::Shell
+            | ToolKind::Github
+            | ToolKind::Slack
+            | ToolKind::Email
+            | ToolKind::Documents
+            | ToolKind::TextToImage
+            | ToolKind::YoutubeTranscript
+            | ToolKind::Memory
+            | ToolKind::Math => Version::new(1, 0, 0),
+        }
+    }
+}
+
+impl std::fmt::Display for ToolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl From<ToolKind> for Tool {
+    /// The tool with default options.
+    fn from(kind: ToolKind) -> Tool {
+        match kind {
+            ToolKind::Search => Tool::Search(SearchTool::default()),
+            ToolKind::Webpage => Tool::Webpage(WebpageTool::default()),
+            ToolKind::Math => Tool::Math,
+            ToolKind::Sql => Tool::Sql(SqlTool::default()),
+            ToolKind::Http => Tool::Http(HttpTool::default()),
+            ToolKind::Files => Tool::Files(FilesTool::default()),
+            ToolKind::Shell => Tool::Shell(ShellTool::default()),
+            ToolKind::Github => Tool::Github(GithubTool::default()),
+            ToolKind::Slack => Tool::Slack(SlackTool::default()),
+            ToolKind::Email => Tool::Email(EmailTool::default()),
+            ToolKind::Documents => Tool::Documents(DocumentsTool::default()),
+            ToolKind::TextToImage => Tool::TextToImage(TextToImageTool::default()),
+            ToolKind::YoutubeTranscript => {
+                Tool::YoutubeTranscript(YoutubeTranscriptTool::default())
+            }
+            ToolKind::Memory => Tool::Memory(MemoryTool::default()),
+        }
+    }
+}
+
+/// One `KEY=value` line of `.env.example` with the comment above it.
+/// config.py checks required variables at startup and falls back to the
+/// example value for the others.
+pub(crate) struct EnvVar {
+    pub name: String,
+    pub comment: String,
+    pub example: String,
+    pub required: bool,
+}
+
+/// A function a tool gives the model, as the Python class declares it, for
+/// targets that generate typed stubs instead of porting the tools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Function {
+    pub name: String,
+    pub description: String,
+    pub params: &'static [Param],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Param {
+    pub name: &'static str,
+    /// JSON Schema type: string, integer or boolean
+    pub kind: &'static str,
+    pub description: &'static str,
+    pub optional: bool,
+}
+
+const fn param(name: &'static str, kind: &'static str, description: &'static str) -> Param {
+    Param {
+        name,
+        kind,
+        description,
+        optional: false,
+    }
+}
+
+const fn optional(name: &'static str, kind: &'static str, description: &'static str) -> Param {
+    Param {
+        optional: true,
+        ..param(name, kind, description)
+    }
+}
+
+/// A function's name, description and inputs.
+type FunctionSpec = (&'static str, &'static str, &'static [Param]);
+
+const WEB_SEARCH: FunctionSpec = (
+    "web_search",
+    "Search the web and return the top results.",
+    &[param("query", "string", "The search query")],
+);
+const VISIT_WEBPAGE: FunctionSpec = (
+    "visit_webpage",
+    "Visit a webpage at the given URL and read its content as a markdown string. Use this to browse webpages.",
+    &[param("url", "string", "The URL of the webpage to visit")],
+);
+const REPO: Param = optional(
+    "repo",
+    "string",
+    "owner/name; the default repository when omitted",
+);
+const SANDBOX_PATH: Param = param("path", "string", "Path relative to the sandbox");
+
+/// Name, description and inputs of each Python tool class.
+const FUNCTIONS: &[(&str, FunctionSpec)] = &[
+    ("DuckDuckGoSearchTool", WEB_SEARCH),
+    ("GoogleSearchTool", WEB_SEARCH),
+    ("BraveSearchTool", WEB_SEARCH),
+    ("TavilySearchTool", WEB_SEARCH),
+    ("GoogleCseSearchTool", WEB_SEARCH),
+    ("VisitWebpageTool", VISIT_WEBPAGE),
+    ("FetchWebpageTool", VISIT_WEBPAGE),
+    (
+        "CalculatorTool",
+        (
+            "calculator",
+            "Evaluate an arithmetic expression exactly, e.g. `sqrt(8) * 3/4` or `factorial(20) mod 7`.",
+            &[param("expression", "string", "The expression (^ means power)")],
+        ),
+    ),
+    (
+        "SqlQueryTool",
+        (
+            "sql_query",
+            "Run one SQL statement against the database and return the rows as tab-separated text with a header line. Call sql_schema first to see the tables and columns.",
+            &[param("query", "string", "A single SQL statement")],
+        ),
+    ),
+    (
+        "SqlSchemaTool",
+        (
+            "sql_schema",
+            "List the database's tables with their columns and column types.",
+            &[],
+        ),
+    ),
+    (
+        "HttpRequestTool",
+        (
+            "http_request",
+            "Send an HTTP request to one of the allowed internal APIs and return the status line and response body. Requests to other hosts are refused and redirects are not followed.",
+            &[
+                param("url", "string", "Full http:// or https:// URL"),
+                optional("method", "string", "HTTP method; GET when omitted"),
+                optional(
+                    "body",
+                    "string",
+                    "Request body; sent as JSON when it parses as JSON",
+                ),
+            ],
+        ),
+    ),
+    (
+        "ReadFileTool",
+        (
+            "read_file",
+            "Read a text file from the sandbox directory.",
+            &[SANDBOX_PATH],
+        ),
+    ),
+    (
+        "WriteFileTool",
+        (
+            "write_file",
+            "Write a text file in the sandbox directory, replacing it if it exists.",
+            &[
+                SANDBOX_PATH,
+                param("content", "string", "Full new contents of the file"),
+            ],
+        ),
+    ),
+    (
+        "ListFilesTool",
+        (
+            "list_files",
+            "List a directory in the sandbox; subdirectories end with a slash.",
+            &[optional(
+                "path",
+                "string",
+                "Directory relative to the sandbox; the sandbox itself when omitted",
+            )],
+        ),
+    ),
+    (
+        "ShellCommandTool",
+        (
+            "run_command",
+            "Run a command without a shell (no pipes, redirects or globbing) and return its exit code, stdout and stderr. Only allowlisted programs run.",
+            &[param(
+                "command",
+                "string",
+                "Program and arguments, quoted like a shell",
+            )],
+        ),
+    ),
+    (
+        "GithubSearchIssuesTool",
+        (
+            "github_search_issues",
+            "Search GitHub issues and pull requests with GitHub search syntax (e.g. 'is:open label:bug crash') and list the matches.",
+            &[param("query", "string", "GitHub issue search query")],
+        ),
+    ),
+    (
+        "GithubPullRequestTool",
+        (
+            "github_pull_request",
+            "Read a pull request: title, author, state, description and the diff of each file.",
+            &[param("number", "integer", "Pull request number"), REPO],
+        ),
+    ),
+    (
+        "GithubFileTool",
+        (
+            "github_file",
+            "Fetch a file from a GitHub repository, or list a directory.",
+            &[
+                param("path", "string", "Path inside the repository"),
+                REPO,
+                optional(
+                    "ref",
+                    "string",
+                    "Branch, tag or commit; the default branch when omitted",
+                ),
+            ],
+        ),
+    ),
+    (
+        "SlackPostTool",
+        (
+            "slack_post",
+            "Post a message to an allowed Slack channel.",
+            &[
+                param("channel", "string", "Channel name or ID"),
+                param(
+                    "text",
+                    "string",
+                    "Message text; Slack mrkdwn formatting works",
+                ),
+            ],
+        ),
+    ),
+    (
+        "SlackSearchTool",
+        (
+            "slack_search",
+            "Search recent messages in the allowed Slack channels for some text (case-insensitive) and return the matches, newest first.",
+            &[
+                param("query", "string", "Text to look for"),
+                optional(
+                    "channel",
+                    "string",
+                    "Channel to search; every allowed channel when omitted",
+                ),
+            ],
+        ),
+    ),
+    (
+        "SendEmailTool",
+        (
+            "send_email",
+            "Send a plain-text email.",
+            &[
+                param("to", "string", "Recipient addresses, comma-separated"),
+                param("subject", "string", "Subject line"),
+                param("body", "string", "Message text"),
+                optional("cc", "string", "Cc addresses, comma-separated"),
+            ],
+        ),
+    ),
+    (
+        "ReadEmailTool",
+        (
+            "read_email",
+            "Read recent messages from the inbox, newest first. Messages are not marked as read. Optionally only unread ones or ones containing some text.",
+            &[
+                optional("query", "string", "Text the message must contain"),
+                optional("unread_only", "boolean", "Only unread messages"),
+                optional("limit", "integer", "How many messages, at most 20"),
+            ],
+        ),
+    ),
+    (
+        "ReadDocumentTool",
+        (
+            "read_document",
+            "Extract the text of a PDF or Word (.docx) document.",
+            &[
+                param("source", "string", "Path relative to the sandbox directory"),
+                optional(
+                    "pages",
+                    "string",
+                    "PDF pages to read, like 3 or 2-5; all when omitted",
+                ),
+            ],
+        ),
+    ),
+    (
+        "TextToImageTool",
+        (
+            "text_to_image",
+            "Generate an image from a text description. Returns the path of the saved PNG file.",
+            &[param(
+                "prompt",
+                "string",
+                "What the image should show, in English",
+            )],
+        ),
+    ),
+    (
+        "YoutubeTranscriptTool",
+        (
+            "youtube_transcript",
+            "Get the transcript (captions) of a YouTube video, to summarize it or answer questions about it.",
+            &[
+                param("video", "string", "Video URL or ID"),
+                optional(
+                    "timestamps",
+                    "boolean",
+                    "Prefix each line with its [mm:ss] time",
+                ),
+            ],
+        ),
+    ),
+    (
+        "RememberTool",
+        (
+            "remember",
+            "Save a fact under a short key so it can be recalled in later conversations. Saving under an existing key replaces the old value.",
+            &[
+                param(
+                    "key",
+                    "string",
+                    "Short name for the fact, e.g. 'user_timezone'",
+                ),
+                param("value", "string", "The fact to remember"),
+            ],
+        ),
+    ),
+    (
+        "RecallTool",
+        (
+            "recall",
+            "Look up a fact saved earlier with remember. Without a key, list every saved key with the start of its value.",
+            &[optional(
+                "key",
+                "string",
+                "Key to look up; omit to list what is remembered",
+            )],
+        ),
+    ),
+];
+
+impl Tool {
+    pub fn kind(&self) -> ToolKind {
+        match self {
+            Tool::Search(_) => ToolKind::Search,
+            Tool::Webpage(_) => ToolKind::Webpage,
+            Tool::Math => ToolKind::Math,
+            Tool::Sql(_) => ToolKind::Sql,
+            Tool::Http(_) => ToolKind::Http,
+            Tool::Files(_) => ToolKind::Files,
+            Tool::Shell(_) => ToolKind::Shell,
+            Tool::Github(_) => ToolKind::Github,
+            Tool::Slack(_) => ToolKind::Slack,
+            Tool::Email(_) => ToolKind::Email,
+            Tool::Documents(_) => ToolKind::Documents,
+            Tool::TextToImage(_) => ToolKind::TextToImage,
+            Tool::YoutubeTranscript(_) => ToolKind::YoutubeTranscript,
+            Tool::Memory(_) => ToolKind::Memory,
+        }
+    }
+
+    /// Python classes the tool puts in the agent's tool list.
+    pub(crate) fn py_classes(&self) -> Vec<&'static str> {
+        match self {
+            Tool::Search(search) => vec![search.provider.py_class()],
+            Tool::Math => vec!["CalculatorTool"],
+            Tool::Webpage(webpage) if *webpage == WebpageTool::default() => {
+                vec!["VisitWebpageTool"]
+            }
+            Tool::Webpage(_) => vec!["FetchWebpageTool"],
+            Tool::Sql(_) => vec!["SqlQueryTool", "SqlSchemaTool"],
+            Tool::Http(_) => vec!["HttpRequestTool"],
+            Tool::Files(files) if files.readonly => vec!["ReadFileTool", "ListFilesTool"],
+            Tool::Files(_) => vec!["ReadFileTool", "WriteFileTool", "ListFilesTool"],
+            Tool::Shell(_) => vec!["ShellCommandTool"],
+            Tool::Github(_) => vec![
+                "GithubSearchIssuesTool",
+                "GithubPullRequestTool",
+                "GithubFileTool",
+            ],
+            Tool::Slack(_) => vec!["SlackPostTool", "SlackSearchTool"],
+            Tool::Email(email) if email.imap_host.is_some() => {
+                vec!["SendEmailTool", "ReadEmailTool"]
+            }
+            Tool::Email(_) => vec!["SendEmailTool"],
+            Tool::Documents(_) => vec!["ReadDocumentTool"],
+            Tool::TextToImage(_) => vec!["TextToImageTool"],
+            Tool::YoutubeTranscript(_) => vec!["YoutubeTranscriptTool"],
+            Tool::Memory(_) => vec!["RememberTool", "RecallTool"],
+        }
+    }
+
+    /// Classes `approval` holds for the operator: all of them, except the
+    /// ones that only read next to a class that writes.
+    pub(crate) fn approval_classes(&self) -> Vec<&'static str> {
+        const READ_ONLY: &[&str] = &[
+            "ReadFileTool",
+            "ListFilesTool",
+            "SqlSchemaTool",
+            "SlackSearchTool",
+            "ReadEmailTool",
+            "RecallTool",
+        ];
+        self.py_classes()
+            .into_iter()
+            .filter(|class| !READ_ONLY.contains(class))
+            .collect()
+    }
+
+    /// The tool a Python class name stands for, if tachi has one.
+    pub(crate) fn from_py_class(class: &str) -> Option<Tool> {
+        match class {
+            "WebSearchTool" => Some(ToolKind::Search.into()),
+            _ => ToolKind::ALL
+                .iter()
+                .map(|&kind| Tool::from(kind))
+                .chain(SearchProvider::ALL.iter().map(|&provider| {
+                    Tool::Search(SearchTool {
+                        provider,
+                        ..SearchTool::default()
+                    })
+                }))
+                .find(|tool| tool.py_classes().contains(&class)),
+        }
+    }
+
+    /// Constructor calls for the agent's tool list, one per class.
+    pub(crate) fn py_instances(&self) -> Vec<String> {
+        match self {
+            Tool::Search(search) => {
+                let mut args = Vec::new();
+                if search.provider == SearchProvider::Serpapi {
+                    args.push("provider=\"serpapi\"".to_string());
+                }
+                if let Some(max_results) = search.max_results {
+                    args.push(format!("max_results={max_results}"));
+                }
+                if let Some(region) = &search.region {
+                    args.push(format!("region={}", py_str(region)));
+                }
+                vec![format!(
+                    "{}({})",
+                    search.provider.py_class(),
+                    args.join(", ")
+                )]
+            }
+            Tool::Math => vec!["CalculatorTool()".to_string()],
+            Tool::Webpage(webpage) if *webpage == WebpageTool::default() => {
+                vec!["VisitWebpageTool()".to_string()]
+            }
+            Tool::Webpage(webpage) => vec![format!(
+                "FetchWebpageTool(max_length={}, user_agent={}, render_js={}, domains={})",
+                webpage.max_length,
+                webpage
+                    .user_agent
+                    .as_deref()
+                    .map_or("None".to_string(), py_str),
+                py_bool(webpage.render_js),
+                py_list(&webpage.domains)
+            )],
+            Tool::Sql(sql) => vec![
+                format!(
+                    "SqlQueryTool(url_env={}, readonly={}, max_rows={})",
+                    py_str(&sql.url_env),
+                    py_bool(sql.readonly),
+                    sql.max_rows
+                ),
+                format!("SqlSchemaTool(url_env={})", py_str(&sql.url_env)),
+            ],
+            Tool::Http(http) => {
+                let methods: Vec<&str> = http.methods.iter().map(|m| m.name()).collect();
+                let headers: Vec<String> = http
+                    .headers
+                    .iter()
+                    .map(|(header, env)| format!("{}: {}", py_str(header), py_str(env)))
+                    .collect();
+                vec![format!(
+                    "HttpRequestTool(domains={}, methods={}, headers={{{}}}, timeout={})",
+                    py_list(&http.domains),
+                    py_list(&methods),
+                    headers.join(", "),
+                    http.timeout
+                )]
+            }
+            Tool::Files(files) => {
+                let root = py_str(&files.root);
+                self.py_classes()
+                    .into_iter()
+                    .map(|class| format!("{class}(root={root})"))
+                    .collect()
+            }
+            Tool::Shell(shell) => vec![format!(
+                "ShellCommandTool(allow={}, timeout={})",
+                py_list(&shell.allow),
+                shell.timeout
+            )],
+            Tool::Github(github) => {
+                let repo = github.repo.as_deref().map_or("None".to_string(), py_str);
+                self.py_classes()
+                    .into_iter()
+                    .map(|class| {
+                        format!(
+                            "{class}(token_env={}, repo={repo})",
+                            py_str(&github.token_env)
+                        )
+                    })
+                    .collect()
+            }
+            Tool::Slack(slack) => {
+                let args = format!(
+                    "token_env={}, channels={}",
+                    py_str(&slack.token_env),
+                    py_list(&slack.channels)
+                );
+                self.py_classes()
+                    .into_iter()
+                    .map(|class| format!("{class}({args})"))
+                    .collect()
+            }
+            Tool::Email(email) => {
+                let login = format!(
+                    "user_env={}, password_env={}",
+                    py_str(&email.user_env),
+                    py_str(&email.password_env)
+                );
+                let sender = email.from.as_deref().map_or("None".to_string(), py_str);
+                let mut instances = vec![format!(
+                    "SendEmailTool(host={}, port={}, {login}, sender={sender}, dry_run={})",
+                    py_str(&email.smtp_host),
+                    email.smtp_port,
+                    py_bool(email.dry_run)
+                )];
+                if let Some(imap_host) = &email.imap_host {
+                    instances.push(format!(
+                        "ReadEmailTool(host={}, port={}, {login})",
+                        py_str(imap_host),
+                        email.imap_port
+                    ));
+                }
+                instances
+            }
+            Tool::Documents(documents) => vec![format!(
+                "ReadDocumentTool(root={}, max_size_mb={}, max_chars={}, urls={})",
+                py_str(&documents.root),
+                documents.max_size_mb,
+                documents.max_chars,
+                py_bool(documents.urls)
+            )],
+            Tool::TextToImage(image) => vec![format!(
+                "TextToImageTool(space={}, outputs={})",
+                py_str(&image.space),
+                py_str(&image.outputs)
+            )],
+            Tool::YoutubeTranscript(youtube) => vec![format!(
+                "YoutubeTranscriptTool(languages={})",
+                py_list(&youtube.languages)
+            )],
+            Tool::Memory(memory) => {
+                let optional = |n: Option<u32>| n.map_or("None".to_string(), |n| n.to_string());
+                let options = format!(
+                    "path={}, store={}, retention_days={}, max_entries={}",
+                    py_str(memory.path()),
+                    py_str(memory.store.name()),
+                    optional(memory.retention_days),
+                    optional(memory.max_entries)
+                );
+                self.py_classes()
+                    .into_iter()
+                    .map(|class| format!("{class}({options})"))
+                    .collect()
+            }
+        }
+    }
+
+    /// Directory under the project the tool saves files into, if any.
+    pub(crate) fn output_dir(&self) -> Option<&str> {
+        match self {
+            Tool::TextToImage(image) if !image.outputs.starts_with('/') => {
+                Some(image.outputs.trim_end_matches('/'))
+            }
+            _ => None,
+        }
+    }
+
+    /// The C# plugin `lang: csharp` registers for the tool; `None` for tools
+    /// with no C# port.
+    pub(crate) fn cs_plugin(&self) -> Option<CsPlugin> {
+        let plugin = |name, class, template, args: String| CsPlugin {
+            name,
+            class,
+            template,
+            expr: format!("new {class}({args})"),
+        };
+        match self {
+            Tool::Math => Some(plugin("math", "MathPlugin", "MathPlugin.cs", String::new())),
+            Tool::Webpage(webpage) if !webpage.render_js => Some(plugin(
+                "webpage",
+                "WebpagePlugin",
+                "WebpagePlugin.cs",
+                format!(
+                    "maxLength: {}, userAgent: {}, domains: {}",
+                    webpage.max_length,
+                    webpage
+                        .user_agent
+                        .as_deref()
+                        .map_or("null".to_string(), py_str),
+                    cs_array(&webpage.domains)
+                ),
+            )),
+            Tool::Http(http) => {
+                let methods: Vec<&str> = http.methods.iter().map(|m| m.name()).collect();
+                let headers: Vec<String> = http
+                    .headers
+                    .iter()
+                    .map(|(header, env)| format!("[{}] = {}", py_str(header), py_str(env)))
+                    .collect();
+                Some(plugin(
+                    "http",
+                    "HttpPlugin",
+                    "HttpPlugin.cs",
+                    format!(
+                        "domains: {}, methods: {}, headers: {}, timeout: {}",
+                        cs_array(&http.domains),
+                        cs_array(&methods),
+                        if headers.is_empty() {
+                            "new()".to_string()
+                        } else {
+                            format!("new() {{ {} }}", headers.join(", "))
+                        },
+                        http.timeout
+                    ),
+                ))
+            }
+            Tool::Files(files) => Some(plugin(
+                "files",
+                if files.readonly {
+                    "FilesPlugin"
+                } else {
+                    "WritableFilesPlugin"
+                },
+                "FilesPlugin.cs",
+                format!("root: {}", py_str(&files.root)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Whether the classes ship with smolagents rather than the generated tools.py.
+    pub(crate) fn ships_with_smolagents(&self) -> bool {
+        match self {
+            Tool::Search(search) => search.provider.ships_with_smolagents(),
+            Tool::Webpage(webpage) => *webpage == WebpageTool::default(),
+            _ => false,
+        }
+    }
+
+    /// Packages the tool needs beyond smolagents itself. DuckDuckGo's
+    /// package depends on the smolagents version, so the spec adds it.
+    pub(crate) fn requirements(&self) -> Vec<&'static str> {
+        match self {
+            Tool::Search(search) => match search.provider {
+                SearchProvider::Duckduckgo | SearchProvider::Serpapi => Vec::new(),
+                SearchProvider::Brave | SearchProvider::GoogleCse => vec!["requests"],
+                SearchProvider::Tavily => vec!["tavily-python"],
+            },
+            Tool::Webpage(webpage) if *webpage == WebpageTool::default() => Vec::new(),
+            Tool::Webpage(webpage) if webpage.render_js => {
+                vec!["requests", "markdownify", "playwright"]
+            }
+            Tool::Webpage(_) => vec!["requests", "markdownify"],
+            Tool::Math => vec!["sympy"],
+            Tool::Sql(sql) => {
+                let driver = sql.scheme.as_deref().and_then(|s| sql_dialect(s).ok());
+                std::iter::once("sqlalchemy")
+                    .chain(driver.and_then(|(package, _)| package))
+                    .collect()
+            }
+            Tool::Http(_) => vec!["requests"],
+            Tool::Files(_) | Tool::Shell(_) | Tool::Email(_) | Tool::Memory(_) => Vec::new(),
+            Tool::Github(_) => vec!["PyGithub"],
+            Tool::Slack(_) => vec!["slack_sdk"],
+            Tool::Documents(_) => vec!["pypdf", "python-docx", "requests"],
+            Tool::TextToImage(_) => vec!["gradio_client", "pillow"],
+            Tool::YoutubeTranscript(_) => vec!["youtube-transcript-api>=1.0"],
+        }
+    }
+
+    /// Variables the tool reads, for `.env.example`.
+    pub(crate) fn env_vars(&self) -> Vec<EnvVar> {
+        match self {
+            Tool::Webpage(_)
+            | Tool::Math
+            | Tool::Files(_)
+            | Tool::Shell(_)
+            | Tool::Documents(_)
+            | Tool::TextToImage(_)
+            | Tool::YoutubeTranscript(_)
+            | Tool::Memory(_) => Vec::new(),
+            Tool::Sql(sql) => {
+                let example = match &sql.scheme {
+                    Some(scheme) => match sql_dialect(scheme) {
+                        Ok((_, rest)) => format!("{scheme}:{rest}"),
+                        Err(_) => String::new(),
+                    },
+                    None => String::new(),
+                };
+                vec![EnvVar {
+                    name: sql.url_env.clone(),
+                    comment: "SQLAlchemy database URL for the sql tool".to_string(),
+                    example,
+                    required: true,
+                }]
+            }
+            Tool::Http(http) => http
+                .headers
+                .iter()
+                .map(|(header, env)| EnvVar {
+                    name: env.clone(),
+                    comment: format!("Sent as the {header} header by the http tool"),
+                    example: String::new(),
+                    required: true,
+                })
+                .collect(),
+            Tool::Github(github) => vec![EnvVar {
+                name: github.token_env.clone(),
+                comment: "GitHub token for the github tools; read access is enough".to_string(),
+                example: String::new(),
+                required: true,
+            }],
+            Tool::Slack(slack) => vec![EnvVar {
+                name: slack.token_env.clone(),
+                comment: "Slack bot token (xoxb-...) with chat:write and channels:history"
+                    .to_string(),
+                example: String::new(),
+                required: true,
+            }],
+            Tool::Email(email) => vec![
+                EnvVar {
+                    name: email.user_env.clone(),
+                    comment: "Mail account the email tools log in as".to_string(),
+                    example: String::new(),
+                    required: true,
+                },
+                EnvVar {
+                    name: email.password_env.clone(),
+                    comment: "Password for that account; prefer an app password".to_string(),
+                    example: String::new(),
+                    required: true,
+                },
+            ],
+            Tool::Search(search) => search
+                .provider
+                .env_vars()
+                .iter()
+                .map(|(name, comment)| EnvVar {
+                    name: name.to_string(),
+                    comment: comment.to_string(),
+                    example: String::new(),
+                    required: true,
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            Tool::Math => Ok(()),
+            Tool::Search(search) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                let provider = search.provider.name();
+                if let Some(max_results) = search.max_results {
+                    match search.provider.max_results() {
+                        None => {
+                            return invalid(format!(
+                                "search provider {provider} does not take max-results"
+                            ))
+                        }
+                        Some(most) if !(1..=most).contains(&max_results) => {
+                            return invalid(format!(
+                                "search max-results must be between 1 and {most} for {provider}"
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                }
+                if let Some(region) = &search.region {
+                    if !search.provider.takes_region() {
+                        return invalid(format!(
+                            "search provider {provider} does not take region; use brave or google-cse"
+                        ));
+                    }
+                    let code = |s: &str| s.len() == 2 && s.bytes().all(|b| b.is_ascii_lowercase());
+                    if !region
+                        .split_once('-')
+                        .is_some_and(|(c, l)| code(c) && code(l))
+                    {
+                        return invalid(format!(
+                            "search region {region:?} must be a country and language like `us-en`"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Tool::Webpage(webpage) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if webpage.max_length == 0 {
+                    return invalid("webpage max-length must be at least 1".to_string());
+                }
+                if let Some(agent) = &webpage.user_agent {
+                    if agent.trim().is_empty() || agent.chars().any(char::is_control) {
+                        return invalid(format!(
+                            "webpage user-agent {agent:?} must be a non-empty single line"
+                        ));
+                    }
+                }
+                for domain in &webpage.domains {
+                    if !is_host_name(domain.strip_prefix("*.").unwrap_or(domain)) {
+                        return invalid(format!(
+                            "webpage domain {domain:?} is not a host name or `*.` wildcard"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Tool::Sql(sql) => {
+                validate_env_name(&sql.url_env, "sql url-env")?;
+                if sql.max_rows == 0 {
+                    return Err(ValidationError::new(
+                        "agent.tools",
+                        "sql max-rows must be at least 1",
+                    ));
+                }
+                if let Some(scheme) = &sql.scheme {
+                    sql_dialect(scheme).map_err(|e| ValidationError::new("agent.tools", e))?;
+                }
+                Ok(())
+            }
+            Tool::Http(http) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if http.domains.is_empty() {
+                    return invalid(
+                        "http tool needs at least one entry in domains; it refuses every other host"
+                            .to_string(),
+                    );
+                }
+                for domain in &http.domains {
+                    if !is_host_name(domain.strip_prefix("*.").unwrap_or(domain)) {
+                        return invalid(format!(
+                            "http domain {domain:?} is not a host name or `*.` wildcard"
+                        ));
+                    }
+                }
+                if http.methods.is_empty() {
+                    return invalid("http tool needs at least one method".to_string());
+                }
+                for (header, env) in &http.headers {
+                    let token =
+                        |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+                    if header.is_empty() || !header.chars().all(token) {
+                        return invalid(format!(
+                            "http header {header:?} is not a valid header name"
+                        ));
+                    }
+                    validate_env_name(env, "http header variable")?;
+                }
+                if http.timeout == 0 {
+                    return invalid("http timeout must be at least 1 second".to_string());
+                }
+                Ok(())
+            }
+            Tool::Files(files) => {
+                if files.root.trim().is_empty() {
+                    return Err(ValidationError::new(
+                        "agent.tools",
+                        "files root must not be empty",
+                    ));
+                }
+                Ok(())
+            }
+            Tool::Documents(documents) => {
+                let invalid = |msg: &str| Err(ValidationError::new("agent.tools", msg));
+                if documents.root.trim().is_empty() {
+                    return invalid("documents root must not be empty");
+                }
+                if documents.max_size_mb == 0 {
+                    return invalid("documents max-size-mb must be at least 1");
+                }
+                if documents.max_chars == 0 {
+                    return invalid("documents max-chars must be at least 1");
+                }
+                Ok(())
+            }
+            Tool::TextToImage(image) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if !is_repo_id(&image.space) {
+                    return invalid(format!(
+                        "text-to-image space {:?} must look like owner/name",
+                        image.space
+                    ));
+                }
+                let outputs = image.outputs.trim_end_matches('/');
+                if outputs.is_empty() || outputs.split('/').any(|part| part == "..") {
+                    return invalid(format!(
+                        "text-to-image outputs {:?} must be a directory inside the project or an absolute path",
+                        image.outputs
+                    ));
+                }
+                Ok(())
+            }
+            Tool::YoutubeTranscript(youtube) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if youtube.languages.is_empty() {
+                    return invalid("youtube-transcript needs at least one language".to_string());
+                }
+                for language in &youtube.languages {
+                    let valid = language.split('-').all(|part| {
+                        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric())
+                    });
+                    if !valid {
+                        return invalid(format!(
+                            "youtube-transcript language {language:?} must be a code such as en or pt-BR"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Tool::Memory(memory) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                let path = memory.path();
+                if path.trim().is_empty() || path.ends_with('/') {
+                    return invalid(format!("memory path {path:?} must name a file"));
+                }
+                if !path.starts_with('/') && path.split('/').any(|part| part == "..") {
+                    return invalid(format!(
+                        "memory path {path:?} must stay inside the project or be absolute"
+                    ));
+                }
+                if memory.retention_days == Some(0) {
+                    return invalid("memory retention-days must be at least 1".to_string());
+                }
+                if memory.max_entries == Some(0) {
+                    return invalid("memory max-entries must be at least 1".to_string());
+                }
+                Ok(())
+            }
+            Tool::Shell(shell) => {
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if shell.allow.is_empty() {
+                    return invalid(
+                        "shell tool needs at least one executable in allow".to_string(),
+                    );
+                }
+                for command in &shell.allow {
+                    if command.is_empty() || command.contains(char::is_whitespace) {
+                        return invalid(format!(
+                            "shell allow entry {command:?} must be a single executable name or path"
+                        ));
+                    }
+                }
+                if shell.timeout == 0 {
+                    return invalid("shell timeout must be at least 1 second".to_string());
+                }
+                Ok(())
+            }
+            Tool::Github(github) => {
+                validate_env_name(&github.token_env, "github token-env")?;
+                if let Some(repo) = &github.repo {
+                    if !is_repo_id(repo) {
+                        return Err(ValidationError::new(
+                            "agent.tools",
+                            format!("github repo {repo:?} must look like owner/name"),
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Tool::Slack(slack) => {
+                validate_env_name(&slack.token_env, "slack token-env")?;
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if slack.channels.is_empty() {
+                    return invalid(
+                        "slack tool needs at least one entry in channels; it refuses every other channel"
+                            .to_string(),
+                    );
+                }
+                for channel in &slack.channels {
+                    let name = channel.trim_start_matches('#');
+                    if name.is_empty() || name.contains(char::is_whitespace) {
+                        return invalid(format!(
+                            "slack channel {channel:?} must be a channel name or ID"
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Tool::Email(email) => {
+                validate_env_name(&email.user_env, "email user-env")?;
+                validate_env_name(&email.password_env, "email password-env")?;
+                let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+                if email.smtp_host.is_empty() {
+                    return invalid("email tool needs smtp-host".to_string());
+                }
+                for host in std::iter::once(&email.smtp_host).chain(&email.imap_host) {
+                    if !is_host_name(host) {
+                        return invalid(format!("email host {host:?} is not a host name"));
+                    }
+                }
+                if email.smtp_port == 0 || email.imap_port == 0 {
+                    return invalid("email ports must be between 1 and 65535".to_string());
+                }
+                if let Some(from) = &email.from {
+                    if from.matches('@').count() != 1 || from.contains(char::is_whitespace) {
+                        return invalid(format!("email from {from:?} is not an address"));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Serialize for Tool {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Tool::Search(search) if *search != SearchTool::default() => {
+                with_options(serializer, self.kind(), search)
+            }
+            Tool::Webpage(webpage) if *webpage != WebpageTool::default() => {
+                with_options(serializer, self.kind(), webpage)
+            }
+            Tool::Sql(sql) if *sql != SqlTool::default() => {
+                with_options(serializer, self.kind(), sql)
+            }
+            Tool::Http(http) if *http != HttpTool::default() => {
+                with_options(serializer, self.kind(), http)
+            }
+            Tool::Files(files) if *files != FilesTool::default() => {
+                with_options(serializer, self.kind(), files)
+            }
+            Tool::Shell(shell) if *shell != ShellTool::default() => {
+                with_options(serializer, self.kind(), shell)
+            }
+            Tool::Github(github) if *github != GithubTool::default() => {
+                with_options(serializer, self.kind(), github)
+            }
+            Tool::Slack(slack) if *slack != SlackTool::default() => {
+                with_options(serializer, self.kind(), slack)
+            }
+            Tool::Email(email) if *email != EmailTool::default() => {
+                with_options(serializer, self.kind(), email)
+            }
+            Tool::Documents(documents) if *documents != DocumentsTool::default() => {
+                with_options(serializer, self.kind(), documents)
+            }
+            Tool::TextToImage(image) if *image != TextToImageTool::default() => {
+                with_options(serializer, self.kind(), image)
+            }
+            Tool::YoutubeTranscript(youtube) if *youtube != YoutubeTranscriptTool::default() => {
+                with_options(serializer, self.kind(), youtube)
+            }
+            Tool::Memory(memory) if *memory != MemoryTool::default() => {
+                with_options(serializer, self.kind(), memory)
+            }
+            _ => self.kind().serialize(serializer),
+        }
+    }
+}
+
+fn with_options<S: Serializer, T: Serialize>(
+    serializer: S,
+    kind: ToolKind,
+    options: &T,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(&kind, options)?;
+    map.end()
+}
+
+impl<'de> Deserialize<'de> for Tool {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (kind, options) = match Value::deserialize(deserializer)? {
+            name @ Value::String(_) => (name, Value::Mapping(Mapping::new())),
+            Value::Mapping(map) if map.len() == 1 => {
+                let (kind, options) = map.into_iter().next().expect("one entry");
+                let options = match options {
+                    Value::Null => Value::Mapping(Mapping::new()),
+                    options => options,
+                };
+                (kind, options)
+            }
+            _ => {
+                return Err(de::Error::custom(
+                    "expected a tool name or a one-key map like `sql: {readonly: true}`",
+                ))
+            }
+        };
+        let kind = ToolKind::deserialize(kind).map_err(de::Error::custom)?;
+        let invalid = |err: serde_yaml_ng::Error| de::Error::custom(format!("{kind}: {err}"));
+        Ok(match kind {
+            ToolKind::Search => Tool::Search(SearchTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Webpage => Tool::Webpage(WebpageTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Math => {
+                if options.as_mapping().is_some_and(|m| !m.is_empty()) {
+                    return Err(de::Error::custom(format!("tool {kind} takes no options")));
+                }
+                kind.into()
+            }
+            ToolKind::Sql => Tool::Sql(SqlTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Http => Tool::Http(HttpTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Files => Tool::Files(FilesTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Shell => Tool::Shell(ShellTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Github => Tool::Github(GithubTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Slack => Tool::Slack(SlackTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Email => Tool::Email(EmailTool::deserialize(options).map_err(invalid)?),
+            ToolKind::Documents => {
+                Tool::Documents(DocumentsTool::deserialize(options).map_err(invalid)?)
+            }
+            ToolKind::TextToImage => {
+                Tool::TextToImage(TextToImageTool::deserialize(options).map_err(invalid)?)
+            }
+            ToolKind::YoutubeTranscript => Tool::YoutubeTranscript(
+                YoutubeTranscriptTool::deserialize(options).map_err(invalid)?,
+            ),
+            ToolKind::Memory => Tool::Memory(MemoryTool::deserialize(options).map_err(invalid)?),
+        })
+    }
+}
+
+/// A tool's C# port: a Semantic Kernel plugin class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct CsPlugin {
+    /// Plugin name the model sees before each function name
+    pub name: &'static str,
+    pub class: &'static str,
+    /// Template declaring the class, rendered under `Plugins/`
+    pub template: &'static str,
+    /// C# expression constructing the plugin with the tool's options
+    pub expr: String,
+}
+
+/// One `agent.tools` entry: the tool, plus the name and description the
+/// model sees in place of the tool's own. Both go in the tool's options
+/// (`search: {name: web_search}`) and are applied after instantiation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentTool {
+    pub tool: Tool,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+impl From<Tool> for AgentTool {
+    fn from(tool: Tool) -> AgentTool {
+        AgentTool {
+            tool,
+            name: None,
+            description: None,
+        }
+    }
+}
+
+impl std::ops::Deref for AgentTool {
+    type Target = Tool;
+
+    fn deref(&self) -> &Tool {
+        &self.tool
+    }
+}
+
+impl AgentTool {
+    /// The functions the tool gives the model, under the spec's name and
+    /// description when it sets them.
+    pub(crate) fn functions(&self) -> Vec<Function> {
+        self.py_classes()
+            .into_iter()
+            .map(|class| {
+                let (_, (name, description, params)) = FUNCTIONS
+                    .iter()
+                    .find(|(c, _)| *c == class)
+                    .expect("every class has a function");
+                Function {
+                    name: self.name.as_deref().unwrap_or(name).to_string(),
+                    description: self
+                        .description
+                        .as_deref()
+                        .unwrap_or(description)
+                        .to_string(),
+                    params,
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn validate_overrides(&self) -> Result<(), ValidationError> {
+        if self.name.is_none() && self.description.is_none() {
+            return Ok(());
+        }
+        let invalid = |msg: String| Err(ValidationError::new("agent.tools", msg));
+        let kind = self.kind();
+        let classes = self.py_classes();
+        if classes.len() > 1 {
+            return invalid(format!(
+                "tool {kind} adds {} tools ({}), so it cannot take one name or description",
+                classes.len(),
+                classes.join(", ")
+            ));
+        }
+        if let Some(name) = &self.name {
+            if !is_py_ident(name) || name == "final_answer" {
+                return invalid(format!(
+                    "tool {kind} name {name:?} must be a Python identifier other than final_answer"
+                ));
+            }
+        }
+        if self
+            .description
+            .as_ref()
+            .is_some_and(|d| d.trim().is_empty())
+        {
+            return invalid(format!("tool {kind} description is empty"));
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for AgentTool {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.name.is_none() && self.description.is_none() {
+            return self.tool.serialize(serializer);
+        }
+        let mut options = match serde_yaml_ng::to_value(&self.tool).map_err(ser::Error::custom)? {
+            Value::Mapping(map) => map.into_iter().next().expect("one entry").1,
+            _ => Value::Mapping(Mapping::new()),
+        };
+        let map = options.as_mapping_mut().expect("tool options are a map");
+        if let Some(name) = &self.name {
+            map.insert("name".into(), name.as_str().into());
+        }
+        if let Some(description) = &self.description {
+            map.insert("description".into(), description.as_str().into());
+        }
+        let mut entry = serializer.serialize_map(Some(1))?;
+        entry.serialize_entry(&self.kind(), &options)?;
+        entry.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for AgentTool {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let mut take = |key: &str| -> Result<Option<String>, D::Error> {
+            let Some(Value::Mapping(options)) = value
+                .as_mapping_mut()
+                .filter(|m| m.len() == 1)
+                .and_then(|m| m.values_mut().next())
+            else {
+                return Ok(None);
+            };
+            match options.remove(key) {
+                None => Ok(None),
+                Some(Value::String(s)) => Ok(Some(s)),
+                Some(_) => Err(de::Error::custom(format!("tool {key} must be a string"))),
+            }
+        };
+        let name = take("name")?;
+        let description = take("description")?;
+        Ok(AgentTool {
+            tool: Tool::deserialize(value).map_err(de::Error::custom)?,
+            name,
+            description,
+        })
+    }
+}
+
+/// Options for the `search` tool: which web search backend answers queries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SearchTool {
+    #[serde(default)]
+    pub provider: SearchProvider,
+    /// Results returned per query; 10 when unset
+    #[serde(default)]
+    pub max_results: Option<u32>,
+    /// Country and language of the results, such as `us-en`
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchProvider {
+    /// No API key needed, but rate limited
+    #[default]
+    Duckduckgo,
+    /// Google results through SerpAPI
+    Serpapi,
+    Brave,
+    Tavily,
+    /// Google Programmable Search Engine
+    GoogleCse,
+}
+
+impl SearchProvider {
+    pub const ALL: &'static [SearchProvider] = &[
+        SearchProvider::Duckduckgo,
+        SearchProvider::Serpapi,
+        SearchProvider::Brave,
+        SearchProvider::Tavily,
+        SearchProvider::GoogleCse,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchProvider::Duckduckgo => "duckduckgo",
+            SearchProvider::Serpapi => "serpapi",
+            SearchProvider::Brave => "brave",
+            SearchProvider::Tavily => "tavily",
+            SearchProvider::GoogleCse => "google-cse",
+        }
+    }
+
+    fn py_class(self) -> &'static str {
+        match self {
+            SearchProvider::Duckduckgo => "DuckDuckGoSearchTool",
+            SearchProvider::Serpapi => "GoogleSearchTool",
+            SearchProvider::Brave => "BraveSearchTool",
+            SearchProvider::Tavily => "TavilySearchTool",
+            SearchProvider::GoogleCse => "GoogleCseSearchTool",
+        }
+    }
+
+    fn ships_with_smolagents(self) -> bool {
+        matches!(self, SearchProvider::Duckduckgo | SearchProvider::Serpapi)
+    }
+
+    /// Most results one query can ask the backend for, or `None` when the
+    /// tool has no such setting.
+    fn max_results(self) -> Option<u32> {
+        match self {
+            SearchProvider::Duckduckgo => Some(100),
+            SearchProvider::Serpapi => None,
+            SearchProvider::Brave | SearchProvider::Tavily => Some(20),
+            SearchProvider::GoogleCse => Some(10),
+        }
+    }
+
+    /// Whether the tool can narrow results to a country and language.
+    fn takes_region(self) -> bool {
+        matches!(self, SearchProvider::Brave | SearchProvider::GoogleCse)
+    }
+
+    /// API key variables with their `.env.example` comments.
+    fn env_vars(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            SearchProvider::Duckduckgo => &[],
+            SearchProvider::Serpapi => &[("SERPAPI_API_KEY", "SerpAPI key for web search")],
+            SearchProvider::Brave => &[("BRAVE_API_KEY", "Brave Search API key for web search")],
+            SearchProvider::Tavily => &[("TAVILY_API_KEY", "Tavily API key for web search")],
+            SearchProvider::GoogleCse => &[
+                (
+                    "GOOGLE_API_KEY",
+                    "Google API key with the Custom Search API enabled",
+                ),
+                (
+                    "GOOGLE_CSE_ID",
+                    "Programmable Search Engine ID (cx) to search with",
+                ),
+            ],
+        }
+    }
+}
+
+/// Options for the `webpage` tool. With every option at its default the
+/// agent gets smolagents' own VisitWebpageTool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WebpageTool {
+    /// Characters of page text returned before truncating
+    #[serde(default = "WebpageTool::default_max_length")]
+    pub max_length: usize,
+    /// User-Agent header sent with each request
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Load pages in headless Chromium so scripts run first
+    #[serde(default)]
+    pub render_js: bool,
+    /// Hosts the tool may visit, `*.example.com` for any subdomain; empty allows all
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+impl WebpageTool {
+    fn default_max_length() -> usize {
+        40000
+    }
+}
+
+impl Default for WebpageTool {
+    fn default() -> Self {
+        WebpageTool {
+            max_length: WebpageTool::default_max_length(),
+            user_agent: None,
+            render_js: false,
+            domains: Vec::new(),
+        }
+    }
+}
+
+/// Options for the `sql` tool: a SQLAlchemy query tool plus a schema
+/// listing, connected through a URL read from the environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SqlTool {
+    /// Environment variable holding the database URL
+    #[serde(default = "SqlTool::default_url_env")]
+    pub url_env: String,
+    /// Refuse anything but single queries and never commit
+    #[serde(default = "SqlTool::default_readonly")]
+    pub readonly: bool,
+    /// URL scheme of the database, such as `postgresql` or `mysql+pymysql`;
+    /// picks the driver package. Only SQLite works without one.
+    #[serde(default)]
+    pub scheme: Option<String>,
+    /// Rows returned per query before the result is cut off
+    #[serde(default = "SqlTool::default_max_rows")]
+    pub max_rows: u32,
+}
+
+impl SqlTool {
+    fn default_url_env() -> String {
+        "DATABASE_URL".to_string()
+    }
+    fn default_readonly() -> bool {
+        true
+    }
+    fn default_max_rows() -> u32 {
+        50
+    }
+}
+
+impl Default for SqlTool {
+    fn default() -> Self {
+        SqlTool {
+            url_env: SqlTool::default_url_env(),
+            readonly: SqlTool::default_readonly(),
+            scheme: None,
+            max_rows: SqlTool::default_max_rows(),
+        }
+    }
+}
+
+/// Options for the `http` tool: requests to allowlisted hosts only, for
+/// internal APIs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HttpTool {
+    /// Hosts the tool may call; `*.example.com` allows any subdomain
+    #[serde(default)]
+    pub domains: Vec<String>,
+    /// Methods the tool may send
+    #[serde(default = "HttpTool::default_methods")]
+    pub methods: Vec<HttpMethod>,
+    /// Header name to the environment variable holding its value
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Seconds before a request is abandoned
+    #[serde(default = "HttpTool::default_timeout")]
+    pub timeout: u32,
+}
+
+impl HttpTool {
+    fn default_methods() -> Vec<HttpMethod> {
+        vec![HttpMethod::Get]
+    }
+    fn default_timeout() -> u32 {
+        30
+    }
+}
+
+impl Default for HttpTool {
+    fn default() -> Self {
+        HttpTool {
+            domains: Vec::new(),
+            methods: HttpTool::default_methods(),
+            headers: BTreeMap::new(),
+            timeout: HttpTool::default_timeout(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpMethod {
+    pub const ALL: &'static [HttpMethod] = &[
+        HttpMethod::Get,
+        HttpMethod::Head,
+        HttpMethod::Post,
+        HttpMethod::Put,
+        HttpMethod::Patch,
+        HttpMethod::Delete,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+        }
+    }
+}
+
+/// Options for the `files` tools: read, list and (unless read-only) write
+/// files under one sandbox directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FilesTool {
+    /// Sandbox directory; relative paths start at the project directory
+    #[serde(default = "FilesTool::default_root")]
+    pub root: String,
+    /// Leave out the write tool
+    #[serde(default)]
+    pub readonly: bool,
+}
+
+impl FilesTool {
+    fn default_root() -> String {
+        "workspace".to_string()
+    }
+}
+
+impl Default for FilesTool {
+    fn default() -> Self {
+        FilesTool {
+            root: FilesTool::default_root(),
+            readonly: false,
+        }
+    }
+}
+
+/// Options for the `shell` tool: run allowlisted executables without a shell.
+/// The allowlist limits which programs run, not the arguments they get.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ShellTool {
+    /// Executables the agent may run, by name (looked up on PATH) or exact path
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Seconds before a command is killed
+    #[serde(default = "ShellTool::default_timeout")]
+    pub timeout: u32,
+}
+
+impl ShellTool {
+    fn default_timeout() -> u32 {
+        30
+    }
+}
+
+impl Default for ShellTool {
+    fn default() -> Self {
+        ShellTool {
+            allow: Vec::new(),
+            timeout: ShellTool::default_timeout(),
+        }
+    }
+}
+
+/// Options for the `github` tools: issue search, pull request reading and
+/// file fetching through PyGithub.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct GithubTool {
+    /// Environment variable holding the GitHub token
+    #[serde(default = "GithubTool::default_token_env")]
+    pub token_env: String,
+    /// `owner/name` used when the agent doesn't name a repository
+    #[serde(default)]
+    pub repo: Option<String>,
+}
+
+impl GithubTool {
+    fn default_token_env() -> String {
+        "GITHUB_TOKEN".to_string()
+    }
+}
+
+impl Default for GithubTool {
+    fn default() -> Self {
+        GithubTool {
+            token_env: GithubTool::default_token_env(),
+            repo: None,
+        }
+    }
+}
+
+/// Options for the `slack` tools: post to and search the history of
+/// allowlisted channels with a bot token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SlackTool {
+    /// Environment variable holding the bot token
+    #[serde(default = "SlackTool::default_token_env")]
+    pub token_env: String,
+    /// Channels the agent may use, by name (`#` optional) or ID
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl SlackTool {
+    fn default_token_env() -> String {
+        "SLACK_BOT_TOKEN".to_string()
+    }
+}
+
+impl Default for SlackTool {
+    fn default() -> Self {
+        SlackTool {
+            token_env: SlackTool::default_token_env(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// Options for the `email` tools: send over SMTP and, when `imap-host` is
+/// set, read the inbox over IMAP. With `dry-run` sending only returns the
+/// message it would have sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct EmailTool {
+    #[serde(default)]
+    pub smtp_host: String,
+    /// 465 means implicit TLS; anything else uses STARTTLS
+    #[serde(default = "EmailTool::default_smtp_port")]
+    pub smtp_port: u16,
+    /// Leave unset to send only
+    #[serde(default)]
+    pub imap_host: Option<String>,
+    #[serde(default = "EmailTool::default_imap_port")]
+    pub imap_port: u16,
+    /// Environment variable holding the login name
+    #[serde(default = "EmailTool::default_user_env")]
+    pub user_env: String,
+    /// Environment variable holding the password
+    #[serde(default = "EmailTool::default_password_env")]
+    pub password_env: String,
+    /// Sender address; the login name when unset
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Draft messages instead of sending them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl EmailTool {
+    fn default_smtp_port() -> u16 {
+        587
+    }
+
+    fn default_imap_port() -> u16 {
+        993
+    }
+
+    fn default_user_env() -> String {
+        "EMAIL_USER".to_string()
+    }
+
+    fn default_password_env() -> String {
+        "EMAIL_PASSWORD".to_string()
+    }
+}
+
+impl Default for EmailTool {
+    fn default() -> Self {
+        EmailTool {
+            smtp_host: String::new(),
+            smtp_port: EmailTool::default_smtp_port(),
+            imap_host: None,
+            imap_port: EmailTool::default_imap_port(),
+            user_env: EmailTool::default_user_env(),
+            password_env: EmailTool::default_password_env(),
+            from: None,
+            dry_run: false,
+        }
+    }
+}
+
+/// Options for the `documents` tool: text from PDF and Word files in a
+/// sandbox directory or, unless `urls` is off, at a URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DocumentsTool {
+    /// Sandbox directory, as for the files tools
+    #[serde(default = "FilesTool::default_root")]
+    pub root: String,
+    /// Larger documents are refused before they are parsed
+    #[serde(default = "DocumentsTool::default_max_size_mb")]
+    pub max_size_mb: u32,
+    /// Extracted text is cut off after this many characters
+    #[serde(default = "DocumentsTool::default_max_chars")]
+    pub max_chars: u32,
+    /// Accept http(s) URLs as well as paths
+    #[serde(default = "DocumentsTool::default_urls")]
+    pub urls: bool,
+}
+
+impl DocumentsTool {
+    fn default_max_size_mb() -> u32 {
+        20
+    }
+
+    fn default_max_chars() -> u32 {
+        20000
+    }
+
+    fn default_urls() -> bool {
+        true
+    }
+}
+
+impl Default for DocumentsTool {
+    fn default() -> Self {
+        DocumentsTool {
+            root: FilesTool::default_root(),
+            max_size_mb: DocumentsTool::default_max_size_mb(),
+            max_chars: DocumentsTool::default_max_chars(),
+            urls: DocumentsTool::default_urls(),
+        }
+    }
+}
+
+/// Options for the `text-to-image` tool: a Hugging Face Space that turns a
+/// prompt into an image, saved as PNG under `outputs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TextToImageTool {
+    /// `owner/name` of the Space
+    #[serde(default = "TextToImageTool::default_space")]
+    pub space: String,
+    /// Where images are saved; relative paths start at the project directory
+    #[serde(default = "TextToImageTool::default_outputs")]
+    pub outputs: String,
+}
+
+impl TextToImageTool {
+    fn default_space() -> String {
+        "black-forest-labs/FLUX.1-schnell".to_string()
+    }
+
+    fn default_outputs() -> String {
+        "outputs".to_string()
+    }
+}
+
+impl Default for TextToImageTool {
+    fn default() -> Self {
+        TextToImageTool {
+            space: TextToImageTool::default_space(),
+            outputs: TextToImageTool::default_outputs(),
+        }
+    }
+}
+
+/// Options for the `youtube-transcript` tool: the captions of a YouTube
+/// video, in the first preferred language that has them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct YoutubeTranscriptTool {
+    /// Language codes in order of preference; other languages are
+    /// translated to the first one when YouTube can
+    #[serde(default = "YoutubeTranscriptTool::default_languages")]
+    pub languages: Vec<String>,
+}
+
+impl YoutubeTranscriptTool {
+    fn default_languages() -> Vec<String> {
+        vec!["en".to_string()]
+    }
+}
+
+impl Default for YoutubeTranscriptTool {
+    fn default() -> Self {
+        YoutubeTranscriptTool {
+            languages: YoutubeTranscriptTool::default_languages(),
+        }
+    }
+}
+
+/// Options for the `memory` tool: facts the agent saves with `remember` and
+/// looks up with `recall`, kept in a file so they outlive the conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MemoryTool {
+    #[serde(default)]
+    pub store: MemoryStore,
+    /// File holding the memories, relative to the project; defaults to
+    /// memory.json or memory.db
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Days a memory is kept after it was last written; unset keeps it forever
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Most memories kept; the least recently written are dropped first
+    #[serde(default)]
+    pub max_entries: Option<u32>,
+}
+
+impl MemoryTool {
+    fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(match self.store {
+            MemoryStore::Json => "memory.json",
+            MemoryStore::Sqlite => "memory.db",
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryStore {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl MemoryStore {
+    pub const ALL: &'static [MemoryStore] = &[MemoryStore::Json, MemoryStore::Sqlite];
+
+    fn name(self) -> &'static str {
+        match self {
+            MemoryStore::Json => "json",
+            MemoryStore::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// SQLAlchemy dialects tachi knows, with the package behind the dialect's
+/// default driver and the rest of an example URL.
+const SQL_DIALECTS: &[(&str, Option<&str>, &str)] = &[
+    ("sqlite", None, "///app.db"),
+    (
+        "postgresql",
+        Some("psycopg2-binary"),
+        "//user:password@localhost:5432/app",
+    ),
+    (
+        "mysql",
+        Some("mysqlclient"),
+        "//user:password@localhost:3306/app",
+    ),
+    (
+        "mariadb",
+        Some("mysqlclient"),
+        "//user:password@localhost:3306/app",
+    ),
+    ("mssql", Some("pyodbc"), "//user:password@app-dsn"),
+    (
+        "oracle",
+        Some("cx_Oracle"),
+        "//user:password@localhost:1521/?service_name=app",
+    ),
+    ("duckdb", Some("duckdb-engine"), "///app.duckdb"),
+];
+
+/// Packages for the `dialect+driver` half of a scheme.
+const SQL_DRIVERS: &[(&str, &str)] = &[
+    ("psycopg", "psycopg[binary]"),
+    ("psycopg2", "psycopg2-binary"),
+    ("pg8000", "pg8000"),
+    ("pymysql", "PyMySQL"),
+    ("mysqldb", "mysqlclient"),
+    ("mysqlconnector", "mysql-connector-python"),
+    ("mariadbconnector", "mariadb"),
+    ("pyodbc", "pyodbc"),
+    ("pymssql", "pymssql"),
+    ("oracledb", "oracledb"),
+    ("cx_oracle", "cx_Oracle"),
+    ("pysqlite", ""),
+];
+
+/// Driver package (if any) and example URL tail for a SQLAlchemy scheme.
+fn sql_dialect(scheme: &str) -> Result<(Option<&'static str>, &'static str), String> {
+    let (dialect, driver) = match scheme.split_once('+') {
+        Some((dialect, driver)) => (dialect, Some(driver)),
+        None => (scheme, None),
+    };
+    let dialect = if dialect == "postgres" {
+        "postgresql"
+    } else {
+        dialect
+    };
+    let Some(&(_, default_package, rest)) = SQL_DIALECTS.iter().find(|(d, ..)| *d == dialect)
+    else {
+        let known: Vec<&str> = SQL_DIALECTS.iter().map(|(d, ..)| *d).collect();
+        return Err(format!(
+            "sql scheme {scheme:?} has an unknown dialect; expected one of {}",
+            known.join(", ")
+        ));
+    };
+    let package = match driver {
+        None => default_package,
+        Some(driver) => match SQL_DRIVERS.iter().find(|(d, _)| *d == driver) {
+            Some((_, "")) => None,
+            Some((_, package)) => Some(*package),
+            None => {
+                return Err(format!(
+                    "sql scheme {scheme:?} has an unknown driver {driver:?}"
+                ))
+            }
+        },
+    };
+    Ok((package, rest))
+}
+
+fn validate_env_name(name: &str, what: &str) -> Result<(), ValidationError> {
+    let valid = name.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new(
+            "agent.tools",
+            format!("{what} {name:?} is not an environment variable name (A-Z, 0-9, _)"),
+        ))
+    }
+}
+
+/// A Python string literal. JSON escapes are valid Python escapes.
+/// Dot-separated labels of letters, digits and hyphens.
+fn is_host_name(host: &str) -> bool {
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// `owner/name`, as GitHub repositories and Hugging Face Spaces are named.
+pub(crate) fn is_repo_id(id: &str) -> bool {
+    id.split_once('/').is_some_and(|(owner, name)| {
+        let part = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        };
+        part(owner) && part(name)
+    })
+}
+
+fn py_str(s: &str) -> String {
+    serde_json::to_string(s).expect("strings serialize")
+}
+
+fn py_list<S: AsRef<str>>(items: &[S]) -> String {
+    let items: Vec<String> = items.iter().map(|s| py_str(s.as_ref())).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// A C# collection expression of strings, which reads like the Python
+/// list; JSON string escapes are valid in C# too.
+fn cs_array<S: AsRef<str>>(items: &[S]) -> String {
+    py_list(items)
+}
+
+fn py_bool(b: bool) -> &'static str {
+    if b {
+        "True"
+    } else {
+        "False"
+    }
+}
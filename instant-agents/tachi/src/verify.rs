@@ -0,0 +1,42 @@
+use crate::error::InvalidPython;
+use crate::generate::WriteStatus;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// Interpreter `verify` compiles with
+const PYTHON: &str = "python3";
+
+/// Byte-compile the Python files `generate` wrote into `project_dir` with
+/// `python3 -m compileall`, failing with the compiler's report if one does
+/// not parse. Files the user kept or that hold conflict markers are theirs
+/// to fix, so they are skipped. The bytecode goes to a cache outside the
+/// project.
+pub fn verify(project_dir: &Path, written: &[(String, WriteStatus)]) -> Result<()> {
+    let files: Vec<&str> = written
+        .iter()
+        .filter(|(name, status)| {
+            name.ends_with(".py") && !matches!(status, WriteStatus::Kept | WriteStatus::Conflicted)
+        })
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+    let output = Command::new(PYTHON)
+        .args(["-m", "compileall", "-q", "-f"])
+        .args(&files)
+        .env(
+            "PYTHONPYCACHEPREFIX",
+            std::env::temp_dir().join("tachi-verify"),
+        )
+        .current_dir(project_dir)
+        .output()
+        .with_context(|| format!("running {PYTHON}, which --verify compiles the output with"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let mut report = String::from_utf8_lossy(&output.stdout).into_owned();
+    report.push_str(&String::from_utf8_lossy(&output.stderr));
+    Err(InvalidPython(report.trim_end().to_string()).into())
+}
@@ -0,0 +1,255 @@
+use crate::error::MergeKeyError;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_yaml_ng::Value;
+use std::collections::HashMap;
+
+/// One `---`-separated document of a spec file.
+pub(crate) struct Document<'a> {
+    /// 0-based line of the file the document starts on
+    pub first_line: usize,
+    pub text: &'a str,
+}
+
+/// `source` cut before each `---` that follows content, so every document
+/// starts with its own separator.
+pub(crate) fn documents(source: &str) -> Vec<Document<'_>> {
+    let mut documents = Vec::new();
+    let (mut start, mut first_line) = (0, 0);
+    let mut has_content = false;
+    let mut offset = 0;
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_end();
+        if has_content && (trimmed == "---" || trimmed.starts_with("--- ")) {
+            documents.push(Document {
+                first_line,
+                text: &source[start..offset],
+            });
+            (start, first_line) = (offset, index);
+            has_content = false;
+        } else if !is_blank(line) && trimmed != "---" {
+            has_content = true;
+        }
+        offset += line.len();
+    }
+    documents.push(Document {
+        first_line,
+        text: &source[start..],
+    });
+    documents
+}
+
+/// Deserialize `document` with its `<<` merge keys applied. Aliases resolve
+/// to their anchor, so serde's location for a bad value in a shared fragment
+/// is the anchor's; lines count from the top of the file.
+pub(crate) fn from_document<T: DeserializeOwned>(document: &Document) -> Result<T> {
+    // Blank lines in front keep serde's line numbers those of the file
+    let text = "\n".repeat(document.first_line) + document.text;
+    let mut value: Value = match serde_yaml_ng::from_str(&text) {
+        Ok(value) => value,
+        Err(err) if document.first_line > 0 && err.to_string().starts_with("unknown anchor") => {
+            return Err(anyhow::Error::new(err)
+                .context("anchors are only visible in the `---` document that defines them"));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if !has_merge_keys(&value) {
+        return Ok(serde_yaml_ng::from_str(&text)?);
+    }
+    // A merged fragment can carry merge keys of its own
+    while has_merge_keys(&value) {
+        value.apply_merge()?;
+    }
+    let merged = serde_yaml_ng::to_string(&value)?;
+    serde_yaml_ng::from_str(&merged).map_err(|err| {
+        // serde's location is in `merged`; the key path leads back to the file
+        let mut message = err.to_string();
+        if let Some(at) = err.location() {
+            let suffix = format!(" at line {} column {}", at.line(), at.column());
+            if let Some(bare) = message.strip_suffix(&suffix) {
+                message = bare.to_string();
+            }
+        }
+        let line = message
+            .split_once(": ")
+            .filter(|(path, _)| !path.contains(char::is_whitespace))
+            .and_then(|(path, _)| key_line(document.text, path))
+            .map(|line| document.first_line + line + 1);
+        MergeKeyError { message, line }.into()
+    })
+}
+
+fn has_merge_keys(value: &Value) -> bool {
+    match value {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(key, value)| key.as_str() == Some("<<") || has_merge_keys(value)),
+        Value::Sequence(items) => items.iter().any(has_merge_keys),
+        Value::Tagged(tagged) => has_merge_keys(&tagged.value),
+        _ => false,
+    }
+}
+
+/// 0-based line setting dotted `key` in block-style YAML. A list index such
+/// as `tools[1]` names the list, unless a key inside the item follows
+/// (`pipeline.steps[1].agent`). A key copied by a `*alias` or a
+/// `<<: *anchor` merge is found where the anchor defines it. Flow mappings
+/// (`agent: {name: x}`) aren't followed.
+pub(crate) fn key_line(text: &str, key: &str) -> Option<usize> {
+    let lines: Vec<&str> = text.lines().collect();
+    let anchors = anchors(&lines);
+    let mut found = None;
+    let mut start = 0;
+    let mut min_indent = 0;
+    let mut end = lines.len();
+    let mut segments = key.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let (name, index) = match segment.split_once('[') {
+            Some((name, index)) => (name, index.trim_end_matches(']').parse().ok()),
+            None => (segment, None),
+        };
+        let i = find_key(&lines[..end], &anchors, start, min_indent, name, 0)?;
+        // Keys below an alias are the anchor's
+        let block = alias_of(lines[i])
+            .and_then(|name| anchors.get(name))
+            .copied();
+        let parent = block.unwrap_or(i);
+        found = Some(i);
+        start = parent + 1;
+        min_indent = indent_of(lines[parent]) + 1;
+        if let Some(index) = index.filter(|_| segments.peek().is_some()) {
+            let (item, item_end) = list_item(&lines[..end], start, index)?;
+            start = item;
+            min_indent = indent_of(lines[item]);
+            end = item_end;
+        }
+    }
+    let i = found?;
+    Some(
+        alias_of(lines[i])
+            .and_then(|name| anchors.get(name))
+            .copied()
+            .unwrap_or(i),
+    )
+}
+
+/// First line of the `index`th `- ` item of the block list from `start`, and
+/// the line after the item.
+fn list_item(lines: &[&str], start: usize, index: usize) -> Option<(usize, usize)> {
+    let mut column = None;
+    let mut item = None;
+    let mut seen = 0;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if is_blank(line) {
+            continue;
+        }
+        let spaces = line.len() - line.trim_start().len();
+        let column = *column.get_or_insert(spaces);
+        let dash = spaces == column && line.trim_start().starts_with('-');
+        if spaces < column || (spaces == column && !dash) || (dash && item.is_some()) {
+            return item.map(|item| (item, i));
+        }
+        if dash {
+            if seen == index {
+                item = Some(i);
+            }
+            seen += 1;
+        }
+    }
+    item.map(|item| (item, lines.len()))
+}
+
+/// First line from `start` with `key`, before the block's indentation ends;
+/// failing that, the first in a mapping the block merges in.
+fn find_key(
+    lines: &[&str],
+    anchors: &HashMap<&str, usize>,
+    start: usize,
+    min_indent: usize,
+    key: &str,
+    depth: usize,
+) -> Option<usize> {
+    let mut level = None;
+    let mut merged = Vec::new();
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if is_blank(line) {
+            continue;
+        }
+        let indent = indent_of(line);
+        if indent < min_indent {
+            break;
+        }
+        if key_of(line) == Some(key) {
+            return Some(i);
+        }
+        if key_of(line) == Some("<<") && *level.get_or_insert(indent) == indent {
+            merged.extend(value_of(line).split(',').filter_map(|alias| {
+                alias
+                    .trim()
+                    .trim_matches(['[', ']'])
+                    .trim()
+                    .strip_prefix('*')
+            }));
+        }
+        level.get_or_insert(indent);
+    }
+    // Anchors come before their aliases, but a wrong guess could still cycle
+    if depth > 8 {
+        return None;
+    }
+    merged.iter().find_map(|name| {
+        let anchor = *anchors.get(name)?;
+        let indent = indent_of(lines[anchor]) + 1;
+        find_key(lines, anchors, anchor + 1, indent, key, depth + 1)
+    })
+}
+
+/// Line of each `&anchor`, as `key: &anchor` or `- &anchor`.
+fn anchors<'a>(lines: &[&'a str]) -> HashMap<&'a str, usize> {
+    let mut anchors = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(anchor) = value_of(line).strip_prefix('&') else {
+            continue;
+        };
+        let name = anchor.split_whitespace().next().unwrap_or_default();
+        anchors.insert(name, i);
+    }
+    anchors
+}
+
+fn alias_of(line: &str) -> Option<&str> {
+    value_of(line).strip_prefix('*')?.split_whitespace().next()
+}
+
+/// What follows the key, or the dash of a list item.
+fn value_of(line: &str) -> &str {
+    let content = line.trim_start().trim_start_matches('-').trim_start();
+    match key_of(line) {
+        Some(_) => content
+            .split_once(':')
+            .map_or("", |(_, value)| value.trim()),
+        None => content,
+    }
+}
+
+/// Indentation of the line's content; a `- ` list marker counts as indent so
+/// items sit under their key.
+pub(crate) fn indent_of(line: &str) -> usize {
+    let spaces = line.len() - line.trim_start().len();
+    match line.trim_start().strip_prefix('-') {
+        Some(rest) => spaces + 1 + (rest.len() - rest.trim_start().len()),
+        None => spaces,
+    }
+}
+
+pub(crate) fn key_of(line: &str) -> Option<&str> {
+    let content = line.trim_start().trim_start_matches('-').trim_start();
+    let (key, _) = content.split_once(':')?;
+    let key = key.trim().trim_matches(['"', '\'']);
+    (!key.is_empty() && !key.contains(char::is_whitespace)).then_some(key)
+}
+
+pub(crate) fn is_blank(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
@@ -0,0 +1,17 @@
+import config
+from smolagents import InferenceClientModel, CodeAgent, DuckDuckGoSearchTool, VisitWebpageTool
+
+def create_agent():
+    """Create and return a configured smolagents instance."""
+    config.check()
+
+    model = InferenceClientModel(
+        model_id="Qwen/Qwen2.5-Coder-32B-Instruct",
+        token=config.get("HUGGINGFACEHUB_API_TOKEN")
+    )
+
+    agent = CodeAgent(
+        tools=[DuckDuckGoSearchTool(), VisitWebpageTool()],
+        model=model,
+    )
+    return agent
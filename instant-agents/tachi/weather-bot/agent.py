@@ -1,19 +1,13 @@
-import os
-from dotenv import load_dotenv
+import config
 from smolagents import InferenceClientModel, CodeAgent, DuckDuckGoSearchTool, VisitWebpageTool
 
-# Load environment variables from .env file
-load_dotenv()
-
 def create_agent():
     """Create and return a configured smolagents instance."""
-    hf_token = os.getenv("HUGGINGFACEHUB_API_TOKEN")
-    if not hf_token:
-        raise ValueError("HUGGINGFACEHUB_API_TOKEN environment variable not set")
+    config.check()
 
     model = InferenceClientModel(
         model_id="Qwen/Qwen2.5-Coder-32B-Instruct",
-        token=hf_token
+        token=config.get("HUGGINGFACEHUB_API_TOKEN")
     )
 
     agent = CodeAgent(
@@ -2,10 +2,114 @@
 """
 Interactive CLI for the smolagent.
 Provides a classic chat interface with input/output loop.
+Pass a question as arguments, or pipe it on stdin, to get a single answer.
 """
 
 import sys
 from agent import create_agent
+from smolagents.monitoring import LogLevel
+
+MODEL_ID = "Qwen/Qwen2.5-Coder-32B-Instruct"
+
+
+def read_prompt():
+    """Return a one-shot prompt from the arguments or piped stdin, or None."""
+    if len(sys.argv) > 1:
+        return " ".join(sys.argv[1:]).strip()
+    if not sys.stdin.isatty():
+        return sys.stdin.read().strip()
+    return None
+
+
+def answer_once(prompt):
+    """Answer a single prompt with plain output and exit."""
+    if not prompt:
+        print("Error: empty prompt", file=sys.stderr)
+        sys.exit(2)
+    try:
+        agent = create_agent()
+        # Only the answer goes to stdout so it can be piped
+        agent.logger.level = LogLevel.OFF
+        print(agent.run(prompt))
+    except Exception as e:
+        print(f"Error: {e}", file=sys.stderr)
+        sys.exit(1)
+
+
+# Slash commands for the chat loop, keyed by name without the leading "/".
+COMMANDS = {}
+
+
+def command(name, help):
+    """Register a slash command handler taking (session, arg)."""
+
+    def register(fn):
+        COMMANDS[name] = (fn, help)
+        return fn
+
+    return register
+
+
+class Session:
+    """Chat state shared between the loop and slash commands."""
+
+    def __init__(self, agent):
+        self.agent = agent
+        self.history = []
+
+    def record(self, role, text):
+        """Add a message to the conversation history."""
+        self.history.append((role, text))
+
+
+@command("help", "Show available commands")
+def cmd_help(session, arg):
+    for name, (_, text) in COMMANDS.items():
+        print(f"  /{name:<8} {text}")
+
+
+@command("tools", "List the agent's tools")
+def cmd_tools(session, arg):
+    for name, tool in session.agent.tools.items():
+        print(f"  {name}: {tool.description}")
+
+
+@command("model", "Show the model in use")
+def cmd_model(session, arg):
+    print(f"  {MODEL_ID}")
+
+
+@command("reset", "Forget the conversation so far")
+def cmd_reset(session, arg):
+    session.agent.memory.reset()
+    session.history.clear()
+    print("  Conversation reset.")
+
+
+@command("save", "Save the transcript: /save <file>")
+def cmd_save(session, arg):
+    if not arg:
+        print("  Usage: /save <file>")
+        return
+    try:
+        with open(arg, "w", encoding="utf-8") as f:
+            for role, text in session.history:
+                f.write(f"{role}: {text}\n\n")
+    except OSError as e:
+        print(f"  Could not save transcript: {e}")
+        return
+    print(f"  Saved {len(session.history)} messages to {arg}")
+
+
+def dispatch(session, line):
+    """Run a slash command line such as "/save notes.txt"."""
+    name, _, arg = line[1:].partition(" ")
+    entry = COMMANDS.get(name.lower())
+    if entry is None:
+        print(f"  Unknown command /{name}. Type /help for a list.")
+        return
+    handler, _ = entry
+    handler(session, arg.strip())
 
 
 def print_banner():
@@ -14,18 +118,25 @@ def print_banner():
     print("HuggingFace Smolagent CLI")
     print("=" * 60)
     print("Type your requests and press Enter.")
+    print("Type /help for commands.")
     print("Type 'exit', 'quit', or press Ctrl+C to exit.")
     print("=" * 60)
     print()
 
 
 def main():
-    """Run the interactive CLI loop."""
+    """Answer a one-shot prompt if given, otherwise run the interactive CLI loop."""
+    prompt = read_prompt()
+    if prompt is not None:
+        answer_once(prompt)
+        return
+
     try:
         # Initialize agent once at startup
         print("Initializing agent...")
         agent = create_agent()
         print("Agent ready!\n")
+        session = Session(agent)
 
         print_banner()
 
@@ -44,10 +155,17 @@ def main():
                 if not user_input:
                     continue
 
-                # Run agent with user input
+                # Handle slash commands
+                if user_input.startswith("/"):
+                    dispatch(session, user_input)
+                    continue
+
+                # Run agent with user input, keeping the conversation
+                session.record("You", user_input)
                 print("\nAgent: ", end="", flush=True)
-                result = agent.run(user_input)
+                result = agent.run(user_input, reset=False)
                 print(result)
+                session.record("Agent", str(result))
 
             except KeyboardInterrupt:
                 print("\n\nGoodbye!")
@@ -0,0 +1,41 @@
+"""
+Every environment variable the agent reads, declared in one place.
+
+check() runs when the agent is created and names each required variable
+that is unset, so a missing key fails at startup instead of at the first
+tool call that needs it. get() returns a variable or its default.
+.env.example lists the same variables; regenerate rather than editing both.
+"""
+
+import os
+from pathlib import Path
+
+from dotenv import load_dotenv
+
+# Load environment variables from .env file
+load_dotenv()
+# A bundled agent runs from an archive, so it looks in the working directory too
+load_dotenv(Path.cwd() / ".env")
+
+# name: (required, default, description)
+VARIABLES = {
+    "HUGGINGFACEHUB_API_TOKEN": (True, None, "Hugging Face token for the model"),
+}
+
+
+class ConfigError(ValueError):
+    """Required environment variables are unset."""
+
+
+def check():
+    """Raise ConfigError naming every required variable that is unset or empty."""
+    missing = [name for name, (required, _, _) in VARIABLES.items() if required and not os.getenv(name)]
+    if missing:
+        details = "".join(f"\n  {name}: {VARIABLES[name][2]}" for name in missing)
+        raise ConfigError(f"environment variables not set (copy .env.example to .env and fill them in):{details}")
+
+
+def get(name):
+    """The variable's value, or its default when it is unset or empty."""
+    _, default, _ = VARIABLES[name]
+    return os.getenv(name) or default